@@ -1,4 +1,6 @@
+#[allow(unused_imports)]
 use chrono::Utc;
+#[allow(unused_imports)]
 use kboard::{app::App, models::{_entities::{replies, threads}, boards}};
 #[allow(unused_imports)]
 use loco_rs::{cli::playground, prelude::*};
@@ -9,6 +9,7 @@ import { ThreadViewer } from "./ThreadViewer";
 import { AboutMe } from "./AboutMe";
 import { SearchResults } from "./SearchResults";
 import { Profile } from "./Profile";
+import { UserPage } from "./UserPage";
 
 export type AuthState =
   // User is not authenticated
@@ -28,7 +29,9 @@ export type UIState =
   // Search results page is displayed
   | { type: "search"; searchQuery: string }
   // User profile form is displayed
-  | { type: "profile" };
+  | { type: "profile" }
+  // Another user's public profile is displayed
+  | { type: "user"; userId: number };
 
 interface KBoardProps {
   uiState: UIState;
@@ -66,6 +69,8 @@ export function KBoard({ uiState }: KBoardProps) {
         return <SearchResults searchQuery={uiState.searchQuery} />;
       case "profile":
         return <Profile authState={authState} onAuthenticationError={logout} onUsernameUpdate={handleUsernameUpdate} />;
+      case "user":
+        return <UserPage userId={uiState.userId} />;
     }
   })();
 
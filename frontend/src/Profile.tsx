@@ -1,5 +1,5 @@
 import React, { useState, useEffect } from "react";
-import { Box, Button, Fieldset, Heading, HStack, Input, Stack, Text } from "@chakra-ui/react";
+import { Box, Button, Fieldset, Heading, HStack, Input, NativeSelect, Stack, Text } from "@chakra-ui/react";
 import { Field } from "./components/ui/field";
 import { API_ROOT } from "./config";
 import { AuthState } from "./KBoard";
@@ -19,6 +19,76 @@ interface UserInfo {
   email: string;
 }
 
+type DigestFrequency = "immediate" | "daily" | "weekly" | "off";
+
+/// How often new replies in followed threads are emailed
+function DigestFrequencySetting({ onAuthenticationError }: { onAuthenticationError: () => void }) {
+  const [frequency, setFrequency] = useState<DigestFrequency | null>(null);
+
+  useEffect(() => {
+    fetch(`${API_ROOT}/users/me/digest_frequency`, {
+      method: "GET",
+      credentials: 'include',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+    })
+      .then((response) => {
+        if (response.status === 401) {
+          onAuthenticationError();
+          return null;
+        }
+        return response.ok ? response.json() : null;
+      })
+      .then((data) => data && setFrequency(data.frequency))
+      .catch((err) => console.error('Error loading digest frequency:', err));
+  }, [onAuthenticationError]);
+
+  const updateFrequency = async (newFrequency: DigestFrequency) => {
+    const previous = frequency;
+    setFrequency(newFrequency);
+    try {
+      const response = await fetch(`${API_ROOT}/users/me/digest_frequency`, {
+        method: "PUT",
+        credentials: 'include',
+        headers: {
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ frequency: newFrequency }),
+      });
+      if (response.status === 401) {
+        onAuthenticationError();
+      } else if (!response.ok) {
+        setFrequency(previous);
+      }
+    } catch (err) {
+      setFrequency(previous);
+    }
+  };
+
+  if (frequency === null) {
+    return null;
+  }
+
+  return (
+    <Field.Root>
+      <Field.Label>Email me new replies in threads I follow</Field.Label>
+      <NativeSelect.Root width="38ch">
+        <NativeSelect.Field
+          value={frequency}
+          onChange={(e) => updateFrequency(e.target.value as DigestFrequency)}
+        >
+          <option value="immediate">As they're posted</option>
+          <option value="daily">In a daily digest</option>
+          <option value="weekly">In a weekly digest</option>
+          <option value="off">Never</option>
+        </NativeSelect.Field>
+        <NativeSelect.Indicator />
+      </NativeSelect.Root>
+    </Field.Root>
+  );
+}
+
 type ProfileState =
   | { type: "loading" }
   | { type: "error"; message: string }
@@ -207,6 +277,10 @@ export function Profile({ authState, onAuthenticationError, onUsernameUpdate }:
               >
                 {profileState.type === "submitting" ? "Updating..." : "Update Profile"}
               </Button>
+
+              <Box mt={6}>
+                <DigestFrequencySetting onAuthenticationError={onAuthenticationError} />
+              </Box>
             </Stack>
 
             <Box ml={10} alignSelf="stretch" bg="gray.100" display="flex" alignItems="flex-start" p={2} flex="1">
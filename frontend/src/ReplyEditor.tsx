@@ -1,7 +1,7 @@
 import { Box, Text, VStack, Input, Textarea, Button, HStack, Image } from "@chakra-ui/react";
 import { useState, useEffect } from "react";
 import { API_ROOT } from "./config";
-import { ImageUploadState, validateFile, getPresignedUrl, uploadToS3 } from "./imageUpload";
+import { ImageUploadState, validateFile, getPresignedUrl, uploadImage } from "./imageUpload";
 
 type ReplyMode =
   | { type: "new_thread"; boardId: number }
@@ -26,6 +26,17 @@ interface Reply {
   created_at: string;
 }
 
+/// The reason the server gave for rejecting a post, such as an image that
+/// failed validation
+async function errorDescription(response: Response, fallback: string): Promise<string> {
+  try {
+    const data = await response.json();
+    return data.description || fallback;
+  } catch {
+    return fallback;
+  }
+}
+
 export function ReplyEditor({ replyMode, onPostSucceeded, onAuthenticationError, onCancel }: ReplyEditorProps) {
   const [title, setTitle] = useState("");
   const [replyText, setReplyText] = useState(replyMode.type === "edit_reply" ? replyMode.currentText : "");
@@ -56,7 +67,7 @@ export function ReplyEditor({ replyMode, onPostSucceeded, onAuthenticationError,
 
     try {
       const { upload_url, pending_key } = await getPresignedUrl(file.type);
-      await uploadToS3(upload_url, file);
+      await uploadImage(upload_url, file);
       const previewUrl = URL.createObjectURL(file);
       setImageState({ type: "uploaded", file, pendingKey: pending_key, previewUrl });
     } catch (err) {
@@ -143,6 +154,8 @@ export function ReplyEditor({ replyMode, onPostSucceeded, onAuthenticationError,
         } else if (response.status === 401 || response.status === 403) {
           onAuthenticationError();
           setError("Authentication error. Please log in or register to create threads.");
+        } else if (response.status === 400) {
+          setError(await errorDescription(response, "Failed to create thread"));
         } else {
           setError("Failed to create thread");
         }
@@ -169,6 +182,8 @@ export function ReplyEditor({ replyMode, onPostSucceeded, onAuthenticationError,
         } else if (response.status === 401 || response.status === 403) {
           onAuthenticationError();
           setError("Authentication error. Please log in or register to reply to threads.");
+        } else if (response.status === 400) {
+          setError(await errorDescription(response, "Failed to create reply"));
         } else {
           setError("Failed to create reply");
         }
@@ -193,6 +208,8 @@ export function ReplyEditor({ replyMode, onPostSucceeded, onAuthenticationError,
         } else if (response.status === 401 || response.status === 403) {
           onAuthenticationError();
           setError("Authentication error. Please log in or register to edit replies.");
+        } else if (response.status === 400) {
+          setError(await errorDescription(response, "Failed to update reply"));
         } else {
           setError("Failed to update reply");
         }
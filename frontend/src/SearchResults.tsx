@@ -4,17 +4,36 @@ import { useState, useEffect } from "react";
 import { Link } from "react-router-dom";
 import { API_ROOT } from "./config";
 
-interface SearchResult {
+interface ReplyHit {
+  type: "reply";
   reply_id: number;
-  reply_body: string;
+  /// HTML-escaped excerpt of the reply with the matched terms wrapped in <mark>
+  snippet: string;
+  /// The same excerpt rendered from Markdown, also with <mark> around matches
+  snippet_html: string;
   thread_id: number;
   thread_title: string;
   board_id: number;
   board_title: string;
   poster_id: number;
   poster_name: string;
+  created_at: string;
 }
 
+interface ThreadHit {
+  type: "thread";
+  thread_id: number;
+  /// HTML-escaped thread title with the matched terms wrapped in <mark>
+  title: string;
+  board_id: number;
+  board_title: string;
+  poster_id: number;
+  poster_name: string;
+  num_replies: number;
+}
+
+type SearchResult = ReplyHit | ThreadHit;
+
 interface SearchResponse {
   results: SearchResult[];
   total_found: number;
@@ -45,7 +64,7 @@ export function SearchResults({ searchQuery, onSearchError }: SearchResultsProps
       setError(null);
       
       const offset = (page - 1) * pageSize;
-      const url = `${API_ROOT}/search/replies?q=${encodeURIComponent(query)}&limit=${pageSize}&offset=${offset}`;
+      const url = `${API_ROOT}/search?q=${encodeURIComponent(query)}&limit=${pageSize}&offset=${offset}`;
       
       const response = await fetch(url, {
         method: "GET",
@@ -110,7 +129,7 @@ export function SearchResults({ searchQuery, onSearchError }: SearchResultsProps
   if (!searchQuery.trim()) {
     return (
       <Box p={4}>
-        <Text color="gray.500">Enter a search term to find replies.</Text>
+        <Text color="gray.500">Enter a search term to find threads and replies.</Text>
       </Box>
     );
   }
@@ -132,39 +151,70 @@ export function SearchResults({ searchQuery, onSearchError }: SearchResultsProps
       <VStack align="stretch" gap={4} mb={6}>
         {results.map((result) => (
           <Box
-            key={result.reply_id}
+            key={result.type === "reply" ? `reply-${result.reply_id}` : `thread-${result.thread_id}`}
             p={4}
             borderWidth={1}
             borderRadius="md"
             bg="white"
             boxShadow="sm"
           >
-            <VStack align="stretch" gap={2}>
-              {/* Board and Thread navigation */}
-              <HStack fontSize="sm" color="gray.600">
-                <Link to={`/boards/${result.board_id}/threads`}>
-                  <Text color="blue.600" _hover={{ color: "blue.800" }}>
-                    {result.board_title}
-                  </Text>
+            {result.type === "thread" ? (
+              <VStack align="stretch" gap={2}>
+                <HStack fontSize="sm" color="gray.600">
+                  <Link to={`/boards/${result.board_id}/threads`}>
+                    <Text color="blue.600" _hover={{ color: "blue.800" }}>
+                      {result.board_title}
+                    </Text>
+                  </Link>
+                </HStack>
+
+                {/* Thread title, escaped by the server */}
+                <Link to={`/boards/${result.board_id}/threads/${result.thread_id}`}>
+                  <Text
+                    fontSize="md"
+                    fontWeight="bold"
+                    color="blue.600"
+                    css={{ "& mark": { background: "yellow.200", paddingX: "1px" } }}
+                    dangerouslySetInnerHTML={{ __html: result.title }}
+                  />
                 </Link>
-                <Text>›</Text>
-                <Link to={`/boards/${result.board_id}/threads/${result.thread_id}#reply-${result.reply_id}`}>
-                  <Text color="blue.600" _hover={{ color: "blue.800" }}>
-                    {result.thread_title}
-                  </Text>
-                </Link>
-              </HStack>
-
-              {/* Reply content */}
-              <Text fontSize="md" lineHeight="1.5">
-                {result.reply_body}
-              </Text>
-
-              {/* Author */}
-              <Text fontSize="sm" color="gray.500">
-                By {result.poster_name}
-              </Text>
-            </VStack>
+
+                <Text fontSize="sm" color="gray.500">
+                  Thread by {result.poster_name} · {result.num_replies} repl{result.num_replies !== 1 ? 'ies' : 'y'}
+                </Text>
+              </VStack>
+            ) : (
+              <VStack align="stretch" gap={2}>
+                {/* Board and Thread navigation */}
+                <HStack fontSize="sm" color="gray.600">
+                  <Link to={`/boards/${result.board_id}/threads`}>
+                    <Text color="blue.600" _hover={{ color: "blue.800" }}>
+                      {result.board_title}
+                    </Text>
+                  </Link>
+                  <Text>›</Text>
+                  <Link to={`/boards/${result.board_id}/threads/${result.thread_id}#reply-${result.reply_id}`}>
+                    <Text color="blue.600" _hover={{ color: "blue.800" }}>
+                      {result.thread_title}
+                    </Text>
+                  </Link>
+                </HStack>
+
+                {/* Matching part of the reply, rendered and sanitized by the server */}
+                <Text
+                  as="div"
+                  fontSize="md"
+                  lineHeight="1.5"
+                  css={{ "& mark": { background: "yellow.200", paddingX: "1px" } }}
+                  dangerouslySetInnerHTML={{ __html: result.snippet_html }}
+                />
+
+                {/* Author */}
+                <Text fontSize="sm" color="gray.500">
+                  By {result.poster_name}
+                </Text>
+              </VStack>
+            )}
           </Box>
         ))}
       </VStack>
@@ -9,7 +9,10 @@ import Cookies from "js-cookie";
 
 interface Reply {
   id: number;
+  /// The text as written, for editing
   body: string;
+  /// The text rendered from Markdown and sanitized by the server
+  body_html: string;
   /// [reply_id, reply_body_text, reply_status] - the ID, text, and status of the reply being responded to
   reply_to: [number, string, number] | null;
   thread_id: number;
@@ -18,9 +21,48 @@ interface Reply {
   poster_is_banned: boolean;
   updated_at: string;
   reply_status: number;
-  image_url: string | null;
+  image: ReplyImage | null;
+  edited: boolean;
+  edit_count: number;
 }
 
+interface ImageVariant {
+  url: string;
+  width: number;
+  height: number;
+}
+
+interface ReplyImage {
+  /// The full-size image
+  url: string;
+  width: number | null;
+  height: number | null;
+  /// Missing until the server has made them, or when the image is already small
+  thumbnail: ImageVariant | null;
+  medium: ImageVariant | null;
+}
+
+/// The copy of an image to show in a thread, with its dimensions if known so
+/// the page doesn't shift as images load
+function displayedImage(image: ReplyImage): { url: string; width: number | null; height: number | null } {
+  return image.medium ?? image;
+}
+
+/// Styles for reply bodies rendered by the server. Spoilers stay blacked
+/// out until they're hovered or focused.
+const replyBodyStyles = {
+  "& p, & ul, & ol, & blockquote, & pre": { marginBottom: "0.5em" },
+  "& ul, & ol": { paddingLeft: "1.5em" },
+  "& blockquote": { borderLeft: "3px solid", borderColor: "gray.300", paddingLeft: "0.75em", color: "gray.600" },
+  "& code": { fontFamily: "mono", background: "gray.100", paddingX: "2px", borderRadius: "sm" },
+  "& pre": { background: "gray.100", padding: "0.5em", borderRadius: "md", overflowX: "auto" },
+  "& pre code": { background: "none", padding: 0 },
+  "& a": { color: "blue.600", textDecoration: "underline" },
+  "& a.mention": { fontWeight: "semibold", textDecoration: "none" },
+  "& .spoiler": { background: "gray.800", color: "transparent", borderRadius: "sm", cursor: "pointer" },
+  "& .spoiler:hover, & .spoiler:focus": { background: "gray.100", color: "inherit" },
+};
+
 type ReplyEditorState =
   | { type: "closed" }
   | { type: "new_reply"; replyToId?: number }
@@ -44,6 +86,8 @@ export function ThreadViewer({ boardId, threadId, authState, onAuthenticationErr
   const [boardName, setBoardName] = useState<string>("");
   const [replyEditorState, setReplyEditorState] = useState<ReplyEditorState>({ type: "closed" });
   const [initialTargetHandled, setInitialTargetHandled] = useState(false);
+  // Whether the current user follows this thread; null until known
+  const [subscribed, setSubscribed] = useState<boolean | null>(null);
 
   const isCurrentUserModerator = Cookies.get("is_moderator") === "true";
 
@@ -165,6 +209,45 @@ export function ThreadViewer({ boardId, threadId, authState, onAuthenticationErr
     fetchReplies();
   }, [boardId, threadId, pageSize, pageNumber]);
 
+  // Posting a reply follows the thread, so this is checked again whenever
+  // the reply count changes
+  useEffect(() => {
+    if (authState.type === "logged_out") {
+      setSubscribed(null);
+      return;
+    }
+    fetch(`${API_ROOT}/boards/${boardId}/threads/${threadId}/subscription`, {
+      method: "GET",
+      credentials: 'include',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+    })
+      .then((response) => (response.ok ? response.json() : null))
+      .then((data) => setSubscribed(data ? data.subscribed : null))
+      .catch((err) => console.error('Error loading subscription:', err));
+  }, [boardId, threadId, authState, totalReplies]);
+
+  const toggleSubscription = async () => {
+    try {
+      const response = await fetch(`${API_ROOT}/boards/${boardId}/threads/${threadId}/subscription`, {
+        method: subscribed ? "DELETE" : "POST",
+        credentials: 'include',
+        headers: {
+          'Content-Type': 'application/json',
+        },
+      });
+      if (response.ok) {
+        const data = await response.json();
+        setSubscribed(data.subscribed);
+      } else if (response.status === 401) {
+        onAuthenticationError();
+      }
+    } catch (err) {
+      console.error('Error updating subscription:', err);
+    }
+  };
+
   // Reset target handling when route changes
   useEffect(() => {
     setInitialTargetHandled(false);
@@ -296,6 +379,13 @@ export function ThreadViewer({ boardId, threadId, authState, onAuthenticationErr
         </Button>
       )}
 
+      {/* Follow Button */}
+      {boardName && threadTitle && subscribed !== null && (
+        <Button onClick={toggleSubscription} variant="outline" size="md" mb={4} ml={2}>
+          {subscribed ? "Unfollow" : "Follow"}
+        </Button>
+      )}
+
       {/* Reply Editor */}
       {replyEditorState.type === "new_reply" && (
         <Box mb={6}>
@@ -398,6 +488,11 @@ export function ThreadViewer({ boardId, threadId, authState, onAuthenticationErr
                   <HStack justifyContent="space-between" alignItems="center">
                     <Text fontSize="sm" color="gray.600">
                       Last updated {formatDate(reply.updated_at)}
+                      {reply.edited && (
+                        <Text as="span" fontStyle="italic">
+                          {" "}(edited {reply.edit_count === 1 ? "once" : `${reply.edit_count} times`})
+                        </Text>
+                      )}
                     </Text>
                     <HStack gap={2}>
                       {authState.type === "logged_in" && reply.reply_status === LIVE && (
@@ -515,14 +610,29 @@ export function ThreadViewer({ boardId, threadId, authState, onAuthenticationErr
                       />
                     ) : (
                       <>
-                        <Text whiteSpace="pre-wrap" color={reply.reply_status === DELETED ? "gray.500" : "inherit"} fontStyle={reply.reply_status === DELETED ? "italic" : "normal"}>
-                          {reply.reply_status === DELETED ? "This reply has been deleted" : reply.body}
-                        </Text>
-                        {reply.reply_status !== DELETED && reply.image_url && (
-                          <a href={reply.image_url} target="_blank" rel="noopener noreferrer">
-                            <Image src={reply.image_url} maxH="400px" mt={2} borderRadius="md" />
-                          </a>
+                        {reply.reply_status === DELETED ? (
+                          <Text color="gray.500" fontStyle="italic">
+                            This reply has been deleted
+                          </Text>
+                        ) : (
+                          <Box css={replyBodyStyles} dangerouslySetInnerHTML={{ __html: reply.body_html }} />
                         )}
+                        {reply.reply_status !== DELETED && reply.image && (() => {
+                          const shown = displayedImage(reply.image);
+                          return (
+                            <a href={reply.image.url} target="_blank" rel="noopener noreferrer">
+                              <Image
+                                src={shown.url}
+                                loading="lazy"
+                                aspectRatio={shown.width && shown.height ? `${shown.width} / ${shown.height}` : undefined}
+                                maxH="400px"
+                                maxW="100%"
+                                mt={2}
+                                borderRadius="md"
+                              />
+                            </a>
+                          );
+                        })()}
                       </>
                     )}
                   </Box>
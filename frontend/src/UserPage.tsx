@@ -0,0 +1,55 @@
+import { Box, Text, Spinner } from "@chakra-ui/react";
+import { useState, useEffect } from "react";
+import { API_ROOT } from "./config";
+
+interface UserProfile {
+  id: number;
+  name: string;
+  is_banned: boolean;
+  created_at: string;
+}
+
+interface UserPageProps {
+  userId: number;
+}
+
+/// The public profile of a user, linked from mentions in replies
+export function UserPage({ userId }: UserPageProps) {
+  const [profile, setProfile] = useState<UserProfile | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    fetch(`${API_ROOT}/users/${userId}`)
+      .then((response) => {
+        if (!response.ok) {
+          throw new Error(response.status === 404 ? "No such user" : "Failed to load user");
+        }
+        return response.json();
+      })
+      .then((data: UserProfile) => setProfile(data))
+      .catch((err: Error) => setError(err.message));
+  }, [userId]);
+
+  if (error) {
+    return <Text color="red.500">{error}</Text>;
+  }
+  if (!profile) {
+    return <Spinner />;
+  }
+
+  return (
+    <Box p={4}>
+      <Text fontSize="2xl" fontWeight="bold">
+        {profile.name}
+      </Text>
+      {profile.is_banned && (
+        <Text fontSize="sm" color="red.600" fontWeight="semibold">
+          banned
+        </Text>
+      )}
+      <Text fontSize="sm" color="gray.600">
+        Member since {new Date(profile.created_at).toLocaleDateString()}
+      </Text>
+    </Box>
+  );
+}
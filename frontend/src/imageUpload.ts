@@ -34,7 +34,7 @@ export async function getPresignedUrl(
   return response.json();
 }
 
-export async function uploadToS3(uploadUrl: string, file: File): Promise<void> {
+export async function uploadImage(uploadUrl: string, file: File): Promise<void> {
   const response = await fetch(uploadUrl, {
     method: "PUT",
     headers: { "Content-Type": file.type },
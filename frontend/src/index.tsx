@@ -44,6 +44,17 @@ function SearchPage() {
   return <KBoard uiState={uiState} />;
 }
 
+function UserProfilePage() {
+  const { userId } = useParams<{ userId: string }>();
+
+  const uiState: UIState = {
+    type: "user",
+    userId: Number(userId) || 0
+  };
+
+  return <KBoard uiState={uiState} />;
+}
+
 const root = document.getElementById("root");
 
 if (!root) {
@@ -61,6 +72,7 @@ ReactDOM.createRoot(root).render(
           <Route path="/about" element={<KBoard uiState={{ type: "about_me" }} />} />
           <Route path="/search/:query" element={<SearchPage />} />
           <Route path="/profile" element={<KBoard uiState={{ type: "profile" }} />} />
+          <Route path="/users/:userId" element={<UserProfilePage />} />
           <Route path="*" element={<KBoard uiState={{ type: "board" }} />} />
         </Routes>
       </ChakraProvider>
@@ -16,6 +16,23 @@ mod m20250802_230712_add_is_deleted_to_threads;
 mod m20250804_210454_reply_statuses;
 mod m20250806_000954_add_reply_status_ref_to_replies;
 mod m20260216_000000_add_image_key_to_replies;
+mod m20261018_000001_add_requires_verified_email_to_boards;
+mod m20261018_000002_bans;
+mod m20261018_000003_mod_actions;
+mod m20261018_000004_reports;
+mod m20261018_000005_add_thread_states_to_threads;
+mod m20261018_000006_add_position_and_is_archived_to_boards;
+mod m20261018_000007_board_categories;
+mod m20261018_000008_board_access;
+mod m20261018_000009_roles_and_board_moderators;
+mod m20261018_000010_search_index;
+mod m20261018_000011_add_image_dimensions_to_replies;
+mod m20261018_000012_reply_revisions;
+mod m20261018_000013_add_body_html_to_replies;
+mod m20261018_000014_mentions;
+mod m20261018_000015_notifications;
+mod m20261018_000016_thread_subscriptions;
+mod m20261018_000017_backfill_email_verified_at;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -36,6 +53,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20250804_210454_reply_statuses::Migration),
             Box::new(m20250806_000954_add_reply_status_ref_to_replies::Migration),
             Box::new(m20260216_000000_add_image_key_to_replies::Migration),
+            Box::new(m20261018_000001_add_requires_verified_email_to_boards::Migration),
+            Box::new(m20261018_000002_bans::Migration),
+            Box::new(m20261018_000003_mod_actions::Migration),
+            Box::new(m20261018_000004_reports::Migration),
+            Box::new(m20261018_000005_add_thread_states_to_threads::Migration),
+            Box::new(m20261018_000006_add_position_and_is_archived_to_boards::Migration),
+            Box::new(m20261018_000007_board_categories::Migration),
+            Box::new(m20261018_000008_board_access::Migration),
+            Box::new(m20261018_000009_roles_and_board_moderators::Migration),
+            Box::new(m20261018_000010_search_index::Migration),
+            Box::new(m20261018_000011_add_image_dimensions_to_replies::Migration),
+            Box::new(m20261018_000012_reply_revisions::Migration),
+            Box::new(m20261018_000013_add_body_html_to_replies::Migration),
+            Box::new(m20261018_000014_mentions::Migration),
+            Box::new(m20261018_000015_notifications::Migration),
+            Box::new(m20261018_000016_thread_subscriptions::Migration),
+            Box::new(m20261018_000017_backfill_email_verified_at::Migration),
             // inject-above (do not remove this comment)
         ]
     }
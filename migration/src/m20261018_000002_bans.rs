@@ -0,0 +1,36 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "bans",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ("reason", ColType::Text),
+            ("expires_at", ColType::TimestampWithTimeZoneNull),
+            ("lifted_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[
+            ("user", ""),
+            ("user", "moderator_id"),
+            ("user?", "lifted_by"),
+            ]
+        ).await?;
+
+        m.get_connection()
+            .execute_unprepared("CREATE INDEX bans_user_index ON bans (user_id);")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "bans").await
+    }
+}
@@ -0,0 +1,62 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        // Built by hand rather than with create_table, whose references
+        // cascade: deleting a moderator must not wipe out what they did
+        m.create_table(
+            table_auto_tz(Alias::new("mod_actions"))
+                .col(pk_auto(Alias::new("id")))
+                .col(string(Alias::new("action")))
+                .col(string(Alias::new("target_type")))
+                .col(integer(Alias::new("target_id")))
+                .col(text_null(Alias::new("reason")))
+                .col(json_null(Alias::new("previous_state")))
+                .col(integer(Alias::new("actor_id")))
+                .col(integer_null(Alias::new("target_user_id")))
+                .col(integer_null(Alias::new("board_id")))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk-users-actor_id-to-mod_actions")
+                        .from(Alias::new("mod_actions"), Alias::new("actor_id"))
+                        .to(Alias::new("users"), Alias::new("id"))
+                        .on_delete(ForeignKeyAction::Restrict)
+                        .on_update(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk-users-target_user_id-to-mod_actions")
+                        .from(Alias::new("mod_actions"), Alias::new("target_user_id"))
+                        .to(Alias::new("users"), Alias::new("id"))
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .on_update(ForeignKeyAction::NoAction),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk-boards-board_id-to-mod_actions")
+                        .from(Alias::new("mod_actions"), Alias::new("board_id"))
+                        .to(Alias::new("boards"), Alias::new("id"))
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .on_update(ForeignKeyAction::NoAction),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+        let db = m.get_connection();
+        db.execute_unprepared("CREATE INDEX mod_actions_actor_index ON mod_actions (actor_id);").await?;
+        db.execute_unprepared("CREATE INDEX mod_actions_target_user_index ON mod_actions (target_user_id);").await?;
+        db.execute_unprepared("CREATE INDEX mod_actions_board_index ON mod_actions (board_id);").await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "mod_actions").await
+    }
+}
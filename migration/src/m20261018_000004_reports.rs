@@ -0,0 +1,37 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "reports",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ("category", ColType::String),
+            ("reason", ColType::Text),
+            ("resolution", ColType::StringNull),
+            ("resolved_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[
+            ("reply", ""),
+            ("user", "reporter_id"),
+            ("user?", "resolved_by"),
+            ]
+        ).await?;
+
+        m.get_connection()
+            .execute_unprepared("CREATE INDEX reports_reply_index ON reports (reply_id);")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "reports").await
+    }
+}
@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "threads", "is_locked", ColType::BooleanWithDefault(false)).await?;
+        add_column(m, "threads", "is_pinned", ColType::BooleanWithDefault(false)).await?;
+        add_column(m, "threads", "is_archived", ColType::BooleanWithDefault(false)).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "threads", "is_archived").await?;
+        remove_column(m, "threads", "is_pinned").await?;
+        remove_column(m, "threads", "is_locked").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "boards", "position", ColType::IntegerWithDefault(0)).await?;
+        add_column(m, "boards", "is_archived", ColType::BooleanWithDefault(false)).await?;
+
+        // Keep the existing boards in the order they were created
+        m.get_connection()
+            .execute_unprepared("UPDATE boards SET position = id;")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "boards", "is_archived").await?;
+        remove_column(m, "boards", "position").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "board_categories",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ("title", ColType::String),
+            ("description", ColType::Text),
+            ("position", ColType::IntegerWithDefault(0)),
+            ],
+            &[]
+        ).await?;
+
+        // add_reference can't add nullable columns, and every existing board
+        // starts out uncategorized and top-level
+        let db = m.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE boards ADD COLUMN category_id INTEGER REFERENCES board_categories(id) ON DELETE SET NULL;"
+        ).await?;
+        db.execute_unprepared(
+            "ALTER TABLE boards ADD COLUMN parent_id INTEGER REFERENCES boards(id) ON DELETE SET NULL;"
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        let db = m.get_connection();
+        if db.get_database_backend() == sea_orm::DbBackend::Sqlite {
+            // SQLite can't drop columns that reference another table, so boards
+            // is rebuilt without them. Foreign keys are off while it's swapped
+            // out so the tables referencing boards are left alone, and it's all
+            // one call so the pragma applies to the connection doing the work.
+            db.execute_unprepared(
+                r#"
+                PRAGMA foreign_keys = OFF;
+                CREATE TABLE "boards_new" (
+                    "created_at" timestamp_with_timezone_text NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    "updated_at" timestamp_with_timezone_text NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+                    "title" varchar NOT NULL,
+                    "description" text NOT NULL,
+                    "num_threads" integer NOT NULL DEFAULT 0,
+                    "requires_verified_email" boolean NOT NULL DEFAULT FALSE,
+                    "position" integer NOT NULL DEFAULT 0,
+                    "is_archived" boolean NOT NULL DEFAULT FALSE
+                );
+                INSERT INTO "boards_new"
+                    SELECT created_at, updated_at, id, title, description, num_threads,
+                        requires_verified_email, position, is_archived
+                    FROM "boards";
+                DROP TABLE "boards";
+                ALTER TABLE "boards_new" RENAME TO "boards";
+                PRAGMA foreign_keys = ON;
+                "#,
+            )
+            .await?;
+        } else {
+            remove_column(m, "boards", "parent_id").await?;
+            remove_column(m, "boards", "category_id").await?;
+        }
+        drop_table(m, "board_categories").await
+    }
+}
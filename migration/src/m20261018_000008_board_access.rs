@@ -0,0 +1,38 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "boards", "visibility", ColType::StringWithDefault("public".to_string())).await?;
+        add_column(m, "boards", "posting_policy", ColType::StringWithDefault("everyone".to_string())).await?;
+
+        create_table(m, "board_members",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ],
+            &[
+            ("board", ""),
+            ("user", ""),
+            ]
+        ).await?;
+
+        m.get_connection()
+            .execute_unprepared("CREATE UNIQUE INDEX board_members_board_user_index ON board_members (board_id, user_id);")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "board_members").await?;
+        remove_column(m, "boards", "posting_policy").await?;
+        remove_column(m, "boards", "visibility").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "users", "role", ColType::StringWithDefault("member".to_string())).await?;
+        m.get_connection()
+            .execute_unprepared("UPDATE users SET role = 'global_moderator' WHERE is_moderator = TRUE;")
+            .await?;
+        remove_column(m, "users", "is_moderator").await?;
+
+        create_table(m, "board_moderators",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ],
+            &[
+            ("board", ""),
+            ("user", ""),
+            ]
+        ).await?;
+
+        m.get_connection()
+            .execute_unprepared("CREATE UNIQUE INDEX board_moderators_board_user_index ON board_moderators (board_id, user_id);")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "board_moderators").await?;
+
+        add_column(m, "users", "is_moderator", ColType::BooleanWithDefault(false)).await?;
+        m.get_connection()
+            .execute_unprepared("UPDATE users SET is_moderator = TRUE WHERE role IN ('admin', 'global_moderator');")
+            .await?;
+        remove_column(m, "users", "role").await?;
+        Ok(())
+    }
+}
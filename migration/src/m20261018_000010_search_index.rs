@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DbBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// SQLite keeps the searchable text in FTS5 tables whose rowids are the
+/// reply and thread ids. Triggers keep them in step with the source tables;
+/// only live replies are indexed.
+const SQLITE_UP: &str = r"
+CREATE VIRTUAL TABLE replies_fts USING fts5(body, tokenize = 'porter unicode61');
+INSERT INTO replies_fts (rowid, body) SELECT id, body FROM replies WHERE reply_status = 1;
+
+CREATE TRIGGER replies_fts_insert AFTER INSERT ON replies WHEN new.reply_status = 1 BEGIN
+    INSERT INTO replies_fts (rowid, body) VALUES (new.id, new.body);
+END;
+CREATE TRIGGER replies_fts_update AFTER UPDATE OF body, reply_status ON replies BEGIN
+    DELETE FROM replies_fts WHERE rowid = old.id;
+    INSERT INTO replies_fts (rowid, body) SELECT new.id, new.body WHERE new.reply_status = 1;
+END;
+CREATE TRIGGER replies_fts_delete AFTER DELETE ON replies BEGIN
+    DELETE FROM replies_fts WHERE rowid = old.id;
+END;
+
+CREATE VIRTUAL TABLE threads_fts USING fts5(title, tokenize = 'porter unicode61');
+INSERT INTO threads_fts (rowid, title) SELECT id, title FROM threads;
+
+CREATE TRIGGER threads_fts_insert AFTER INSERT ON threads BEGIN
+    INSERT INTO threads_fts (rowid, title) VALUES (new.id, new.title);
+END;
+CREATE TRIGGER threads_fts_update AFTER UPDATE OF title ON threads BEGIN
+    UPDATE threads_fts SET title = new.title WHERE rowid = old.id;
+END;
+CREATE TRIGGER threads_fts_delete AFTER DELETE ON threads BEGIN
+    DELETE FROM threads_fts WHERE rowid = old.id;
+END;
+";
+
+const SQLITE_DOWN: &str = r"
+DROP TRIGGER IF EXISTS threads_fts_delete;
+DROP TRIGGER IF EXISTS threads_fts_update;
+DROP TRIGGER IF EXISTS threads_fts_insert;
+DROP TABLE IF EXISTS threads_fts;
+DROP TRIGGER IF EXISTS replies_fts_delete;
+DROP TRIGGER IF EXISTS replies_fts_update;
+DROP TRIGGER IF EXISTS replies_fts_insert;
+DROP TABLE IF EXISTS replies_fts;
+";
+
+/// Postgres derives a tsvector column from the text, so edits are picked up
+/// without any help. Hidden and deleted replies are filtered at query time.
+const POSTGRES_UP: &str = r"
+ALTER TABLE replies ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (to_tsvector('english', body)) STORED;
+CREATE INDEX replies_search_vector_index ON replies USING GIN (search_vector);
+ALTER TABLE threads ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (to_tsvector('english', title)) STORED;
+CREATE INDEX threads_search_vector_index ON threads USING GIN (search_vector);
+";
+
+const POSTGRES_DOWN: &str = r"
+DROP INDEX IF EXISTS threads_search_vector_index;
+ALTER TABLE threads DROP COLUMN IF EXISTS search_vector;
+DROP INDEX IF EXISTS replies_search_vector_index;
+ALTER TABLE replies DROP COLUMN IF EXISTS search_vector;
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        let sql = match m.get_database_backend() {
+            DbBackend::Sqlite => SQLITE_UP,
+            DbBackend::Postgres => POSTGRES_UP,
+            DbBackend::MySql => return Err(DbErr::Migration("Full-text search isn't supported on MySQL".to_string())),
+        };
+        m.get_connection().execute_unprepared(sql).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        let sql = match m.get_database_backend() {
+            DbBackend::Sqlite => SQLITE_DOWN,
+            DbBackend::Postgres => POSTGRES_DOWN,
+            DbBackend::MySql => return Ok(()),
+        };
+        m.get_connection().execute_unprepared(sql).await?;
+        Ok(())
+    }
+}
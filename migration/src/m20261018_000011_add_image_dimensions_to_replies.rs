@@ -0,0 +1,22 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        add_column(m, "replies", "image_width", ColType::IntegerNull).await?;
+        add_column(m, "replies", "image_height", ColType::IntegerNull).await?;
+        add_column(m, "replies", "image_variants", ColType::JsonNull).await?;
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "replies", "image_variants").await?;
+        remove_column(m, "replies", "image_height").await?;
+        remove_column(m, "replies", "image_width").await?;
+        Ok(())
+    }
+}
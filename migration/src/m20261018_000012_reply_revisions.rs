@@ -0,0 +1,38 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "reply_revisions",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ("body", ColType::Text),
+            ],
+            &[
+            ("reply", ""),
+            ("user", "editor_id"),
+            ]
+        ).await?;
+
+        m.get_connection()
+            .execute_unprepared("CREATE INDEX reply_revisions_reply_index ON reply_revisions (reply_id);")
+            .await?;
+
+        add_column(m, "replies", "edit_count", ColType::IntegerWithDefault(0)).await?;
+        add_column(m, "boards", "revisions_public", ColType::BooleanWithDefault(false)).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "boards", "revisions_public").await?;
+        remove_column(m, "replies", "edit_count").await?;
+        drop_table(m, "reply_revisions").await
+    }
+}
@@ -0,0 +1,35 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "mentions",
+            &[
+
+            ("id", ColType::PkAuto),
+            // Set when an edit takes the mention out. The row is kept so that
+            // putting it back doesn't notify the user a second time.
+            ("removed_at", ColType::TimestampWithTimeZoneNull),
+
+            ],
+            &[
+            ("reply", ""),
+            ("user", ""),
+            ]
+        ).await?;
+
+        let db = m.get_connection();
+        db.execute_unprepared("CREATE UNIQUE INDEX mentions_reply_user_index ON mentions (reply_id, user_id);").await?;
+        db.execute_unprepared("CREATE INDEX mentions_user_index ON mentions (user_id);").await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "mentions").await
+    }
+}
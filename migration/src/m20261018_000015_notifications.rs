@@ -0,0 +1,37 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "notifications",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ("kind", ColType::String),
+            ("read_at", ColType::TimestampWithTimeZoneNull),
+            ],
+            &[
+            ("user", ""),
+            ("user", "actor_id"),
+            ("board?", "board_id"),
+            ("thread?", "thread_id"),
+            ("reply?", "reply_id"),
+            ("mod_action?", "mod_action_id"),
+            ]
+        ).await?;
+
+        let db = m.get_connection();
+        db.execute_unprepared("CREATE INDEX notifications_user_read_index ON notifications (user_id, read_at);").await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        drop_table(m, "notifications").await
+    }
+}
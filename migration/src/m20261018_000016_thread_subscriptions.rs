@@ -0,0 +1,39 @@
+use loco_rs::schema::*;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        create_table(m, "thread_subscriptions",
+            &[
+
+            ("id", ColType::PkAuto),
+
+            ("last_reply_id", ColType::IntegerWithDefault(0)),
+            ],
+            &[
+            ("thread", ""),
+            ("user", ""),
+            ]
+        ).await?;
+
+        let db = m.get_connection();
+        db.execute_unprepared("CREATE UNIQUE INDEX thread_subscriptions_thread_user_index ON thread_subscriptions (thread_id, user_id);").await?;
+        db.execute_unprepared("CREATE INDEX thread_subscriptions_user_index ON thread_subscriptions (user_id);").await?;
+
+        // Existing users never opted in to digests; new accounts are set to daily when created
+        add_column(m, "users", "digest_frequency", ColType::StringWithDefault("off".to_string())).await?;
+        add_column(m, "users", "last_digest_at", ColType::TimestampWithTimeZoneNull).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        remove_column(m, "users", "last_digest_at").await?;
+        remove_column(m, "users", "digest_frequency").await?;
+        drop_table(m, "thread_subscriptions").await
+    }
+}
@@ -0,0 +1,23 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, m: &SchemaManager) -> Result<(), DbErr> {
+        // Every account created before email verification existed came through Google OAuth,
+        // whose addresses are already verified
+        m.get_connection()
+            .execute_unprepared(
+                "UPDATE users SET email_verified_at = created_at WHERE email_verified_at IS NULL;",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _m: &SchemaManager) -> Result<(), DbErr> {
+        // Backfilled rows can't be told apart from genuinely verified ones
+        Ok(())
+    }
+}
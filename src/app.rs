@@ -13,9 +13,15 @@ use loco_rs::{
 use migration::Migrator;
 use std::path::Path;
 
-use crate::models::{_entities::reply_statuses, boards, replies, threads};
+use crate::image_storage;
+use crate::models::{boards, replies, threads};
 #[allow(unused_imports)]
-use crate::{controllers, models::_entities::users, tasks, workers::downloader::DownloadWorker};
+use crate::{
+    controllers,
+    models::_entities::users,
+    tasks,
+    workers::{downloader::DownloadWorker, image_variants::ImageVariantsWorker},
+};
 
 pub struct App;
 #[async_trait]
@@ -42,6 +48,11 @@ impl Hooks for App {
         create_app::<Self, Migrator>(mode, environment, config).await
     }
 
+    async fn after_context(ctx: AppContext) -> Result<AppContext> {
+        image_storage::init(&ctx.config).await?;
+        Ok(ctx)
+    }
+
     async fn initializers(_ctx: &AppContext) -> Result<Vec<Box<dyn Initializer>>> {
         Ok(vec![])
     }
@@ -51,18 +62,25 @@ impl Hooks for App {
             .add_route(controllers::auth::routes())
             .add_route(controllers::google_auth::routes())
             .add_route(controllers::boards::routes())
+            .add_route(controllers::board_categories::routes())
             .add_route(controllers::search::routes())
             .add_route(controllers::users::routes())
             .add_route(controllers::images::routes())
+            .add_route(controllers::moderation::routes())
+            .add_route(controllers::notifications::routes())
     }
     async fn connect_workers(ctx: &AppContext, queue: &Queue) -> Result<()> {
         queue.register(DownloadWorker::build(ctx)).await?;
+        queue.register(ImageVariantsWorker::build(ctx)).await?;
         Ok(())
     }
 
     #[allow(unused_variables)]
     fn register_tasks(tasks: &mut Tasks) {
         tasks.register(tasks::activity_report::ActivityReport);
+        tasks.register(tasks::lift_expired_bans::LiftExpiredBans);
+        tasks.register(tasks::cleanup_images::CleanupImages);
+        tasks.register(tasks::thread_digest::ThreadDigest);
         // tasks-inject (do not remove)
     }
     async fn truncate(ctx: &AppContext) -> Result<()> {
@@ -1,15 +1,283 @@
 use crate::{
-    models::_entities::users,
-    views::auth::CurrentResponse,
+    controllers::extractors::{ensure_not_banned, CurrentUser},
+    mailers::auth::AuthMailer,
+    models::{
+        _entities::users,
+        bans::{self, BanResponse},
+        mod_actions::{self, ModAction, ModTarget, NewModAction},
+        users::{LoginParams, RegisterParams, ResetParams},
+    },
+    permissions::{self, Permission},
+    views::auth::{CurrentResponse, LoginResponse},
 };
-use sea_orm::Set;
-use axum::debug_handler;
-use loco_rs::prelude::*;
+use axum::{debug_handler, extract::Path, response::Redirect};
+use loco_rs::{environment, prelude::cookie::*, prelude::*};
 use serde::{Deserialize, Serialize};
+use time::Duration;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BanUserParams {
     pub user_id: i32,
+    #[serde(default)]
+    pub reason: String,
+    /// When the ban lifts by itself; omit for a permanent ban
+    pub expires_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UnbanUserParams {
+    pub user_id: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ForgotParams {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MagicLinkParams {
+    pub email: String,
+}
+
+/// Generates a JWT for the user and adds the jwt, username and is_moderator
+/// cookies that the frontend relies on. Board moderators count as moderators.
+/// Returns the updated jar and the token.
+pub(crate) async fn set_login_cookies(
+    ctx: &AppContext,
+    jar: CookieJar,
+    user: &users::Model,
+) -> Result<(CookieJar, String)> {
+    let jwt_secret = ctx.config.get_jwt_config()?;
+    let token = user
+        .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
+        .map_err(|_| Error::Message("Failed to generate token".to_string()))?;
+
+    let is_development = ctx.environment == environment::Environment::Development;
+
+    let jwt_cookie = Cookie::build(("jwt", token.clone()))
+        .secure(!is_development)
+        .same_site(if is_development { SameSite::Lax } else { SameSite::Strict })
+        .max_age(Duration::days(7))
+        .path("/")
+        .http_only(true);
+
+    let username_cookie = Cookie::build(("username", user.name.clone()))
+        .secure(!is_development)
+        .same_site(if is_development { SameSite::Lax } else { SameSite::Strict })
+        .max_age(Duration::days(7))
+        .path("/")
+        .http_only(false);
+
+    let is_moderator = permissions::is_moderator(&ctx.db, user).await?;
+    let is_moderator_cookie = Cookie::build(("is_moderator", is_moderator.to_string()))
+        .secure(!is_development)
+        .same_site(if is_development { SameSite::Lax } else { SameSite::Strict })
+        .max_age(Duration::days(7))
+        .path("/")
+        .http_only(false);
+
+    Ok((
+        jar.add(jwt_cookie).add(username_cookie).add(is_moderator_cookie),
+        token,
+    ))
+}
+
+/// Register a new user with an email and password, and log them in
+#[debug_handler]
+async fn register(
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<RegisterParams>,
+) -> Result<(CookieJar, Response)> {
+    let user = match users::Model::create_with_password(&ctx.db, &params).await {
+        Ok(user) => user,
+        Err(ModelError::EntityAlreadyExists) => {
+            return bad_request("An account with this email already exists");
+        }
+        Err(err) => {
+            tracing::info!(
+                message = err.to_string(),
+                user_email = &params.email,
+                "could not register user",
+            );
+            return Err(err.into());
+        }
+    };
+
+    let user = user
+        .into_active_model()
+        .set_email_verification_sent(&ctx.db)
+        .await?;
+
+    AuthMailer::send_welcome(&ctx, &user).await?;
+
+    let (jar, token) = set_login_cookies(&ctx, jar, &user).await?;
+
+    tracing::info!(
+        user_pid = user.pid.to_string(),
+        user_email = user.email,
+        "User registered with email and password"
+    );
+
+    Ok((jar, format::json(LoginResponse::new(&user, &token))?))
+}
+
+/// Log in with an email and password
+#[debug_handler]
+async fn login(
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Json(params): Json<LoginParams>,
+) -> Result<(CookieJar, Response)> {
+    let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
+        tracing::debug!(
+            email = params.email,
+            "login attempt with non-existent email"
+        );
+        return unauthorized("Invalid email or password");
+    };
+
+    if !user.verify_password(&params.password) {
+        return unauthorized("Invalid email or password");
+    }
+
+    let user = ensure_not_banned(&ctx.db, user).await?;
+
+    let (jar, token) = set_login_cookies(&ctx, jar, &user).await?;
+
+    tracing::info!(
+        user_pid = user.pid.to_string(),
+        user_email = user.email,
+        "User logged in with email and password"
+    );
+
+    Ok((jar, format::json(LoginResponse::new(&user, &token))?))
+}
+
+/// Verify a user's email address from the link in their welcome email
+#[debug_handler]
+async fn verify(State(ctx): State<AppContext>, Path(token): Path<String>) -> Result<Redirect> {
+    let Ok(user) = users::Model::find_by_verification_token(&ctx.db, &token).await else {
+        return unauthorized("Invalid or expired verification token");
+    };
+
+    if user.is_verified() {
+        tracing::info!(user_pid = user.pid.to_string(), "user already verified");
+    } else {
+        let user = user.into_active_model().verified(&ctx.db).await?;
+        tracing::info!(user_pid = user.pid.to_string(), "user verified");
+    }
+
+    Ok(Redirect::temporary("/"))
+}
+
+/// Send a fresh verification email to the current user
+#[debug_handler]
+async fn resend_verification(CurrentUser(user): CurrentUser, State(ctx): State<AppContext>) -> Result<Response> {
+    if user.is_verified() {
+        return bad_request("Email is already verified");
+    }
+
+    let user = user
+        .into_active_model()
+        .set_email_verification_sent(&ctx.db)
+        .await?;
+
+    AuthMailer::send_welcome(&ctx, &user).await?;
+
+    format::json(())
+}
+
+/// Email a password reset link. Succeeds whether or not the email belongs to
+/// an account, so that this endpoint can't be used to discover users.
+#[debug_handler]
+async fn forgot(
+    State(ctx): State<AppContext>,
+    Json(params): Json<ForgotParams>,
+) -> Result<Response> {
+    let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
+        tracing::debug!(email = params.email, "password reset requested for unknown email");
+        return format::json(());
+    };
+
+    let user = user
+        .into_active_model()
+        .set_forgot_password_sent(&ctx.db)
+        .await?;
+
+    AuthMailer::forgot_password(&ctx, &user).await?;
+
+    format::json(())
+}
+
+/// Reset a user's password using the token from their forgot password email
+#[debug_handler]
+async fn reset(
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<ResetParams>,
+) -> Result<Response> {
+    let Ok(user) = users::Model::find_by_reset_token(&ctx.db, &params.token).await else {
+        return unauthorized("Invalid or expired reset token");
+    };
+
+    let user = user
+        .into_active_model()
+        .reset_password(&ctx.db, &params.password)
+        .await?;
+
+    tracing::info!(user_pid = user.pid.to_string(), "user reset their password");
+
+    format::json(())
+}
+
+/// Email a one-time login link. Like `forgot`, this succeeds for unknown emails.
+#[debug_handler]
+async fn magic_link(
+    State(ctx): State<AppContext>,
+    Json(params): Json<MagicLinkParams>,
+) -> Result<Response> {
+    let Ok(user) = users::Model::find_by_email(&ctx.db, &params.email).await else {
+        tracing::debug!(email = params.email, "magic link requested for unknown email");
+        return format::json(());
+    };
+
+    let user = user.into_active_model().create_magic_link(&ctx.db).await?;
+    AuthMailer::send_magic_link(&ctx, &user).await?;
+
+    format::json(())
+}
+
+/// Log in from the link in a magic link email
+#[debug_handler]
+async fn magic_link_verify(
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Path(token): Path<String>,
+) -> Result<(CookieJar, Redirect)> {
+    let Ok(user) = users::Model::find_by_magic_token(&ctx.db, &token).await else {
+        return unauthorized("Invalid or expired login link");
+    };
+
+    let user = user.into_active_model().clear_magic_link(&ctx.db).await?;
+
+    let user = ensure_not_banned(&ctx.db, user).await?;
+
+    // Following the link proves the user controls the address
+    let user = if user.is_verified() {
+        user
+    } else {
+        user.into_active_model().verified(&ctx.db).await?
+    };
+
+    let (jar, _token) = set_login_cookies(&ctx, jar, &user).await?;
+
+    tracing::info!(
+        user_pid = user.pid.to_string(),
+        user_email = user.email,
+        "User logged in via magic link"
+    );
+
+    Ok((jar, Redirect::temporary("/")))
 }
 
 #[debug_handler]
@@ -21,22 +289,93 @@ async fn current(auth: auth::JWT, State(ctx): State<AppContext>) -> Result<Respo
 /// Ban a user (moderator only)
 #[debug_handler]
 async fn ban_user(
-    auth: auth::JWT,
+    CurrentUser(current_user): CurrentUser,
     State(ctx): State<AppContext>,
     Json(params): Json<BanUserParams>,
 ) -> Result<Response> {
-    let current_user = users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    permissions::ensure(&ctx.db, &current_user, Permission::BanUser, None).await?;
 
-    if !current_user.is_moderator {
-        return unauthorized("Only moderators can ban users");
+    if params.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+        return bad_request("Ban expiry must be in the future");
     }
 
-    let target_user = users::Entity::find_by_id(params.user_id).one(&ctx.db).await?
+    let target = users::Entity::find_by_id(params.user_id)
+        .one(&ctx.db)
+        .await?
         .ok_or_else(|| Error::NotFound)?;
+    // Moderators can't ban each other, or their own superiors
+    if !current_user.role().outranks(target.role()) {
+        return Err(Error::Unauthorized(
+            "You can only ban users whose role is below yours".to_string(),
+        ));
+    }
+
+    let active_ban = bans::Entity::find_active_for_user(&ctx.db, params.user_id).await?;
+
+    let txn = ctx.db.begin().await?;
+    let ban = bans::Model::create(
+        &txn,
+        params.user_id,
+        current_user.id,
+        params.reason.clone(),
+        params.expires_at,
+    ).await?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: current_user.id,
+        action: ModAction::BanUser,
+        target: ModTarget::User(params.user_id),
+        target_user_id: Some(params.user_id),
+        board_id: None,
+        reason: Some(params.reason),
+        previous_state: active_ban
+            .as_ref()
+            .and_then(|ban| serde_json::to_value(BanResponse::from(ban)).ok()),
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(BanResponse::from(&ban))
+}
+
+/// Lift all active bans on a user (moderator only)
+#[debug_handler]
+async fn unban_user(
+    CurrentUser(current_user): CurrentUser,
+    State(ctx): State<AppContext>,
+    Json(params): Json<UnbanUserParams>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &current_user, Permission::BanUser, None).await?;
+
+    let target = users::Entity::find_by_id(params.user_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+    // Bans on moderators and their superiors are out of reach, like banning them is
+    if !current_user.role().outranks(target.role()) {
+        return Err(Error::Unauthorized(
+            "You can only unban users whose role is below yours".to_string(),
+        ));
+    }
+
+    let active_ban = bans::Entity::find_active_for_user(&ctx.db, params.user_id).await?;
+
+    let txn = ctx.db.begin().await?;
+    bans::Model::lift(&txn, params.user_id, Some(current_user.id)).await?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: current_user.id,
+        action: ModAction::UnbanUser,
+        target: ModTarget::User(params.user_id),
+        target_user_id: Some(params.user_id),
+        board_id: None,
+        reason: None,
+        previous_state: active_ban
+            .as_ref()
+            .and_then(|ban| serde_json::to_value(BanResponse::from(ban)).ok()),
+    }).await?;
 
-    let mut active_user: users::ActiveModel = target_user.into();
-    active_user.is_banned = Set(true);
-    active_user.update(&ctx.db).await?;
+    txn.commit().await?;
 
     format::json(())
 }
@@ -44,6 +383,15 @@ async fn ban_user(
 pub fn routes() -> Routes {
     Routes::new()
         .prefix("/api/auth")
+        .add("/register", post(register))
+        .add("/login", post(login))
+        .add("/verify/resend", post(resend_verification))
+        .add("/verify/{token}", get(verify))
+        .add("/forgot", post(forgot))
+        .add("/reset", post(reset))
+        .add("/magic-link", post(magic_link))
+        .add("/magic-link/{token}", get(magic_link_verify))
         .add("/current", get(current))
         .add("/ban", post(ban_user))
+        .add("/unban", post(unban_user))
 }
@@ -0,0 +1,71 @@
+use crate::controllers::extractors::CurrentUser;
+use crate::models::board_categories::{self, CategoryParams};
+use crate::permissions::{self, Permission};
+use axum::{debug_handler, extract::Path};
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+
+/// Get all board categories in display order
+#[debug_handler]
+async fn list(State(ctx): State<AppContext>) -> Result<Response> {
+    let categories = board_categories::Entity::find()
+        .order_by_asc(board_categories::Column::Position)
+        .order_by_asc(board_categories::Column::Id)
+        .all(&ctx.db)
+        .await?;
+    format::json(categories)
+}
+
+/// Create a board category (moderator only)
+#[debug_handler]
+async fn create(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<CategoryParams>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    let category = board_categories::Model::create(&ctx.db, &params).await?;
+    format::json(category)
+}
+
+/// Edit a board category (moderator only)
+#[debug_handler]
+async fn update(
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<CategoryParams>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    let category = board_categories::Entity::find_by_id(id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+
+    let category = category.into_active_model().apply_update(&ctx.db, &params).await?;
+    format::json(category)
+}
+
+/// Delete a board category, leaving its boards uncategorized (moderator only)
+#[debug_handler]
+async fn remove(
+    CurrentUser(user): CurrentUser,
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    board_categories::Model::delete_category(&ctx.db, id).await?;
+    format::json(serde_json::json!({"success": true}))
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/board_categories")
+        .add("/", get(list))
+        .add("/", post(create))
+        .add("/{id}", patch(update))
+        .add("/{id}", delete(remove))
+}
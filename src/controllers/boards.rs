@@ -1,20 +1,59 @@
+use crate::controllers::extractors::{CurrentUser, MaybeCurrentUser};
+use crate::permissions::{self, Permission};
 use crate::models::{
+    board_members::{self, Entity as BoardMemberEntity},
+    board_moderators::{self, Entity as BoardModeratorEntity},
+    board_categories::Entity as BoardCategoryEntity,
     boards::Entity as BoardEntity,
     boards::Model as Board,
+    boards::{BoardVisibility, CreateBoardParams, PostingPolicy, UpdateBoardParams},
+    mod_actions::{self, ModAction, ModTarget, NewModAction},
     threads::Entity as ThreadEntity,
     threads::ThreadWithPosterName,
     threads::Model as ThreadModel,
+    threads::ThreadStateParams,
     replies::Entity as ReplyEntity,
-    replies::Model as Reply
+    replies::Model as Reply,
+    reply_revisions::{self, ReplyVersion},
+    reports::{self, CreateReportParams},
+    users::Entity as UserEntity,
+    users::Model as User,
+    mentions::Entity as MentionEntity,
+    notifications::{self, NewNotification, NotificationKind},
+    thread_subscriptions::{DigestFrequency, DigestReply, DigestThread, Entity as ThreadSubscriptionEntity},
 };
-use axum::{debug_handler, extract::Path, extract::Query, Json};
-use loco_rs::prelude::*;
+use crate::mailers::digests::DigestMailer;
+use crate::mailers::mentions::{MentionArgs, MentionMailer};
+use crate::workers::image_variants::ImageVariantsWorker;
+use axum::{debug_handler, extract::Path, extract::Query, http::StatusCode, Json};
+use loco_rs::{controller::ErrorDetail, prelude::*};
 use serde::{Deserialize, Serialize};
 
+#[derive(Deserialize)]
+struct BoardsQuery {
+    #[serde(default)]
+    include_archived: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteBoardQuery {
+    /// Purge the board's threads along with it
+    #[serde(default)]
+    cascade: bool,
+    reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReorderBoardsRequest {
+    pub board_ids: Vec<i32>,
+}
+
 #[derive(Deserialize)]
 struct ThreadsQuery {
     page_size: Option<u64>,
     page_number: Option<u64>,
+    #[serde(default)]
+    include_archived: bool,
 }
 
 #[derive(Deserialize)]
@@ -29,6 +68,8 @@ pub struct RepliesResponse {
     pub total_count: u64,
     pub thread_title: String,
     pub board_name: String,
+    pub thread_is_locked: bool,
+    pub thread_is_archived: bool,
 }
 
 #[derive(Deserialize)]
@@ -38,9 +79,9 @@ struct CreateThreadRequest {
     pending_image_key: Option<String>,
 }
 
-#[derive(Serialize)]
-struct CreateThreadResponse {
-    thread_id: i32,
+#[derive(Serialize, Deserialize)]
+pub struct CreateThreadResponse {
+    pub thread_id: i32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -74,9 +115,69 @@ struct UpdateReplyRequest {
     remove_image: bool,
 }
 
+#[derive(Deserialize)]
+pub struct DeleteThreadQuery {
+    reason: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct DeleteReplyRequest {
     action: String, // "delete" or "hide"
+    /// Recorded in the moderation log when a moderator acts on a reply
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateThreadStateRequest {
+    #[serde(flatten)]
+    pub state: ThreadStateParams,
+    /// Recorded in the moderation log
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MoveThreadRequest {
+    pub board_id: i32,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MergeThreadRequest {
+    pub into_thread_id: i32,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SplitThreadRequest {
+    pub first_reply_id: i32,
+    pub last_reply_id: i32,
+    pub title: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateReportResponse {
+    pub report_id: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubscriptionResponse {
+    pub subscribed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddBoardMemberRequest {
+    pub user_id: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddBoardModeratorRequest {
+    pub user_id: i32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -85,29 +186,448 @@ pub struct ThreadsResponse {
     pub total_count: u64,
 }
 
-/// Get all boards
+/// Checks that `user` (None for anonymous visitors) can see `board`.
+/// Members-only boards are reported as missing to anyone outside them.
+pub(crate) async fn ensure_can_view(db: &DatabaseConnection, board: &Board, user: Option<&User>) -> Result<()> {
+    let member_board_ids = BoardEntity::member_board_ids(db, user).await?;
+    if board.is_visible_to(user, &member_board_ids) {
+        return Ok(());
+    }
+    match (board.visibility(), user) {
+        (BoardVisibility::LoggedIn, None) => {
+            Err(loco_rs::Error::Unauthorized("You must log in to view this board".to_string()))
+        }
+        _ => Err(loco_rs::Error::NotFound),
+    }
+}
+
+/// Loads a board, checking that `user` can see it
+async fn find_visible_board(db: &DatabaseConnection, board_id: i32, user: Option<&User>) -> Result<Board> {
+    let board = BoardEntity::find_by_id(board_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    ensure_can_view(db, &board, user).await?;
+    Ok(board)
+}
+
+/// Notifies and emails the users in `user_ids` that `poster` mentioned them
+/// in `reply`. Users who can't see the board aren't told, so a mention can't
+/// leak a private board's posts. Failures are logged rather than failing the
+/// post.
+async fn notify_mentioned(
+    ctx: &AppContext,
+    board: &Board,
+    thread: &ThreadModel,
+    reply: &Reply,
+    poster: &User,
+    user_ids: Vec<i32>,
+) {
+    if user_ids.is_empty() {
+        return;
+    }
+
+    let users = match UserEntity::find()
+        .filter(crate::models::users::users::Column::Id.is_in(user_ids))
+        .all(&ctx.db)
+        .await
+    {
+        Ok(users) => users,
+        Err(err) => {
+            tracing::error!(reply_id = reply.id, "Failed to look up mentioned users: {err}");
+            return;
+        }
+    };
+
+    let args = MentionArgs {
+        poster_name: &poster.name,
+        board_id: board.id,
+        thread_id: thread.id,
+        thread_title: &thread.title,
+        body_html: reply.body_html.as_deref().unwrap_or_default(),
+        body: &reply.body,
+    };
+    for user in users {
+        if ensure_can_view(&ctx.db, board, Some(&user)).await.is_err() {
+            continue;
+        }
+        let notification = NewNotification {
+            user_id: user.id,
+            actor_id: poster.id,
+            kind: NotificationKind::Mention,
+            board_id: Some(board.id),
+            thread_id: Some(thread.id),
+            reply_id: Some(reply.id),
+            mod_action_id: None,
+        };
+        if let Err(err) = notifications::Model::notify(&ctx.db, notification).await {
+            tracing::error!(reply_id = reply.id, user_id = user.id, "Failed to notify mentioned user: {err}");
+        }
+        if let Err(err) = MentionMailer::send_mentioned(ctx, &user, &args).await {
+            tracing::error!(reply_id = reply.id, user_id = user.id, "Failed to send mention email: {err}");
+        }
+    }
+}
+
+/// Notifies the poster of the reply that `reply` answers, the user who
+/// started `thread` and its subscribers that `poster` posted it. Anyone in
+/// `mentioned` already heard about it, and users who can no longer see the
+/// board aren't told. Failures are logged rather than failing the post.
+async fn notify_participants(
+    ctx: &AppContext,
+    board: &Board,
+    thread: &ThreadModel,
+    reply: &Reply,
+    poster: &User,
+    mentioned: &[i32],
+) {
+    let mut recipients = Vec::new();
+    if let Some(reply_to) = reply.reply_to {
+        match ReplyEntity::find_by_id(reply_to).one(&ctx.db).await {
+            Ok(Some(parent)) => recipients.push((parent.poster, NotificationKind::Reply)),
+            Ok(None) => {}
+            Err(err) => tracing::error!(reply_id = reply.id, "Failed to look up the reply being answered: {err}"),
+        }
+    }
+    recipients.push((thread.poster, NotificationKind::ThreadReply));
+    match ThreadSubscriptionEntity::subscriptions_for_thread(&ctx.db, thread.id).await {
+        Ok(subscriptions) => recipients.extend(
+            subscriptions
+                .into_iter()
+                .map(|subscription| (subscription.user_id, NotificationKind::ThreadReply)),
+        ),
+        Err(err) => tracing::error!(thread_id = thread.id, "Failed to look up thread subscribers: {err}"),
+    }
+
+    let mut notified: Vec<i32> = mentioned.to_vec();
+    notified.push(poster.id);
+    for (user_id, kind) in recipients {
+        if notified.contains(&user_id) {
+            continue;
+        }
+        notified.push(user_id);
+
+        let user = match UserEntity::find_by_id(user_id).one(&ctx.db).await {
+            Ok(Some(user)) => user,
+            Ok(None) => continue,
+            Err(err) => {
+                tracing::error!(reply_id = reply.id, user_id, "Failed to look up user to notify: {err}");
+                continue;
+            }
+        };
+        if ensure_can_view(&ctx.db, board, Some(&user)).await.is_err() {
+            continue;
+        }
+        let notification = NewNotification {
+            user_id,
+            actor_id: poster.id,
+            kind,
+            board_id: Some(board.id),
+            thread_id: Some(thread.id),
+            reply_id: Some(reply.id),
+            mod_action_id: None,
+        };
+        if let Err(err) = notifications::Model::notify(&ctx.db, notification).await {
+            tracing::error!(reply_id = reply.id, user_id, "Failed to send notification: {err}");
+        }
+    }
+}
+
+/// Emails `reply` to the subscribers of `thread` who want every reply as it
+/// comes, and moves their subscriptions past it so digests don't repeat it.
+/// Anyone in `mentioned` was already emailed about it. Failures are logged
+/// rather than failing the post.
+async fn email_immediate_subscribers(
+    ctx: &AppContext,
+    board: &Board,
+    thread: &ThreadModel,
+    reply: &Reply,
+    poster: &User,
+    mentioned: &[i32],
+) {
+    let subscriptions = match ThreadSubscriptionEntity::subscriptions_for_thread(&ctx.db, thread.id).await {
+        Ok(subscriptions) => subscriptions,
+        Err(err) => {
+            tracing::error!(thread_id = thread.id, "Failed to look up thread subscribers: {err}");
+            return;
+        }
+    };
+
+    let digest = DigestThread {
+        board_id: board.id,
+        thread_id: thread.id,
+        title: thread.title.clone(),
+        replies: vec![DigestReply {
+            reply_id: reply.id,
+            poster_name: poster.name.clone(),
+            body: reply.body.clone(),
+            body_html: reply.body_html.clone().unwrap_or_default(),
+            created_at: reply.created_at,
+        }],
+        more_replies: 0,
+        latest_reply_id: reply.id,
+    };
+    for subscription in subscriptions {
+        if subscription.user_id == poster.id {
+            continue;
+        }
+        let user = match UserEntity::find_by_id(subscription.user_id).one(&ctx.db).await {
+            Ok(Some(user)) => user,
+            Ok(None) => continue,
+            Err(err) => {
+                tracing::error!(reply_id = reply.id, user_id = subscription.user_id, "Failed to look up subscriber: {err}");
+                continue;
+            }
+        };
+        if user.digest_frequency() != DigestFrequency::Immediate
+            || ensure_can_view(&ctx.db, board, Some(&user)).await.is_err()
+        {
+            continue;
+        }
+        if mentioned.contains(&user.id) {
+            // The mention email already carried the reply
+        } else if let Err(err) = DigestMailer::send_digest(ctx, &user, std::slice::from_ref(&digest)).await {
+            tracing::error!(reply_id = reply.id, user_id = user.id, "Failed to email subscriber: {err}");
+            continue;
+        }
+        if let Err(err) = subscription.mark_sent(&ctx.db, reply.id).await {
+            tracing::error!(reply_id = reply.id, user_id = user.id, "Failed to update subscription: {err}");
+        }
+    }
+}
+
+/// Checks that `user` may start threads and post replies in `board`
+async fn ensure_can_post(db: &DatabaseConnection, board: &Board, user: &User) -> Result<()> {
+    if board.is_archived {
+        return Err(Error::CustomError(
+            StatusCode::FORBIDDEN,
+            ErrorDetail::new("board_archived", "This board is archived and no longer takes new posts"),
+        ));
+    }
+    if board.requires_verified_email && !user.is_verified() {
+        return Err(loco_rs::Error::Unauthorized("You must verify your email before posting on this board".to_string()));
+    }
+    match board.posting_policy() {
+        PostingPolicy::Everyone => {}
+        PostingPolicy::Members => {
+            if !permissions::moderates_board(db, user, board.id).await?
+                && !BoardMemberEntity::is_member(db, board.id, user.id).await?
+            {
+                return Err(Error::CustomError(
+                    StatusCode::FORBIDDEN,
+                    ErrorDetail::new("members_only", "Only members of this board can post here"),
+                ));
+            }
+        }
+        PostingPolicy::Moderators => {
+            if !permissions::moderates_board(db, user, board.id).await? {
+                return Err(Error::CustomError(
+                    StatusCode::FORBIDDEN,
+                    ErrorDetail::new("moderators_only", "Only moderators can post on this board"),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `thread` isn't archived, which makes it read-only
+fn ensure_thread_not_archived(thread: &ThreadModel) -> Result<()> {
+    if thread.is_archived {
+        return Err(Error::CustomError(
+            StatusCode::FORBIDDEN,
+            ErrorDetail::new("thread_archived", "This thread is archived and can no longer be changed"),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `thread` is still taking replies
+fn ensure_thread_open(thread: &ThreadModel) -> Result<()> {
+    ensure_thread_not_archived(thread)?;
+    if thread.is_locked {
+        return Err(Error::CustomError(
+            StatusCode::FORBIDDEN,
+            ErrorDetail::new("thread_locked", "This thread is locked"),
+        ));
+    }
+    Ok(())
+}
+
+/// Get the boards the current user can see, in display order. Archived
+/// boards are only listed on request.
 #[debug_handler]
-async fn list(State(ctx): State<AppContext>) -> Result<Response> {
-    let boards = Board::find_all(&ctx.db).await?;
+async fn list(
+    MaybeCurrentUser(user): MaybeCurrentUser,
+    Query(params): Query<BoardsQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let boards: Vec<Board> = BoardEntity::find_visible(&ctx.db, user.as_ref())
+        .await?
+        .into_iter()
+        .filter(|board| params.include_archived || !board.is_archived)
+        .collect();
     format::json(boards)
 }
 
+/// Get every board grouped into categories and sub-boards, with thread
+/// counts and latest activity rolled up into parent boards
+#[debug_handler]
+async fn tree(
+    MaybeCurrentUser(user): MaybeCurrentUser,
+    Query(params): Query<BoardsQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let tree = BoardCategoryEntity::find_tree(&ctx.db, user.as_ref(), params.include_archived).await?;
+    format::json(tree)
+}
+
+/// Create a board (moderator only)
+#[debug_handler]
+async fn create_board(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<CreateBoardParams>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    let txn = ctx.db.begin().await?;
+    let board = Board::create(&txn, &params).await?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::CreateBoard,
+        target: ModTarget::Board(board.id),
+        target_user_id: None,
+        board_id: Some(board.id),
+        reason: None,
+        previous_state: None,
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(board)
+}
+
+/// Edit, move or archive a board (moderator only)
+#[debug_handler]
+async fn update_board(
+    CurrentUser(user): CurrentUser,
+    Path(board_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<UpdateBoardParams>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    let board = BoardEntity::find_by_id(board_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+
+    let txn = ctx.db.begin().await?;
+    let updated = board.clone().into_active_model().apply_update(&txn, &params).await?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::UpdateBoard,
+        target: ModTarget::Board(board.id),
+        target_user_id: None,
+        board_id: Some(board.id),
+        reason: None,
+        previous_state: serde_json::to_value(&board).ok(),
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(updated)
+}
+
+/// Set the display order of every board (moderator only)
+#[debug_handler]
+async fn reorder_boards(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+    Json(req): Json<ReorderBoardsRequest>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    let txn = ctx.db.begin().await?;
+    let moved = BoardEntity::reorder(&txn, &req.board_ids).await?;
+
+    for board in moved {
+        mod_actions::Model::record(&txn, NewModAction {
+            actor_id: user.id,
+            action: ModAction::UpdateBoard,
+            target: ModTarget::Board(board.id),
+            target_user_id: None,
+            board_id: Some(board.id),
+            reason: None,
+            previous_state: serde_json::to_value(&board).ok(),
+        }).await?;
+    }
+
+    txn.commit().await?;
+
+    format::json(Board::find_all(&ctx.db).await?)
+}
+
+/// Delete a board (moderator only). Fails while the board has threads
+/// unless `cascade` is set.
+#[debug_handler]
+async fn delete_board(
+    CurrentUser(user): CurrentUser,
+    Path(board_id): Path<i32>,
+    Query(params): Query<DeleteBoardQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, None).await?;
+
+    let txn = ctx.db.begin().await?;
+    let (board, image_keys) = Board::delete_board(&txn, board_id, params.cascade).await?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::DeleteBoard,
+        target: ModTarget::Board(board.id),
+        target_user_id: None,
+        // The board is gone, so only the target id refers to it
+        board_id: None,
+        reason: params.reason,
+        previous_state: serde_json::to_value(&board).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    crate::models::threads::delete_purged_images(image_keys).await;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
 /// Get threads for a specific board with pagination
 #[debug_handler]
 async fn get_threads(
+    MaybeCurrentUser(user): MaybeCurrentUser,
     Path(board_id): Path<i32>,
     Query(params): Query<ThreadsQuery>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
-    let page_size = params.page_size.unwrap_or(10).max(1).min(100); // Default 10, max 100
+    let page_size = params.page_size.unwrap_or(10).clamp(1, 100); // Default 10, max 100
     let page_number = params.page_number.unwrap_or(0);
 
-    let threads = ThreadEntity::find_paginated(&ctx.db, board_id, page_size, page_number).await?;
-    let total_count = BoardEntity::find_by_id(board_id).one(&ctx.db).await?.unwrap().num_threads;
+    let board = find_visible_board(&ctx.db, board_id, user.as_ref()).await?;
+
+    let threads = ThreadEntity::find_paginated(&ctx.db, board_id, params.include_archived, page_size, page_number).await?;
+    let num_threads: u64 = board.num_threads.try_into().unwrap();
+
+    // num_threads counts archived threads, which are only listed on request
+    let total_count = if params.include_archived {
+        num_threads
+    } else {
+        num_threads.saturating_sub(ThreadEntity::count_archived(&ctx.db, board_id).await?)
+    };
 
     let response = ThreadsResponse {
         threads,
-        total_count: total_count.try_into().unwrap(),
+        total_count,
     };
 
     format::json(response)
@@ -116,32 +636,34 @@ async fn get_threads(
 /// Get replies for a specific thread with pagination
 #[debug_handler]
 async fn get_replies(
-    Path((board_id, thread_id)): Path<(i32, i32)>,
+    MaybeCurrentUser(user): MaybeCurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
     Query(params): Query<RepliesQuery>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
-    let page_size = params.page_size.unwrap_or(10).max(1).min(100); // Default 10, max 100
+    let page_size = params.page_size.unwrap_or(10).clamp(1, 100); // Default 10, max 100
     let page_number = params.page_number.unwrap_or(0);
 
-    let replies = ReplyEntity::find_paginated(&ctx.db, thread_id, page_size, page_number).await?;
-
     // Get the thread to fetch num_replies
     let thread = ThreadEntity::find_by_id(thread_id)
         .one(&ctx.db)
         .await?
+        .filter(|thread| !thread.is_deleted)
         .ok_or_else(|| loco_rs::Error::NotFound)?;
 
-    // Get the board to fetch the board name
-    let board = crate::models::boards::Entity::find_by_id(board_id)
-        .one(&ctx.db)
-        .await?
-        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    // Get the board to fetch the board name. The thread's own board is the
+    // one whose visibility applies, whatever board the path names.
+    let board = find_visible_board(&ctx.db, thread.board_id, user.as_ref()).await?;
+
+    let replies = ReplyEntity::find_paginated(&ctx.db, thread_id, page_size, page_number).await?;
 
     let response = RepliesResponse {
         replies,
         total_count: thread.num_replies as u64,
         thread_title: thread.title,
         board_name: board.title,
+        thread_is_locked: thread.is_locked,
+        thread_is_archived: thread.is_archived,
     };
 
     format::json(response)
@@ -150,13 +672,15 @@ async fn get_replies(
 /// Create a new thread in a board
 #[debug_handler]
 async fn create_thread(
-    auth: auth::JWT,
+    CurrentUser(user): CurrentUser,
     Path(board_id): Path<i32>,
     State(ctx): State<AppContext>,
     Json(req): Json<CreateThreadRequest>,
 ) -> Result<Response> {
-    let user = crate::models::users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let board = find_visible_board(&ctx.db, board_id, Some(&user)).await?;
+    ensure_can_post(&ctx.db, &board, &user).await?;
 
+    let has_image = req.pending_image_key.is_some();
     let thread = ThreadModel::create(
         &ctx.db,
         req.title,
@@ -166,6 +690,20 @@ async fn create_thread(
         req.pending_image_key,
     ).await?;
 
+    use sea_orm::QueryOrder;
+    let first_reply = ReplyEntity::find()
+        .filter(crate::models::replies::Column::ThreadId.eq(thread.id))
+        .order_by_asc(crate::models::replies::Column::Id)
+        .one(&ctx.db)
+        .await?;
+    if let Some(first_reply) = first_reply {
+        if has_image {
+            ImageVariantsWorker::enqueue(&ctx, first_reply.id).await;
+        }
+        let mentioned = MentionEntity::user_ids_for_reply(&ctx.db, first_reply.id).await?;
+        notify_mentioned(&ctx, &board, &thread, &first_reply, &user, mentioned).await;
+    }
+
     let response = CreateThreadResponse {
         thread_id: thread.id,
     };
@@ -173,29 +711,284 @@ async fn create_thread(
     format::json(response)
 }
 
+/// Soft-delete a thread (moderator only)
 #[debug_handler]
 pub async fn delete_thread(
-    auth: auth::JWT,
-    Path((board_id, thread_id)): Path<(i32, i32)>,
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    Query(params): Query<DeleteThreadQuery>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
-    let user = crate::models::users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
-    if !user.is_moderator {
-        return Err(loco_rs::Error::Unauthorized("You can only delete your own threads unless you are a moderator".to_string()));
-    }
-    ThreadModel::delete(&ctx.db, board_id, thread_id).await?;
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::DeleteThread, Some(thread.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    ThreadModel::delete(&txn, thread_id).await?;
+
+    let action = mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::DeleteThread,
+        target: ModTarget::Thread(thread.id),
+        target_user_id: Some(thread.poster),
+        board_id: Some(thread.board_id),
+        reason: params.reason,
+        previous_state: serde_json::to_value(&thread).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    notify_mod_action(&ctx.db, &action).await;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
+/// Bring back a soft-deleted thread (moderator only)
+#[debug_handler]
+async fn restore_thread(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    Query(params): Query<DeleteThreadQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::DeleteThread, Some(thread.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    ThreadModel::restore(&txn, thread_id).await?;
+
+    let action = mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::RestoreThread,
+        target: ModTarget::Thread(thread.id),
+        target_user_id: Some(thread.poster),
+        board_id: Some(thread.board_id),
+        reason: params.reason,
+        previous_state: serde_json::to_value(&thread).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    notify_mod_action(&ctx.db, &action).await;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
+/// Permanently delete a thread, its replies and their images (moderator only)
+#[debug_handler]
+async fn purge_thread(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    Query(params): Query<DeleteThreadQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::DeleteThread, Some(thread.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    let (_, image_keys) = ThreadModel::purge(&txn, thread_id).await?;
+
+    let action = mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::PurgeThread,
+        target: ModTarget::Thread(thread.id),
+        target_user_id: Some(thread.poster),
+        board_id: Some(thread.board_id),
+        reason: params.reason,
+        previous_state: serde_json::to_value(&thread).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    notify_mod_action(&ctx.db, &action).await;
+    crate::models::threads::delete_purged_images(image_keys).await;
+
     format::json(serde_json::json!({"success": true}))
 }
 
+/// Lock, pin or archive a thread, or reverse any of those (moderator only)
+#[debug_handler]
+async fn update_thread_state(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<UpdateThreadStateRequest>,
+) -> Result<Response> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::ManageThread, Some(thread.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    let updated = thread.clone().into_active_model().set_state(&txn, &req.state).await?;
+
+    let changes = [
+        (thread.is_locked, updated.is_locked, ModAction::LockThread, ModAction::UnlockThread),
+        (thread.is_pinned, updated.is_pinned, ModAction::PinThread, ModAction::UnpinThread),
+        (thread.is_archived, updated.is_archived, ModAction::ArchiveThread, ModAction::UnarchiveThread),
+    ];
+    let previous_state = serde_json::to_value(&thread).ok();
+    let mut actions = Vec::new();
+    for (before, after, set_action, unset_action) in changes {
+        if before == after {
+            continue;
+        }
+        let action = mod_actions::Model::record(&txn, NewModAction {
+            actor_id: user.id,
+            action: if after { set_action } else { unset_action },
+            target: ModTarget::Thread(thread.id),
+            target_user_id: Some(thread.poster),
+            board_id: Some(thread.board_id),
+            reason: req.reason.clone(),
+            previous_state: previous_state.clone(),
+        }).await?;
+        actions.push(action);
+    }
+
+    txn.commit().await?;
+    for action in &actions {
+        notify_mod_action(&ctx.db, action).await;
+    }
+
+    format::json(updated)
+}
+
+/// Move a thread to another board (moderator only)
+#[debug_handler]
+async fn move_thread(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<MoveThreadRequest>,
+) -> Result<Response> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::ManageThread, Some(thread.board_id)).await?;
+    permissions::ensure(&ctx.db, &user, Permission::ManageThread, Some(req.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    let moved = ThreadModel::move_to_board(&txn, thread_id, req.board_id).await?;
+
+    let action = mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::MoveThread,
+        target: ModTarget::Thread(thread.id),
+        target_user_id: Some(thread.poster),
+        board_id: Some(thread.board_id),
+        reason: req.reason,
+        previous_state: serde_json::to_value(&thread).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    notify_mod_action(&ctx.db, &action).await;
+
+    format::json(moved)
+}
+
+/// Merge a thread's replies into another thread (moderator only)
+#[debug_handler]
+async fn merge_thread(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<MergeThreadRequest>,
+) -> Result<Response> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::ManageThread, Some(thread.board_id)).await?;
+    let target = ThreadEntity::find_by_id(req.into_thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::ManageThread, Some(target.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    let merged = ThreadModel::merge_into(&txn, thread_id, req.into_thread_id).await?;
+
+    let action = mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::MergeThread,
+        target: ModTarget::Thread(thread.id),
+        target_user_id: Some(thread.poster),
+        board_id: Some(thread.board_id),
+        reason: req.reason,
+        previous_state: serde_json::to_value(&thread).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    notify_mod_action(&ctx.db, &action).await;
+
+    format::json(merged)
+}
+
+/// Move a range of a thread's replies into a new thread (moderator only)
+#[debug_handler]
+async fn split_thread(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<SplitThreadRequest>,
+) -> Result<Response> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    permissions::ensure(&ctx.db, &user, Permission::ManageThread, Some(thread.board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    let new_thread = ThreadModel::split(
+        &txn,
+        thread_id,
+        req.first_reply_id,
+        req.last_reply_id,
+        req.title,
+    ).await?;
+
+    let action = mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::SplitThread,
+        target: ModTarget::Thread(thread.id),
+        target_user_id: Some(thread.poster),
+        board_id: Some(thread.board_id),
+        reason: req.reason,
+        previous_state: serde_json::to_value(&thread).ok(),
+    }).await?;
+
+    txn.commit().await?;
+    notify_mod_action(&ctx.db, &action).await;
+
+    format::json(CreateThreadResponse {
+        thread_id: new_thread.id,
+    })
+}
+
 /// Create a new reply in a thread
 #[debug_handler]
 async fn create_reply(
-    auth: auth::JWT,
+    CurrentUser(user): CurrentUser,
     Path((_board_id, thread_id)): Path<(i32, i32)>,
     State(ctx): State<AppContext>,
     Json(req): Json<CreateReplyRequest>,
 ) -> Result<Response> {
-    let user = crate::models::users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    let board = find_visible_board(&ctx.db, thread.board_id, Some(&user)).await?;
+    ensure_can_post(&ctx.db, &board, &user).await?;
+    ensure_thread_open(&thread)?;
 
     let reply = Reply::create(
         &ctx.db,
@@ -206,6 +999,15 @@ async fn create_reply(
         req.pending_image_key,
     ).await?;
 
+    if reply.image_key.is_some() {
+        ImageVariantsWorker::enqueue(&ctx, reply.id).await;
+    }
+
+    let mentioned = MentionEntity::user_ids_for_reply(&ctx.db, reply.id).await?;
+    notify_participants(&ctx, &board, &thread, &reply, &user, &mentioned).await;
+    notify_mentioned(&ctx, &board, &thread, &reply, &user, mentioned.clone()).await;
+    email_immediate_subscribers(&ctx, &board, &thread, &reply, &user, &mentioned).await;
+
     let response = CreateReplyResponse {
         reply_id: reply.id,
     };
@@ -216,13 +1018,11 @@ async fn create_reply(
 /// Update an existing reply
 #[debug_handler]
 async fn update_reply(
-    auth: auth::JWT,
+    CurrentUser(user): CurrentUser,
     Path((_board_id, _thread_id, reply_id)): Path<(i32, i32, i32)>,
     State(ctx): State<AppContext>,
     Json(req): Json<UpdateReplyRequest>,
 ) -> Result<Response> {
-    let user = crate::models::users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
-
     // First, find the reply and verify ownership
     let reply = ReplyEntity::find_by_id(reply_id)
         .one(&ctx.db)
@@ -234,73 +1034,108 @@ async fn update_reply(
         return Err(loco_rs::Error::Unauthorized("You can only edit your own replies".to_string()));
     }
 
-    assert!(
-        !(req.remove_image && req.pending_image_key.is_some()),
-        "Cannot both remove image and attach a new image"
-    );
+    let thread = ThreadEntity::find_by_id(reply.thread_id)
+        .one(&ctx.db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    let board = find_visible_board(&ctx.db, thread.board_id, Some(&user)).await?;
+    ensure_thread_not_archived(&thread)?;
 
-    // Handle image changes
-    let new_image_key = if req.remove_image {
-        if let Some(ref key) = reply.image_key {
-            let _ = crate::s3::delete_image(key).await;
-        }
-        Some(None)
+    if req.remove_image && req.pending_image_key.is_some() {
+        return bad_request("Cannot both remove image and attach a new image");
+    }
+
+    // Handle image changes. A new image is checked before anything else, so a
+    // rejected upload leaves the reply as it was. The old image is only
+    // deleted once the edit is committed.
+    let (new_image, stale_keys) = if req.remove_image {
+        (Some(None), reply.image_keys())
     } else if let Some(pending_key) = req.pending_image_key {
-        if let Some(ref old_key) = reply.image_key {
-            let _ = crate::s3::delete_image(old_key).await;
-        }
-        match crate::s3::move_pending_to_reply(&pending_key, reply_id).await {
-            Ok(final_key) => Some(Some(final_key)),
-            Err(e) => {
-                tracing::error!("Failed to move image from pending: {e}");
-                None
-            }
-        }
+        let image = crate::image_storage::get().move_pending_to_reply(&pending_key, reply_id).await?;
+        // The original keeps its key when the extension didn't change, in
+        // which case the new image has already replaced it
+        let stale_keys = reply.image_keys().into_iter().filter(|key| *key != image.key).collect();
+        (Some(Some(image)), stale_keys)
     } else {
-        None
+        (None, Vec::new())
     };
 
-    use sea_orm::{ActiveModelTrait, Set};
+    use sea_orm::{ActiveModelTrait, Set, TransactionTrait};
+    let txn = ctx.db.begin().await?;
+    // Only changes to the text are kept in the history; an image that's
+    // swapped out is deleted
+    let body_changed = reply.body != req.body;
+    if body_changed {
+        reply_revisions::Model::record(&txn, &reply, user.id).await?;
+    }
+
+    let edit_count = reply.edit_count;
     let mut active_reply: crate::models::replies::ActiveModel = reply.into();
     active_reply.body = Set(req.body);
-    if let Some(image_key) = new_image_key {
-        active_reply.image_key = Set(image_key);
+    if body_changed {
+        active_reply.edit_count = Set(edit_count + 1);
+    }
+    let has_new_image = matches!(new_image, Some(Some(_)));
+    if let Some(image) = new_image {
+        active_reply.set_image(image);
+    }
+    let reply = active_reply.update(&txn).await?;
+    let mentioned = MentionEntity::sync(&txn, &reply).await?;
+    txn.commit().await?;
+
+    for key in stale_keys {
+        let _ = crate::image_storage::get().delete(&key).await;
+    }
+
+    notify_mentioned(&ctx, &board, &thread, &reply, &user, mentioned).await;
+
+    if has_new_image {
+        ImageVariantsWorker::enqueue(&ctx, reply.id).await;
     }
-    active_reply.update(&ctx.db).await?;
 
     format::json(serde_json::json!({"success": true}))
 }
 
-/// Delete or hide a reply based on action parameter
-#[debug_handler]
-async fn delete_reply(
-    auth: auth::JWT,
-    Path((_board_id, _thread_id, reply_id)): Path<(i32, i32, i32)>,
-    State(ctx): State<AppContext>,
-    Json(req): Json<DeleteReplyRequest>,
-) -> Result<Response> {
-    let user = crate::models::users::Model::find_by_pid(&ctx.db, &auth.claims.pid).await?;
+/// Lets the user whose thread or reply `action` was taken against know.
+/// Called once the action is committed; failures are logged rather than
+/// failing the action.
+pub(crate) async fn notify_mod_action(db: &DatabaseConnection, action: &mod_actions::Model) {
+    if let Err(err) = notifications::Model::notify_mod_action(db, action).await {
+        tracing::error!(mod_action_id = action.id, "Failed to notify about moderator action: {err}");
+    }
+}
 
-    // First, find the reply
-    let reply = ReplyEntity::find_by_id(reply_id)
-        .one(&ctx.db)
+/// Hides or deletes `reply` on behalf of `user`, checking that they're allowed
+/// to and logging the change when it's a moderator action. `action` is
+/// "delete" or "hide". Shared by `delete_reply` and the moderation report queue.
+///
+/// Everything goes through `txn`, so the caller commits the change together with
+/// anything else it does. The logged action is returned so the caller can
+/// notify the poster with `notify_mod_action` once it's committed.
+pub(crate) async fn apply_reply_action<C: ConnectionTrait>(
+    txn: &C,
+    user: &crate::models::users::Model,
+    reply: Reply,
+    action: &str,
+    reason: Option<String>,
+) -> Result<(Reply, Option<mod_actions::Model>)> {
+    let thread = ThreadEntity::find_by_id(reply.thread_id)
+        .one(txn)
         .await?
         .ok_or_else(|| loco_rs::Error::NotFound)?;
 
     // Determine the required permission based on action
-    let new_status = match req.action.as_str() {
+    let new_status = match action {
         "delete" => {
-            // Check if the user owns this reply or is a moderator
-            if reply.poster != user.id && !user.is_moderator {
-                return Err(loco_rs::Error::Unauthorized("You can only delete your own replies or must be a moderator".to_string()));
+            // Anyone can delete their own replies
+            if reply.poster != user.id {
+                permissions::ensure(txn, user, Permission::DeleteReply, Some(thread.board_id)).await?;
             }
             3 // deleted status
         },
         "hide" => {
-            // Only moderators can hide replies
-            if !user.is_moderator {
-                return Err(loco_rs::Error::Unauthorized("Only moderators can hide replies".to_string()));
-            }
+            permissions::ensure(txn, user, Permission::HideReply, Some(thread.board_id)).await?;
             2 // hidden status
         },
         _ => {
@@ -308,23 +1143,384 @@ async fn delete_reply(
         }
     };
 
+    // Hiding is always a moderator action; deleting is one only when the
+    // reply belongs to someone else
+    let is_moderator_action = new_status == 2 || reply.poster != user.id;
+    let previous_state = serde_json::to_value(&reply).ok();
+    let poster = reply.poster;
+
     // Update the reply status
     use sea_orm::{ActiveModelTrait, Set};
     let mut active_reply: crate::models::replies::ActiveModel = reply.into();
     active_reply.reply_status = Set(new_status);
-    active_reply.update(&ctx.db).await?;
+    let reply = active_reply.update(txn).await?;
+
+    let mut action = None;
+    if is_moderator_action {
+        action = Some(mod_actions::Model::record(txn, NewModAction {
+            actor_id: user.id,
+            action: if new_status == 2 { ModAction::HideReply } else { ModAction::DeleteReply },
+            target: ModTarget::Reply(reply.id),
+            target_user_id: Some(poster),
+            board_id: Some(thread.board_id),
+            reason,
+            previous_state,
+        }).await?);
+    }
+
+    Ok((reply, action))
+}
+
+/// Delete or hide a reply based on action parameter
+#[debug_handler]
+async fn delete_reply(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, _thread_id, reply_id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<DeleteReplyRequest>,
+) -> Result<Response> {
+    // First, find the reply
+    let reply = ReplyEntity::find_by_id(reply_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+
+    let txn = ctx.db.begin().await?;
+    let (_, action) = apply_reply_action(&txn, &user, reply, &req.action, req.reason).await?;
+    txn.commit().await?;
+    if let Some(action) = action {
+        notify_mod_action(&ctx.db, &action).await;
+    }
 
     format::json(serde_json::json!({"success": true}))
 }
 
+/// Report a reply to the moderators
+#[debug_handler]
+async fn report_reply(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, _thread_id, reply_id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+    JsonValidateWithMessage(params): JsonValidateWithMessage<CreateReportParams>,
+) -> Result<Response> {
+    let reply = ReplyEntity::find_by_id(reply_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    let thread = ThreadEntity::find_by_id(reply.thread_id)
+        .one(&ctx.db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    find_visible_board(&ctx.db, thread.board_id, Some(&user)).await?;
+    // Hidden and deleted replies have already been dealt with
+    if reply.reply_status != 1 /* live */ {
+        return bad_request("This reply has already been removed");
+    }
+
+    let report = match reports::Model::create(&ctx.db, reply_id, user.id, &params).await {
+        Ok(report) => report,
+        Err(ModelError::EntityNotFound) => return Err(loco_rs::Error::NotFound),
+        Err(ModelError::EntityAlreadyExists) => {
+            return bad_request("You have already reported this reply");
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    format::json(CreateReportResponse { report_id: report.id })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplyRevisionsResponse {
+    pub reply_id: i32,
+    pub edit_count: i32,
+    /// Oldest first, ending with the current text
+    pub versions: Vec<ReplyVersion>,
+}
+
+/// List every version of a reply with what changed in each. Moderators of
+/// the board can always see them, and so can the poster. Everyone else can
+/// only when the board makes edit history public, and only for live replies.
+#[debug_handler]
+async fn get_reply_revisions(
+    MaybeCurrentUser(user): MaybeCurrentUser,
+    Path((_board_id, _thread_id, reply_id)): Path<(i32, i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let reply = ReplyEntity::find_by_id(reply_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    let thread = ThreadEntity::find_by_id(reply.thread_id)
+        .one(&ctx.db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    let board = find_visible_board(&ctx.db, thread.board_id, user.as_ref()).await?;
+
+    let is_moderator = match &user {
+        Some(user) => permissions::moderates_board(&ctx.db, user, board.id).await?,
+        None => false,
+    };
+    let is_poster = user.as_ref().is_some_and(|user| user.id == reply.poster);
+    if !is_moderator {
+        if reply.reply_status != 1 /* live */ {
+            return Err(loco_rs::Error::NotFound);
+        }
+        if !is_poster && !board.revisions_public {
+            return Err(Error::CustomError(
+                StatusCode::FORBIDDEN,
+                ErrorDetail::new("revisions_hidden", "Only moderators can see the edit history of replies on this board"),
+            ));
+        }
+    }
+
+    let versions = reply_revisions::Entity::versions(&ctx.db, &reply).await?;
+
+    format::json(ReplyRevisionsResponse {
+        reply_id: reply.id,
+        edit_count: reply.edit_count,
+        versions,
+    })
+}
+
+/// List the members of a board (moderators of the board only)
+#[debug_handler]
+async fn list_members(
+    CurrentUser(user): CurrentUser,
+    Path(board_id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageMembers, Some(board_id)).await?;
+
+    BoardEntity::find_by_id(board_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+
+    let members = BoardMemberEntity::find_for_board(&ctx.db, board_id).await?;
+    format::json(members)
+}
+
+/// Add a user to a board's members (moderators of the board only)
+#[debug_handler]
+async fn add_member(
+    CurrentUser(user): CurrentUser,
+    Path(board_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<AddBoardMemberRequest>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageMembers, Some(board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    match board_members::Model::add(&txn, board_id, req.user_id).await {
+        Ok(_) => {}
+        Err(ModelError::EntityNotFound) => return Err(loco_rs::Error::NotFound),
+        Err(ModelError::EntityAlreadyExists) => {
+            return bad_request("That user is already a member of this board");
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::AddBoardMember,
+        target: ModTarget::User(req.user_id),
+        target_user_id: Some(req.user_id),
+        board_id: Some(board_id),
+        reason: None,
+        previous_state: None,
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
+/// Remove a user from a board's members (moderators of the board only)
+#[debug_handler]
+async fn remove_member(
+    CurrentUser(user): CurrentUser,
+    Path((board_id, user_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageMembers, Some(board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    board_members::Model::remove(&txn, board_id, user_id)
+        .await
+        .map_err(|err| match err {
+            ModelError::EntityNotFound => loco_rs::Error::NotFound,
+            err => err.into(),
+        })?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::RemoveBoardMember,
+        target: ModTarget::User(user_id),
+        target_user_id: Some(user_id),
+        board_id: Some(board_id),
+        reason: None,
+        previous_state: None,
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
+/// List the moderators of a board
+#[debug_handler]
+async fn list_moderators(
+    CurrentUser(user): CurrentUser,
+    Path(board_id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, Some(board_id)).await?;
+
+    BoardEntity::find_by_id(board_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+
+    let moderators = BoardModeratorEntity::find_for_board(&ctx.db, board_id).await?;
+    format::json(moderators)
+}
+
+/// Make a user a moderator of a board
+#[debug_handler]
+async fn add_moderator(
+    CurrentUser(user): CurrentUser,
+    Path(board_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<AddBoardModeratorRequest>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, Some(board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    match board_moderators::Model::add(&txn, board_id, req.user_id).await {
+        Ok(_) => {}
+        Err(ModelError::EntityNotFound) => return Err(loco_rs::Error::NotFound),
+        Err(ModelError::EntityAlreadyExists) => {
+            return bad_request("That user already moderates this board");
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::AddBoardModerator,
+        target: ModTarget::User(req.user_id),
+        target_user_id: Some(req.user_id),
+        board_id: Some(board_id),
+        reason: None,
+        previous_state: None,
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
+/// Stop a user from moderating a board
+#[debug_handler]
+async fn remove_moderator(
+    CurrentUser(user): CurrentUser,
+    Path((board_id, user_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ManageBoard, Some(board_id)).await?;
+
+    let txn = ctx.db.begin().await?;
+    board_moderators::Model::remove(&txn, board_id, user_id)
+        .await
+        .map_err(|err| match err {
+            ModelError::EntityNotFound => loco_rs::Error::NotFound,
+            err => err.into(),
+        })?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: user.id,
+        action: ModAction::RemoveBoardModerator,
+        target: ModTarget::User(user_id),
+        target_user_id: Some(user_id),
+        board_id: Some(board_id),
+        reason: None,
+        previous_state: None,
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
+/// Loads a thread that hasn't been deleted, checking that `user` can see it
+async fn find_visible_thread(db: &DatabaseConnection, thread_id: i32, user: &User) -> Result<ThreadModel> {
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    find_visible_board(db, thread.board_id, Some(user)).await?;
+    Ok(thread)
+}
+
+/// Whether the current user follows a thread
+#[debug_handler]
+async fn get_subscription(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let thread = find_visible_thread(&ctx.db, thread_id, &user).await?;
+    let subscription = ThreadSubscriptionEntity::find_subscription(&ctx.db, thread.id, user.id).await?;
+
+    format::json(SubscriptionResponse { subscribed: subscription.is_some() })
+}
+
+/// Follow a thread, to be told about new replies in it. Users follow the
+/// threads they start or reply to without asking.
+#[debug_handler]
+async fn subscribe(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let thread = find_visible_thread(&ctx.db, thread_id, &user).await?;
+    ThreadSubscriptionEntity::subscribe(&ctx.db, thread.id, user.id).await?;
+
+    format::json(SubscriptionResponse { subscribed: true })
+}
+
+/// Stop following a thread
+#[debug_handler]
+async fn unsubscribe(
+    CurrentUser(user): CurrentUser,
+    Path((_board_id, thread_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    // Deleted and hidden threads can still be left
+    ThreadSubscriptionEntity::unsubscribe(&ctx.db, thread_id, user.id).await?;
+
+    format::json(SubscriptionResponse { subscribed: false })
+}
+
 #[debug_handler]
 pub async fn find_reply_page(
+    MaybeCurrentUser(user): MaybeCurrentUser,
     Path((_board_id, thread_id)): Path<(i32, i32)>,
     Query(query): Query<FindReplyPageQuery>,
     State(ctx): State<AppContext>,
 ) -> Result<Json<FindReplyPageResponse>> {
     use sea_orm::{EntityTrait, QueryFilter, ColumnTrait, PaginatorTrait};
 
+    let thread = ThreadEntity::find_by_id(thread_id)
+        .one(&ctx.db)
+        .await?
+        .filter(|thread| !thread.is_deleted)
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    find_visible_board(&ctx.db, thread.board_id, user.as_ref()).await?;
+
     // First verify the reply exists in this thread
     let reply = ReplyEntity::find()
         .filter(crate::models::replies::Column::Id.eq(query.reply_id))
@@ -353,12 +1549,34 @@ pub fn routes() -> Routes {
     Routes::new()
         .prefix("/api/boards/")
         .add("/", get(list))
+        .add("/tree", get(tree))
+        .add("/", post(create_board))
+        .add("/reorder", post(reorder_boards))
+        .add("/{id}", patch(update_board))
+        .add("/{id}", delete(delete_board))
+        .add("/{id}/members", get(list_members))
+        .add("/{id}/members", post(add_member))
+        .add("/{board_id}/members/{user_id}", delete(remove_member))
+        .add("/{id}/moderators", get(list_moderators))
+        .add("/{id}/moderators", post(add_moderator))
+        .add("/{board_id}/moderators/{user_id}", delete(remove_moderator))
         .add("/{id}/threads", get(get_threads))
         .add("/{id}/threads", post(create_thread))
         .add("/{board_id}/threads/{thread_id}", delete(delete_thread))
+        .add("/{board_id}/threads/{thread_id}", patch(update_thread_state))
+        .add("/{board_id}/threads/{thread_id}/restore", post(restore_thread))
+        .add("/{board_id}/threads/{thread_id}/move", post(move_thread))
+        .add("/{board_id}/threads/{thread_id}/merge", post(merge_thread))
+        .add("/{board_id}/threads/{thread_id}/split", post(split_thread))
+        .add("/{board_id}/threads/{thread_id}/purge", delete(purge_thread))
+        .add("/{board_id}/threads/{thread_id}/subscription", get(get_subscription))
+        .add("/{board_id}/threads/{thread_id}/subscription", post(subscribe))
+        .add("/{board_id}/threads/{thread_id}/subscription", delete(unsubscribe))
         .add("/{board_id}/threads/{thread_id}/replies", get(get_replies))
         .add("/{board_id}/threads/{thread_id}/replies", post(create_reply))
         .add("/{board_id}/threads/{thread_id}/replies/find_page", get(find_reply_page))
         .add("/{board_id}/threads/{thread_id}/replies/{reply_id}", patch(update_reply))
         .add("/{board_id}/threads/{thread_id}/replies/{reply_id}", delete(delete_reply))
+        .add("/{board_id}/threads/{thread_id}/replies/{reply_id}/report", post(report_reply))
+        .add("/{board_id}/threads/{thread_id}/replies/{reply_id}/revisions", get(get_reply_revisions))
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+};
+use loco_rs::{controller::ErrorDetail, prelude::*};
+
+use crate::models::{bans, users};
+
+/// The user making an authenticated request, identified either by the JWT
+/// (from the location configured under `auth.jwt`) or, for bots and scripts,
+/// by an `Authorization: Bearer <api_key>` header.
+///
+/// Banned users are rejected, so every handler taking a `CurrentUser` is
+/// closed to them.
+pub struct CurrentUser(pub users::Model);
+
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let ctx = AppContext::from_ref(state);
+
+        let user = match auth::JWTWithUser::<users::Model>::from_request_parts(parts, state).await {
+            Ok(jwt) => jwt.user,
+            Err(_) => auth::ApiToken::<users::Model>::from_request_parts(parts, state).await?.user,
+        };
+
+        Ok(Self(ensure_not_banned(&ctx.db, user).await?))
+    }
+}
+
+/// Lifts the user's ban if it has expired, and otherwise rejects banned users
+/// with a 403 that includes the reason for their ban.
+pub(crate) async fn ensure_not_banned(
+    db: &DatabaseConnection,
+    user: users::Model,
+) -> Result<users::Model> {
+    let user = user.lift_ban_if_expired(db).await?;
+    if !user.is_banned {
+        return Ok(user);
+    }
+
+    let message = match bans::Entity::find_active_for_user(db, user.id).await? {
+        Some(ban) => match ban.expires_at {
+            Some(expires_at) => format!("Account has been banned until {}: {}", expires_at.to_rfc3339(), ban.reason),
+            None => format!("Account has been banned: {}", ban.reason),
+        },
+        None => "Account has been banned".to_string(),
+    };
+    Err(Error::CustomError(StatusCode::FORBIDDEN, ErrorDetail::new("banned", &message)))
+}
+
+/// Like `CurrentUser`, but for endpoints that anonymous visitors can use too.
+/// Requests without valid credentials, or from banned users, are treated as
+/// anonymous rather than rejected.
+pub struct MaybeCurrentUser(pub Option<users::Model>);
+
+impl<S> FromRequestParts<S> for MaybeCurrentUser
+where
+    AppContext: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        Ok(Self(CurrentUser::from_request_parts(parts, state).await.ok().map(|user| user.0)))
+    }
+}
@@ -1,9 +1,11 @@
 use axum::{debug_handler, extract::Query, response::Redirect};
-use loco_rs::{environment, prelude::cookie::*, prelude::*};
+use loco_rs::{prelude::cookie::*, prelude::*};
 use serde::Deserialize;
-use time::Duration;
 
-use crate::models::users;
+use crate::{
+    controllers::{auth::set_login_cookies, extractors::ensure_not_banned},
+    models::users,
+};
 
 #[derive(Debug, Deserialize)]
 struct GoogleOAuthConfig {
@@ -124,40 +126,9 @@ async fn google_callback(
     )
     .await?;
 
-    // Check if user is banned
-    if user.is_banned {
-        return Err(Error::Message("Account has been banned".to_string()));
-    }
+    let user = ensure_not_banned(&ctx.db, user).await?;
 
-    // Generate JWT
-    let jwt_secret = ctx.config.get_jwt_config()?;
-    let token = user
-        .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
-        .map_err(|_| Error::Message("Failed to generate token".to_string()))?;
-
-    // Set cookies
-    let is_development = ctx.environment == environment::Environment::Development;
-
-    let jwt_cookie = Cookie::build(("jwt", token.clone()))
-        .secure(!is_development)
-        .same_site(if is_development { SameSite::Lax } else { SameSite::Strict })
-        .max_age(Duration::days(7))
-        .path("/")
-        .http_only(true);
-
-    let username_cookie = Cookie::build(("username", user.name.clone()))
-        .secure(!is_development)
-        .same_site(if is_development { SameSite::Lax } else { SameSite::Strict })
-        .max_age(Duration::days(7))
-        .path("/")
-        .http_only(false);
-
-    let is_moderator_cookie = Cookie::build(("is_moderator", user.is_moderator.to_string()))
-        .secure(!is_development)
-        .same_site(if is_development { SameSite::Lax } else { SameSite::Strict })
-        .max_age(Duration::days(7))
-        .path("/")
-        .http_only(false);
+    let (jar, _token) = set_login_cookies(&ctx, jar, &user).await?;
 
     tracing::info!(
         user_pid = user.pid.to_string(),
@@ -165,10 +136,7 @@ async fn google_callback(
         "User logged in via Google OAuth"
     );
 
-    Ok((
-        jar.add(jwt_cookie).add(username_cookie).add(is_moderator_cookie),
-        Redirect::temporary("/"),
-    ))
+    Ok((jar, Redirect::temporary("/")))
 }
 
 pub fn routes() -> Routes {
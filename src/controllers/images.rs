@@ -1,9 +1,9 @@
-use axum::debug_handler;
+use axum::{body::Bytes, debug_handler, extract::DefaultBodyLimit, http::header};
 use loco_rs::prelude::*;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
-const ALLOWED_TYPES: &[&str] = &["image/jpeg", "image/png", "image/gif", "image/webp"];
+use crate::controllers::extractors::CurrentUser;
+use crate::image_storage;
 
 #[derive(Deserialize)]
 struct PresignRequest {
@@ -18,54 +18,84 @@ struct PresignResponse {
 
 #[debug_handler]
 async fn presign_upload(
-    _auth: auth::JWT,
+    CurrentUser(_user): CurrentUser,
     State(_ctx): State<AppContext>,
     Json(req): Json<PresignRequest>,
 ) -> Result<Response> {
-    if !ALLOWED_TYPES.contains(&req.content_type.as_str()) {
+    let Some(ext) = image_storage::extension_for(&req.content_type) else {
         return Err(loco_rs::Error::BadRequest(format!(
             "Unsupported content type: {}. Allowed: jpeg, png, gif, webp",
             req.content_type
         )));
-    }
-
-    let ext = match req.content_type.as_str() {
-        "image/jpeg" => "jpg",
-        "image/png" => "png",
-        "image/gif" => "gif",
-        "image/webp" => "webp",
-        _ => unreachable!(),
     };
 
-    let pending_key = format!("pending/{}.{}", Uuid::new_v4(), ext);
-    let bucket = crate::s3::images_bucket();
-    let client = crate::s3::get_client().await;
-
-    let presign_config = aws_sdk_s3::presigning::PresigningConfig::builder()
-        .expires_in(std::time::Duration::from_secs(600))
-        .build()
-        .expect("valid presigning config");
-
-    let presigned = client
-        .put_object()
-        .bucket(&bucket)
-        .key(&pending_key)
-        .content_type(&req.content_type)
-        .presigned(presign_config)
+    let pending_key = image_storage::new_pending_key(ext);
+    let upload_url = image_storage::get()
+        .presign_upload(&pending_key, &req.content_type)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to generate presigned URL: {e}");
+            tracing::error!("Failed to generate upload URL: {e}");
             loco_rs::Error::InternalServerError
         })?;
 
     format::json(PresignResponse {
-        upload_url: presigned.uri().to_string(),
+        upload_url,
         pending_key,
     })
 }
 
+/// Receives an upload for stores that don't take uploads directly, such as
+/// local disk. The pending key in the path is what authorizes the upload, the
+/// same way a presigned S3 URL would.
+#[debug_handler]
+async fn upload(
+    Path(pending_key): Path<String>,
+    State(_ctx): State<AppContext>,
+    body: Bytes,
+) -> Result<Response> {
+    image_storage::get()
+        .receive_upload(&pending_key, &body)
+        .await
+        .map_err(loco_rs::Error::BadRequest)?;
+
+    format::empty()
+}
+
+/// Serves an attached image for stores that don't serve them themselves
+#[debug_handler]
+async fn serve_file(Path(key): Path<String>, State(_ctx): State<AppContext>) -> Result<Response> {
+    // Pending uploads aren't public until they're attached to a reply
+    if !key.starts_with("replies/") {
+        return Err(loco_rs::Error::NotFound);
+    }
+
+    let ext = key.rsplit('.').next().unwrap_or_default();
+    let Some(content_type) = image_storage::content_type_for(ext) else {
+        return Err(loco_rs::Error::NotFound);
+    };
+
+    let bytes = image_storage::get()
+        .read(&key)
+        .await
+        .map_err(|_| loco_rs::Error::NotFound)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
 pub fn routes() -> Routes {
     Routes::new()
         .prefix("/api/images")
         .add("/presign", post(presign_upload))
+        .add(
+            "/upload/{*pending_key}",
+            put(upload).layer(DefaultBodyLimit::max(image_storage::MAX_UPLOAD_BYTES)),
+        )
+        .add("/files/{*key}", get(serve_file))
 }
@@ -1,6 +1,10 @@
 pub mod auth;
+pub mod board_categories;
 pub mod boards;
+pub mod extractors;
 pub mod google_auth;
 pub mod images;
+pub mod moderation;
+pub mod notifications;
 pub mod search;
-pub mod users;
\ No newline at end of file
+pub mod users;
@@ -0,0 +1,157 @@
+use crate::controllers::{
+    boards::{apply_reply_action, notify_mod_action},
+    extractors::CurrentUser,
+};
+use crate::permissions::{self, Permission};
+use crate::models::{
+    mod_actions::{self, ModAction, ModActionFilter, ModActionResponse, ModTarget, NewModAction},
+    replies::Entity as ReplyEntity,
+    reports::{self, ReportResolution, ReportedReply},
+    threads::Entity as ThreadEntity,
+};
+use axum::{debug_handler, extract::{Path, Query}};
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct ModActionsQuery {
+    page_size: Option<u64>,
+    page_number: Option<u64>,
+    actor_id: Option<i32>,
+    target_user_id: Option<i32>,
+    board_id: Option<i32>,
+    action: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ModActionsResponse {
+    pub actions: Vec<ModActionResponse>,
+    pub total_count: u64,
+}
+
+#[derive(Deserialize)]
+struct ReportsQuery {
+    page_size: Option<u64>,
+    page_number: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReportsResponse {
+    pub replies: Vec<ReportedReply>,
+    pub total_count: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResolveReportsRequest {
+    pub action: String, // "hide", "delete" or "dismiss"
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResolveReportsResponse {
+    pub resolved: u64,
+}
+
+/// Browse the moderation log, newest first (moderator only)
+#[debug_handler]
+async fn list_actions(
+    CurrentUser(user): CurrentUser,
+    Query(params): Query<ModActionsQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ReviewReports, None).await?;
+
+    let page_size = params.page_size.unwrap_or(10).clamp(1, 100); // Default 10, max 100
+    let page_number = params.page_number.unwrap_or(0);
+
+    let filter = ModActionFilter {
+        actor_id: params.actor_id,
+        target_user_id: params.target_user_id,
+        board_id: params.board_id,
+        action: params.action,
+    };
+
+    let (actions, total_count) =
+        mod_actions::Entity::find_paginated(&ctx.db, &filter, page_size, page_number).await?;
+
+    format::json(ModActionsResponse { actions, total_count })
+}
+
+/// List replies with open reports, oldest report first (moderator only)
+#[debug_handler]
+async fn list_reports(
+    CurrentUser(user): CurrentUser,
+    Query(params): Query<ReportsQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ReviewReports, None).await?;
+
+    let page_size = params.page_size.unwrap_or(10).clamp(1, 100); // Default 10, max 100
+    let page_number = params.page_number.unwrap_or(0);
+
+    let (replies, total_count) =
+        reports::Entity::find_open_grouped(&ctx.db, page_size, page_number).await?;
+
+    format::json(ReportsResponse { replies, total_count })
+}
+
+/// Close every open report on a reply by hiding or deleting the reply, or
+/// by dismissing the reports (moderator only)
+#[debug_handler]
+async fn resolve_reports(
+    CurrentUser(user): CurrentUser,
+    Path(reply_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<ResolveReportsRequest>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &user, Permission::ReviewReports, None).await?;
+
+    let reply = ReplyEntity::find_by_id(reply_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+
+    let resolution = match req.action.as_str() {
+        "hide" => ReportResolution::Hidden,
+        "delete" => ReportResolution::Deleted,
+        "dismiss" => ReportResolution::Dismissed,
+        _ => return bad_request("Invalid action. Must be 'hide', 'delete' or 'dismiss'"),
+    };
+
+    let resolved = if resolution == ReportResolution::Dismissed {
+        let thread = ThreadEntity::find_by_id(reply.thread_id).one(&ctx.db).await?;
+        let txn = ctx.db.begin().await?;
+        mod_actions::Model::record(&txn, NewModAction {
+            actor_id: user.id,
+            action: ModAction::DismissReports,
+            target: ModTarget::Reply(reply.id),
+            target_user_id: Some(reply.poster),
+            board_id: thread.map(|thread| thread.board_id),
+            reason: req.reason,
+            previous_state: None,
+        }).await?;
+        let resolved = reports::Entity::resolve_for_reply(&txn, reply_id, user.id, resolution).await?;
+        txn.commit().await?;
+        resolved
+    } else {
+        let txn = ctx.db.begin().await?;
+        let (_, action) = apply_reply_action(&txn, &user, reply, &req.action, req.reason).await?;
+        let resolved = reports::Entity::resolve_for_reply(&txn, reply_id, user.id, resolution).await?;
+        txn.commit().await?;
+        if let Some(action) = action {
+            notify_mod_action(&ctx.db, &action).await;
+        }
+        resolved
+    };
+
+    format::json(ResolveReportsResponse { resolved })
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/moderation")
+        .add("/actions", get(list_actions))
+        .add("/reports", get(list_reports))
+        .add("/reports/{reply_id}/resolve", post(resolve_reports))
+}
@@ -0,0 +1,92 @@
+use crate::controllers::extractors::CurrentUser;
+use crate::models::notifications::{self, NotificationResponse};
+use axum::{debug_handler, extract::{Path, Query}};
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct NotificationsQuery {
+    page_size: Option<u64>,
+    page_number: Option<u64>,
+    #[serde(default)]
+    unread_only: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NotificationsResponse {
+    pub notifications: Vec<NotificationResponse>,
+    pub total_count: u64,
+    pub unread_count: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UnreadCountResponse {
+    pub unread_count: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MarkAllReadResponse {
+    pub marked: u64,
+}
+
+/// List the current user's notifications, newest first
+#[debug_handler]
+async fn list(
+    CurrentUser(user): CurrentUser,
+    Query(params): Query<NotificationsQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let page_size = params.page_size.unwrap_or(20).clamp(1, 100); // Default 20, max 100
+    let page_number = params.page_number.unwrap_or(0);
+
+    let (notifications, total_count) =
+        notifications::Entity::find_paginated(&ctx.db, user.id, params.unread_only, page_size, page_number).await?;
+    let unread_count = notifications::Entity::unread_count(&ctx.db, user.id).await?;
+
+    format::json(NotificationsResponse { notifications, total_count, unread_count })
+}
+
+/// Count the current user's unread notifications
+#[debug_handler]
+async fn unread_count(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let unread_count = notifications::Entity::unread_count(&ctx.db, user.id).await?;
+
+    format::json(UnreadCountResponse { unread_count })
+}
+
+/// Mark one of the current user's notifications as read
+#[debug_handler]
+async fn mark_read(
+    CurrentUser(user): CurrentUser,
+    Path(notification_id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    match notifications::Entity::mark_read(&ctx.db, user.id, notification_id).await {
+        Ok(_) => format::json(serde_json::json!({"success": true})),
+        Err(ModelError::EntityNotFound) => Err(loco_rs::Error::NotFound),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Mark all of the current user's notifications as read
+#[debug_handler]
+async fn mark_all_read(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let marked = notifications::Entity::mark_all_read(&ctx.db, user.id).await?;
+
+    format::json(MarkAllReadResponse { marked })
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("/api/notifications")
+        .add("/", get(list))
+        .add("/unread_count", get(unread_count))
+        .add("/read_all", post(mark_all_read))
+        .add("/{notification_id}/read", post(mark_read))
+}
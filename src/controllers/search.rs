@@ -1,15 +1,54 @@
-use crate::models::replies::Entity as ReplyEntity;
+use crate::controllers::extractors::MaybeCurrentUser;
+use crate::models::boards::Entity as BoardEntity;
+use crate::models::search::{self, SearchFilter, SearchHit, SearchReplyResult, SearchThreadResult};
+use crate::models::users;
 use axum::extract::Query;
 use loco_rs::prelude::*;
-use sea_orm::{QueryFilter, QuerySelect, JoinType, ColumnTrait, RelationTrait};
-use sea_orm::prelude::Expr;
 use serde::{Deserialize, Serialize};
 
+/// Furthest into the results a search can page. Deeper pages get slower,
+/// especially for combined searches, and nobody reads that far.
+const MAX_OFFSET: u64 = 10_000;
+
 #[derive(Deserialize)]
 pub struct SearchQuery {
     q: String, // search term
     #[serde(default = "default_limit")]
     limit: u64, // optional limit, default 50
+    #[serde(default)]
+    offset: u64,
+    board_id: Option<i32>,
+    thread_id: Option<i32>,
+    author_id: Option<i32>,
+    /// First day to include, as YYYY-MM-DD
+    from: Option<chrono::NaiveDate>,
+    /// Last day to include, as YYYY-MM-DD
+    to: Option<chrono::NaiveDate>,
+    has_image: Option<bool>,
+}
+
+impl SearchQuery {
+    fn filter(&self) -> SearchFilter {
+        SearchFilter {
+            board_id: self.board_id,
+            thread_id: self.thread_id,
+            author_id: self.author_id,
+            from: self.from,
+            to: self.to,
+            has_image: self.has_image,
+        }
+    }
+
+    fn limit(&self) -> u64 {
+        self.limit.clamp(1, 100)
+    }
+
+    fn offset(&self) -> Result<u64> {
+        if self.offset > MAX_OFFSET {
+            return Err(Error::BadRequest(format!("offset can't be more than {MAX_OFFSET}")));
+        }
+        Ok(self.offset)
+    }
 }
 
 fn default_limit() -> u64 {
@@ -17,80 +56,87 @@ fn default_limit() -> u64 {
 }
 
 #[derive(Serialize)]
-pub struct SearchReplyResult {
-    pub reply_id: i32,
-    pub reply_body: String,
-    pub thread_id: i32,
-    pub thread_title: String,
-    pub board_id: i32,
-    pub board_title: String,
-    pub poster_id: i32,
-    pub poster_name: String,
+pub struct SearchResponse<T> {
+    pub results: Vec<T>,
+    /// Every match, not just the ones on this page
+    pub total_found: u64,
 }
 
-#[derive(Serialize)]
-pub struct SearchResponse {
-    pub results: Vec<SearchReplyResult>,
-    pub total_found: usize,
+impl<T> SearchResponse<T> {
+    fn empty() -> Self {
+        Self {
+            results: vec![],
+            total_found: 0,
+        }
+    }
 }
 
-/// Search replies by text content
+/// Ids of the boards `user` can search
+async fn visible_board_ids(db: &DatabaseConnection, user: Option<&users::Model>) -> Result<Vec<i32>> {
+    let board_ids = BoardEntity::find_visible(db, user)
+        .await?
+        .into_iter()
+        .map(|board| board.id)
+        .collect();
+    Ok(board_ids)
+}
+
+/// Full-text search over live replies in the boards the current user can
+/// see, most relevant first
 pub async fn search_replies(
+    MaybeCurrentUser(user): MaybeCurrentUser,
     Query(params): Query<SearchQuery>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
     if params.q.trim().is_empty() {
-        return format::json(SearchResponse {
-            results: vec![],
-            total_found: 0,
-        });
+        return format::json(SearchResponse::<SearchReplyResult>::empty());
     }
 
-    // Build the search query with joins to get thread, board, and user info
-    let search_results = ReplyEntity::find()
-        .filter(crate::models::_entities::replies::Column::Body.contains(&params.q))
-        .filter(crate::models::_entities::replies::Column::ReplyStatus.eq(1)) // Only search live replies
-        .join(JoinType::InnerJoin, crate::models::_entities::replies::Relation::Threads.def())
-        .join(JoinType::InnerJoin, crate::models::_entities::replies::Relation::Users.def())
-        .join_as(
-            JoinType::InnerJoin,
-            crate::models::_entities::threads::Relation::Boards.def(),
-            "board"
-        )
-        .select_only()
-        .columns([
-            crate::models::_entities::replies::Column::Id,
-            crate::models::_entities::replies::Column::Body,
-            crate::models::_entities::replies::Column::ThreadId,
-        ])
-        .column_as(crate::models::_entities::threads::Column::Title, "thread_title")
-        .column_as(crate::models::_entities::threads::Column::BoardId, "board_id")
-        .column_as(Expr::col(("board", crate::models::_entities::boards::Column::Id)), "board_id_alt")
-        .column_as(Expr::col(("board", crate::models::_entities::boards::Column::Title)), "board_title")
-        .column_as(crate::models::_entities::replies::Column::Poster, "poster_id")
-        .column_as(crate::models::_entities::users::Column::Name, "poster_name")
-        .limit(params.limit)
-        .into_tuple::<(i32, String, i32, String, i32, i32, String, i32, String)>()
-        .all(&ctx.db)
-        .await?;
-
-    let results: Vec<SearchReplyResult> = search_results
-        .into_iter()
-        .map(|(reply_id, reply_body, thread_id, thread_title, board_id, _board_id_alt, board_title, poster_id, poster_name)| {
-            SearchReplyResult {
-                reply_id,
-                reply_body,
-                thread_id,
-                thread_title,
-                board_id,
-                board_title,
-                poster_id,
-                poster_name,
-            }
-        })
-        .collect();
+    let board_ids = visible_board_ids(&ctx.db, user.as_ref()).await?;
+    let (results, total_found) =
+        search::search_replies(&ctx.db, &params.q, board_ids, &params.filter(), params.limit(), params.offset()?).await?;
+
+    format::json(SearchResponse {
+        results,
+        total_found,
+    })
+}
+
+/// Full-text search over the titles of threads in the boards the current
+/// user can see, most relevant first
+pub async fn search_threads(
+    MaybeCurrentUser(user): MaybeCurrentUser,
+    Query(params): Query<SearchQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    if params.q.trim().is_empty() {
+        return format::json(SearchResponse::<SearchThreadResult>::empty());
+    }
 
-    let total_found = results.len();
+    let board_ids = visible_board_ids(&ctx.db, user.as_ref()).await?;
+    let (results, total_found) =
+        search::search_threads(&ctx.db, &params.q, board_ids, &params.filter(), params.limit(), params.offset()?).await?;
+
+    format::json(SearchResponse {
+        results,
+        total_found,
+    })
+}
+
+/// Search thread titles and replies at once. Each result is tagged with
+/// its `type`, "thread" or "reply", and the two are ranked together.
+pub async fn search_all(
+    MaybeCurrentUser(user): MaybeCurrentUser,
+    Query(params): Query<SearchQuery>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    if params.q.trim().is_empty() {
+        return format::json(SearchResponse::<SearchHit>::empty());
+    }
+
+    let board_ids = visible_board_ids(&ctx.db, user.as_ref()).await?;
+    let (results, total_found) =
+        search::search_all(&ctx.db, &params.q, board_ids, &params.filter(), params.limit(), params.offset()?).await?;
 
     format::json(SearchResponse {
         results,
@@ -101,5 +147,7 @@ pub async fn search_replies(
 pub fn routes() -> Routes {
     Routes::new()
         .prefix("/api/search/")
+        .add("/", get(search_all))
         .add("/replies", get(search_replies))
-}
\ No newline at end of file
+        .add("/threads", get(search_threads))
+}
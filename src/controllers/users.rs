@@ -1,4 +1,8 @@
-use crate::models::users::{Entity as UserEntity, Model as User};
+use crate::controllers::extractors::CurrentUser;
+use crate::models::mod_actions::{self, ModAction, ModTarget, NewModAction};
+use crate::models::thread_subscriptions::DigestFrequency;
+use crate::models::users::Entity as UserEntity;
+use crate::permissions::{self, Permission, Role};
 use axum::{debug_handler, extract::Path, Json};
 use loco_rs::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -8,16 +12,67 @@ struct UpdateUserRequest {
     name: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct SetRoleRequest {
+    /// "admin", "global_moderator" or "member"
+    pub role: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// What anyone can see about a user, such as someone following a mention
+#[derive(Serialize, Deserialize)]
+pub struct UserProfileResponse {
+    pub id: i32,
+    pub name: String,
+    pub is_banned: bool,
+    pub created_at: sea_orm::prelude::DateTimeWithTimeZone,
+}
+
+/// How often the user is emailed about new replies in threads they follow
+#[derive(Serialize, Deserialize)]
+pub struct DigestFrequencyRequest {
+    /// "immediate", "daily", "weekly" or "off"
+    pub frequency: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DigestFrequencyResponse {
+    pub frequency: DigestFrequency,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ApiKeyResponse {
+    pub api_key: String,
+}
+
+/// Get a user's public profile
+#[debug_handler]
+async fn get_user(
+    Path(user_id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let user = UserEntity::find_by_id(user_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+
+    format::json(UserProfileResponse {
+        id: user.id,
+        name: user.name,
+        is_banned: user.is_banned,
+        created_at: user.created_at,
+    })
+}
+
 /// Update a user's name
 #[debug_handler]
 async fn update_user(
-    auth: auth::JWT,
+    CurrentUser(current_user): CurrentUser,
     Path(user_id): Path<i32>,
     State(ctx): State<AppContext>,
     Json(req): Json<UpdateUserRequest>,
 ) -> Result<Response> {
-    let current_user = User::find_by_pid(&ctx.db, &auth.claims.pid).await?;
-    
     // Users can only update their own profile
     if current_user.id != user_id {
         return Err(loco_rs::Error::Unauthorized("You can only update your own profile".to_string()));
@@ -38,8 +93,97 @@ async fn update_user(
     format::json(serde_json::json!({"success": true}))
 }
 
+/// Get the current user's API key, for authenticating bots and scripts
+#[debug_handler(state = AppContext)]
+async fn get_api_key(CurrentUser(user): CurrentUser) -> Result<Response> {
+    format::json(ApiKeyResponse { api_key: user.api_key })
+}
+
+/// Replace the current user's API key with a new one, invalidating the old key
+#[debug_handler]
+async fn rotate_api_key(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let user = user.into_active_model().rotate_api_key(&ctx.db).await?;
+
+    tracing::info!(user_pid = user.pid.to_string(), "user rotated their api key");
+
+    format::json(ApiKeyResponse { api_key: user.api_key })
+}
+
+/// Get how often the current user is emailed about threads they follow
+#[debug_handler(state = AppContext)]
+async fn get_digest_frequency(CurrentUser(user): CurrentUser) -> Result<Response> {
+    format::json(DigestFrequencyResponse { frequency: user.digest_frequency() })
+}
+
+/// Change how often the current user is emailed about threads they follow
+#[debug_handler]
+async fn set_digest_frequency(
+    CurrentUser(user): CurrentUser,
+    State(ctx): State<AppContext>,
+    Json(req): Json<DigestFrequencyRequest>,
+) -> Result<Response> {
+    let Some(frequency) = DigestFrequency::parse(&req.frequency) else {
+        return bad_request("Frequency must be immediate, daily, weekly or off");
+    };
+
+    let user = user.into_active_model().set_digest_frequency(&ctx.db, frequency).await?;
+
+    format::json(DigestFrequencyResponse { frequency: user.digest_frequency() })
+}
+
+/// Change a user's global role (admin only)
+#[debug_handler]
+async fn set_role(
+    CurrentUser(current_user): CurrentUser,
+    Path(user_id): Path<i32>,
+    State(ctx): State<AppContext>,
+    Json(req): Json<SetRoleRequest>,
+) -> Result<Response> {
+    permissions::ensure(&ctx.db, &current_user, Permission::ManageRoles, None).await?;
+
+    let Some(role) = Role::parse(&req.role) else {
+        return bad_request("Role must be admin, global_moderator or member");
+    };
+    // Keeps the last admin from locking everyone out of role management
+    if current_user.id == user_id {
+        return bad_request("You can't change your own role");
+    }
+
+    let user = UserEntity::find_by_id(user_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::NotFound)?;
+    let previous_role = user.role.clone();
+
+    let txn = ctx.db.begin().await?;
+    user.into_active_model().set_role(&txn, role).await?;
+
+    mod_actions::Model::record(&txn, NewModAction {
+        actor_id: current_user.id,
+        action: ModAction::SetRole,
+        target: ModTarget::User(user_id),
+        target_user_id: Some(user_id),
+        board_id: None,
+        reason: req.reason,
+        previous_state: Some(serde_json::json!({ "role": previous_role })),
+    }).await?;
+
+    txn.commit().await?;
+
+    format::json(serde_json::json!({"success": true}))
+}
+
 pub fn routes() -> Routes {
     Routes::new()
         .prefix("/api/users/")
+        .add("/me/api_key", get(get_api_key))
+        .add("/me/api_key/rotate", post(rotate_api_key))
+        .add("/me/digest_frequency", get(get_digest_frequency))
+        .add("/me/digest_frequency", put(set_digest_frequency))
+        .add("/{user_id}", get(get_user))
         .add("/{user_id}", patch(update_user))
-}
\ No newline at end of file
+        .add("/{user_id}/role", patch(set_role))
+}
@@ -0,0 +1,135 @@
+//! Finds stored images nothing refers to any more.
+//!
+//! Uploads that never made it into a post are left under `pending/`, and
+//! images of deleted replies, or ones that were replaced, are left under
+//! `replies/`. Anything modified within the last [`UPLOAD_URL_TTL`] is left
+//! alone: a pending upload may still be attached, and a reply's image is
+//! written a moment before the reply that points at it is committed.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use loco_rs::prelude::*;
+use sea_orm::sea_query::Expr;
+
+use super::{ImageStorage, UPLOAD_URL_TTL};
+use crate::models::replies;
+
+/// A stored file that can be deleted, and why
+#[derive(Debug, Clone)]
+pub struct Orphan {
+    pub key: String,
+    pub reason: &'static str,
+    /// The deleted reply still pointing at the image, if any
+    pub deleted_reply_id: Option<i32>,
+}
+
+/// Everything in `storage` that can be deleted, as of `now`
+///
+/// # Errors
+///
+/// When the store can't be listed or the database can't be queried
+pub async fn find_orphans(
+    db: &DatabaseConnection,
+    storage: &dyn ImageStorage,
+    now: DateTime<Utc>,
+) -> Result<Vec<Orphan>> {
+    let cutoff = now - chrono::Duration::from_std(UPLOAD_URL_TTL).unwrap_or_default();
+    let mut orphans = Vec::new();
+
+    for object in storage.list("pending/").await.map_err(Error::Message)? {
+        if object.last_modified < cutoff {
+            orphans.push(Orphan {
+                key: object.key,
+                reason: "upload was never attached to a reply",
+                deleted_reply_id: None,
+            });
+        }
+    }
+
+    // replies/{id}/{file}, grouped by reply
+    let mut keys_by_reply: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+    for object in storage.list("replies/").await.map_err(Error::Message)? {
+        if object.last_modified >= cutoff {
+            continue;
+        }
+        let reply_id = object.key.split('/').nth(1).and_then(|id| id.parse::<i32>().ok());
+        match reply_id {
+            Some(reply_id) => keys_by_reply.entry(reply_id).or_default().push(object.key),
+            None => tracing::warn!("Skipping unexpected image key {}", object.key),
+        }
+    }
+
+    let replies: HashMap<i32, replies::Model> = replies::Entity::find()
+        .filter(replies::Column::Id.is_in(keys_by_reply.keys().copied()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|reply| (reply.id, reply))
+        .collect();
+
+    for (reply_id, keys) in keys_by_reply {
+        let reply = replies.get(&reply_id);
+        let attached = reply.map(replies::Model::image_keys).unwrap_or_default();
+
+        for key in keys {
+            let orphan = match reply {
+                None => Orphan {
+                    key,
+                    reason: "reply no longer exists",
+                    deleted_reply_id: None,
+                },
+                Some(reply) if reply.reply_status == 3 /* deleted */ => Orphan {
+                    key,
+                    reason: "reply is deleted",
+                    deleted_reply_id: Some(reply_id),
+                },
+                Some(_) if !attached.contains(&key) => Orphan {
+                    key,
+                    reason: "image was replaced or removed",
+                    deleted_reply_id: None,
+                },
+                Some(_) => continue,
+            };
+            orphans.push(orphan);
+        }
+    }
+
+    Ok(orphans)
+}
+
+/// Deletes `orphans` from `storage` and detaches images from the deleted
+/// replies they belonged to. Returns how many files were deleted.
+///
+/// # Errors
+///
+/// When the database can't be updated. Files that can't be deleted are
+/// logged and left for the next run.
+pub async fn remove_orphans(
+    db: &DatabaseConnection,
+    storage: &dyn ImageStorage,
+    orphans: &[Orphan],
+) -> Result<usize> {
+    let mut deleted = 0;
+    for orphan in orphans {
+        match storage.delete(&orphan.key).await {
+            Ok(()) => deleted += 1,
+            Err(e) => tracing::error!("Failed to delete {}: {e}", orphan.key),
+        }
+    }
+
+    let deleted_reply_ids: Vec<i32> = orphans.iter().filter_map(|orphan| orphan.deleted_reply_id).collect();
+    if !deleted_reply_ids.is_empty() {
+        // Goes around the model so the replies' updated_at is left alone
+        replies::Entity::update_many()
+            .col_expr(replies::Column::ImageKey, Expr::value(Option::<String>::None))
+            .col_expr(replies::Column::ImageWidth, Expr::value(Option::<i32>::None))
+            .col_expr(replies::Column::ImageHeight, Expr::value(Option::<i32>::None))
+            .col_expr(replies::Column::ImageVariants, Expr::value(Option::<serde_json::Value>::None))
+            .filter(replies::Column::Id.is_in(deleted_reply_ids))
+            .exec(db)
+            .await?;
+    }
+
+    Ok(deleted)
+}
@@ -0,0 +1,171 @@
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::fs;
+
+use super::{ImageStorage, StorageResult, StoredObject, UPLOAD_URL_TTL};
+
+/// Images kept in a directory on the server and served by the images
+/// controller. Meant for development and small single-server installs.
+///
+/// There's nothing to sign an upload URL with, so handing one out creates an
+/// empty file at the pending key instead. Uploads are only accepted for keys
+/// that have such a file, for as long as an S3 upload URL would stay valid.
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    #[must_use]
+    pub fn new(root: PathBuf, base_url: String) -> Self {
+        Self {
+            root,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Where `key` lives on disk. Keys come from the URL, so anything that
+    /// could step outside the root is rejected.
+    fn path_for(&self, key: &str) -> StorageResult<PathBuf> {
+        let relative = Path::new(key);
+        let is_safe = !key.is_empty()
+            && relative.components().all(|component| match component {
+                Component::Normal(part) => part
+                    .to_str()
+                    .is_some_and(|part| part.chars().all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))),
+                _ => false,
+            });
+
+        if !is_safe {
+            return Err(format!("Invalid image key: {key}"));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl ImageStorage for LocalStorage {
+    async fn presign_upload(&self, pending_key: &str, _content_type: &str) -> StorageResult<String> {
+        self.put(pending_key, "", Vec::new()).await?;
+        Ok(format!("{}/api/images/upload/{}", self.base_url, pending_key))
+    }
+
+    async fn receive_upload(&self, pending_key: &str, body: &[u8]) -> StorageResult<()> {
+        if !super::is_pending_key(pending_key) {
+            return Err("Invalid pending key".to_string());
+        }
+
+        let path = self.path_for(pending_key)?;
+        let metadata = fs::metadata(&path)
+            .await
+            .map_err(|_| "No upload was requested for this key".to_string())?;
+        if metadata.len() > 0 {
+            return Err("An image was already uploaded to this key".to_string());
+        }
+
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+        if age > UPLOAD_URL_TTL {
+            return Err("The upload URL has expired".to_string());
+        }
+
+        fs::write(&path, body)
+            .await
+            .map_err(|e| format!("Failed to write {pending_key}: {e}"))
+    }
+
+    async fn read(&self, key: &str) -> StorageResult<Vec<u8>> {
+        let path = self.path_for(key)?;
+        fs::read(&path).await.map_err(|e| format!("Failed to read {key}: {e}"))
+    }
+
+    async fn size(&self, key: &str) -> StorageResult<u64> {
+        let path = self.path_for(key)?;
+        let metadata = fs::metadata(&path)
+            .await
+            .map_err(|e| format!("Failed to read {key}: {e}"))?;
+        Ok(metadata.len())
+    }
+
+    async fn put(&self, key: &str, _content_type: &str, body: Vec<u8>) -> StorageResult<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        fs::write(&path, body)
+            .await
+            .map_err(|e| format!("Failed to write {key}: {e}"))
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        let path = self.path_for(key)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            // Like S3, deleting something that isn't there is fine
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Failed to delete {key}: {e}")),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> StorageResult<Vec<StoredObject>> {
+        let mut objects = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                // Nothing has been stored yet
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Failed to list {}: {e}", dir.display())),
+            };
+
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| format!("Failed to list {}: {e}", dir.display()))?
+            {
+                let path = entry.path();
+                let metadata = entry
+                    .metadata()
+                    .await
+                    .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+                if metadata.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                let Some(key) = path
+                    .strip_prefix(&self.root)
+                    .ok()
+                    .and_then(Path::to_str)
+                    .map(|key| key.replace(std::path::MAIN_SEPARATOR, "/"))
+                else {
+                    continue;
+                };
+                if key.starts_with(prefix) {
+                    objects.push(StoredObject {
+                        key,
+                        last_modified: metadata.modified().map(DateTime::<Utc>::from).unwrap_or_default(),
+                    });
+                }
+            }
+        }
+
+        Ok(objects)
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/api/images/files/{}", self.base_url, key)
+    }
+}
@@ -0,0 +1,297 @@
+//! Where reply images are kept.
+//!
+//! Browsers never send images through the reply endpoints. They ask for an
+//! upload URL, PUT the file to a `pending/` key, and then pass that key along
+//! when creating or editing a reply, at which point the image is moved under
+//! `replies/{reply_id}/`. The backend is picked by `settings.image_storage`
+//! in the config:
+//!
+//! ```yaml
+//! settings:
+//!   image_storage:
+//!     backend: s3
+//!     bucket: kboard-images
+//!     region: us-east-1
+//!     # Optional, for MinIO and other S3-compatible stores
+//!     endpoint: "http://localhost:9000"
+//! ```
+//!
+//! or, to keep images on disk and serve them through the app:
+//!
+//! ```yaml
+//! settings:
+//!   image_storage:
+//!     backend: local
+//!     root: storage/images
+//!     base_url: "http://localhost:5150"
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use loco_rs::{config::Config, Error, Result};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+pub mod cleanup;
+pub mod local;
+pub mod processing;
+pub mod s3;
+
+pub use local::LocalStorage;
+pub use processing::ImageError;
+pub use s3::S3Storage;
+
+/// Content types that can be uploaded, with the extension stored for each
+pub const ALLOWED_TYPES: &[(&str, &str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+];
+
+/// Largest image a browser may upload
+pub const MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// How long an upload URL stays valid
+pub const UPLOAD_URL_TTL: std::time::Duration = std::time::Duration::from_secs(600);
+
+pub type StorageResult<T> = std::result::Result<T, String>;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum StorageConfig {
+    S3 {
+        bucket: String,
+        region: String,
+        /// Endpoint of an S3-compatible store. Requests use path-style
+        /// addressing when this is set.
+        #[serde(default)]
+        endpoint: Option<String>,
+        /// Base URL images are read from, when it differs from the bucket's
+        #[serde(default)]
+        public_url: Option<String>,
+    },
+    Local {
+        /// Directory images are written to
+        root: std::path::PathBuf,
+        /// URL of the app as seen by browsers
+        base_url: String,
+    },
+}
+
+#[async_trait]
+pub trait ImageStorage: Send + Sync {
+    /// Returns a URL the browser can PUT an image of `content_type` to,
+    /// which will land at `pending_key`
+    async fn presign_upload(&self, pending_key: &str, content_type: &str) -> StorageResult<String>;
+
+    /// Stores an image the browser uploaded through the app rather than
+    /// straight to the store
+    async fn receive_upload(&self, _pending_key: &str, _body: &[u8]) -> StorageResult<()> {
+        Err("Uploads go directly to the image store".to_string())
+    }
+
+    async fn read(&self, key: &str) -> StorageResult<Vec<u8>>;
+
+    /// Size in bytes of the file at `key`, looked up without reading it
+    async fn size(&self, key: &str) -> StorageResult<u64>;
+
+    async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> StorageResult<()>;
+
+    /// Checks the image uploaded to `pending_key` and strips its metadata.
+    /// Nothing is written, so this is done before a reply is saved and a
+    /// rejected upload never holds a transaction open.
+    async fn process_pending(&self, pending_key: &str) -> Result<PendingImage, ImageError> {
+        if !is_pending_key(pending_key) {
+            return Err(ImageError::Rejected("Invalid pending key".to_string()));
+        }
+        let ext = pending_key.rsplit('.').next().unwrap().to_string();
+
+        // Upload URLs can't limit how much is sent to them, so oversized
+        // uploads are turned away before they're downloaded
+        let size = self.size(pending_key).await.map_err(|e| {
+            tracing::warn!("Failed to look up pending image: {e}");
+            ImageError::Rejected("No image was uploaded for this key".to_string())
+        })?;
+        if size > MAX_UPLOAD_BYTES as u64 {
+            return Err(processing::too_large());
+        }
+
+        let original = self.read(pending_key).await.map_err(|e| {
+            tracing::warn!("Failed to read pending image: {e}");
+            ImageError::Rejected("No image was uploaded for this key".to_string())
+        })?;
+        let image = tokio::task::spawn_blocking(move || processing::sanitize(&original, &ext))
+            .await
+            .map_err(|e| ImageError::Storage(format!("Image processing panicked: {e}")))??;
+
+        Ok(PendingImage {
+            pending_key: pending_key.to_string(),
+            image,
+        })
+    }
+
+    /// Writes a processed upload to replies/{reply_id}/image.{ext} and
+    /// removes the original
+    async fn attach(&self, pending: PendingImage, reply_id: i32) -> Result<AttachedImage, ImageError> {
+        let final_key = reply_image_key(&pending.pending_key, reply_id).map_err(ImageError::Rejected)?;
+        let image = pending.image;
+
+        self.put(&final_key, image.content_type, image.bytes)
+            .await
+            .map_err(ImageError::Storage)?;
+
+        // The image is safely attached by now, and leftover uploads get
+        // cleaned up eventually, so this isn't worth failing the post over
+        if let Err(e) = self.delete(&pending.pending_key).await {
+            tracing::error!("Failed to delete pending image {}: {e}", pending.pending_key);
+        }
+
+        Ok(AttachedImage {
+            key: final_key,
+            width: image.width,
+            height: image.height,
+        })
+    }
+
+    /// Checks the image uploaded to pending/{key}, writes a copy without its
+    /// metadata to replies/{reply_id}/image.{ext} and removes the upload
+    async fn move_pending_to_reply(&self, pending_key: &str, reply_id: i32) -> Result<AttachedImage, ImageError> {
+        let pending = self.process_pending(pending_key).await?;
+        self.attach(pending, reply_id).await
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()>;
+
+    /// Everything stored under `prefix`
+    async fn list(&self, prefix: &str) -> StorageResult<Vec<StoredObject>>;
+
+    /// URL browsers can load the image at `key` from
+    fn public_url(&self, key: &str) -> String;
+}
+
+/// A file in the store
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+/// An upload that's been checked and stripped of its metadata, waiting to
+/// be attached to a reply
+pub struct PendingImage {
+    pending_key: String,
+    image: processing::ProcessedImage,
+}
+
+/// An image that's been attached to a reply
+#[derive(Debug, Clone)]
+pub struct AttachedImage {
+    pub key: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+static STORAGE: OnceCell<Box<dyn ImageStorage>> = OnceCell::const_new();
+
+/// Sets up the backend described in `settings.image_storage`. Called once
+/// while the app context is being built.
+///
+/// # Errors
+///
+/// When the setting is missing or malformed
+pub async fn init(config: &Config) -> Result<()> {
+    STORAGE
+        .get_or_try_init(|| async {
+            let settings = config
+                .settings
+                .as_ref()
+                .and_then(|settings| settings.get("image_storage"))
+                .ok_or_else(|| Error::Message("No image_storage in settings".to_string()))?;
+
+            let storage_config: StorageConfig = serde_json::from_value(settings.clone())
+                .map_err(|e| Error::Message(format!("Failed to parse image_storage config: {e}")))?;
+
+            let storage: Box<dyn ImageStorage> = match storage_config {
+                StorageConfig::S3 {
+                    bucket,
+                    region,
+                    endpoint,
+                    public_url,
+                } => Box::new(S3Storage::new(bucket, region, endpoint, public_url).await),
+                StorageConfig::Local { root, base_url } => Box::new(LocalStorage::new(root, base_url)),
+            };
+            Ok::<_, Error>(storage)
+        })
+        .await?;
+    Ok(())
+}
+
+/// The configured image store
+///
+/// # Panics
+///
+/// If called before [`init`]
+pub fn get() -> &'static dyn ImageStorage {
+    STORAGE
+        .get()
+        .expect("image storage is set up when the app context is created")
+        .as_ref()
+}
+
+/// The extension stored for `content_type`, if it may be uploaded
+#[must_use]
+pub fn extension_for(content_type: &str) -> Option<&'static str> {
+    ALLOWED_TYPES
+        .iter()
+        .find(|(allowed, _)| *allowed == content_type)
+        .map(|(_, ext)| *ext)
+}
+
+/// The content type of images stored with `ext`
+#[must_use]
+pub fn content_type_for(ext: &str) -> Option<&'static str> {
+    ALLOWED_TYPES
+        .iter()
+        .find(|(_, allowed)| *allowed == ext)
+        .map(|(content_type, _)| *content_type)
+}
+
+/// A fresh, unguessable key to upload an image to
+#[must_use]
+pub fn new_pending_key(ext: &str) -> String {
+    format!("pending/{}.{}", Uuid::new_v4(), ext)
+}
+
+/// Where the image uploaded to `pending_key` ends up once attached to a reply
+///
+/// # Errors
+///
+/// When `pending_key` isn't a pending key
+pub fn reply_image_key(pending_key: &str, reply_id: i32) -> StorageResult<String> {
+    if !is_pending_key(pending_key) {
+        return Err("Invalid pending key".to_string());
+    }
+
+    let ext = pending_key.rsplit('.').next().unwrap();
+    Ok(format!("replies/{}/image.{}", reply_id, ext))
+}
+
+/// Where the `variant` of a reply's image is kept
+#[must_use]
+pub fn variant_key(reply_id: i32, variant: &str, ext: &str) -> String {
+    format!("replies/{}/{}.{}", reply_id, variant, ext)
+}
+
+/// Whether `key` is one handed out by [`new_pending_key`]
+#[must_use]
+pub fn is_pending_key(key: &str) -> bool {
+    let Some(name) = key.strip_prefix("pending/") else {
+        return false;
+    };
+    let Some((id, ext)) = name.split_once('.') else {
+        return false;
+    };
+    id.len() == 36 && Uuid::parse_str(id).is_ok() && content_type_for(ext).is_some()
+}
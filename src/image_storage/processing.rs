@@ -0,0 +1,235 @@
+//! Checks uploaded images and rewrites them without their metadata.
+//!
+//! Uploads go straight from the browser to the store, so nothing about them
+//! can be trusted: the content type was picked by the client and the bytes
+//! may not be an image at all. Every image is decoded here before it's
+//! attached to a reply. JPEG, PNG and WebP files are re-encoded from their
+//! pixels, which drops EXIF data such as GPS coordinates along with anything
+//! else that isn't part of the picture. GIFs can't carry EXIF and re-encoding
+//! them would lose their animation, so they're kept as uploaded once they've
+//! been decoded successfully.
+
+use std::{fmt, io::Cursor};
+
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits,
+};
+
+use super::MAX_UPLOAD_BYTES;
+
+/// Largest width or height an image may have
+pub const MAX_DIMENSION: u32 = 8192;
+
+/// Quality JPEGs are re-encoded at
+const JPEG_QUALITY: u8 = 90;
+
+/// Smaller copies made of every attached image, by name and the size of
+/// the box they're scaled down to fit in
+pub const VARIANTS: &[(&str, u32)] = &[("thumbnail", 320), ("medium", 1024)];
+
+/// Why an image couldn't be attached to a reply
+#[derive(Debug)]
+pub enum ImageError {
+    /// The upload was refused, with a message that can be shown to the poster
+    Rejected(String),
+    /// The store failed; not the poster's fault
+    Storage(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected(message) | Self::Storage(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<ImageError> for loco_rs::Error {
+    fn from(err: ImageError) -> Self {
+        match err {
+            ImageError::Rejected(message) => Self::BadRequest(message),
+            ImageError::Storage(message) => {
+                tracing::error!("Failed to attach image: {message}");
+                Self::InternalServerError
+            }
+        }
+    }
+}
+
+/// An image that's safe to attach
+pub struct ProcessedImage {
+    pub bytes: Vec<u8>,
+    pub content_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn rejected(message: impl Into<String>) -> ImageError {
+    ImageError::Rejected(message.into())
+}
+
+/// The rejection for uploads over [`MAX_UPLOAD_BYTES`]
+pub(crate) fn too_large() -> ImageError {
+    rejected(format!(
+        "Image is too large. Maximum size is {} MB.",
+        MAX_UPLOAD_BYTES / (1024 * 1024)
+    ))
+}
+
+fn format_for(ext: &str) -> Option<ImageFormat> {
+    match ext {
+        "jpg" => Some(ImageFormat::Jpeg),
+        "png" => Some(ImageFormat::Png),
+        "gif" => Some(ImageFormat::Gif),
+        "webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+fn name_of(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Jpeg => "JPEG",
+        ImageFormat::Png => "PNG",
+        ImageFormat::Gif => "GIF",
+        ImageFormat::WebP => "WebP",
+        _ => "an unsupported format",
+    }
+}
+
+/// Checks that `bytes` is an image of the type `ext` stands for, and
+/// returns it stripped of metadata
+///
+/// # Errors
+///
+/// [`ImageError::Rejected`] when the upload is empty, too large, not the
+/// format it claims to be, too big in either dimension, or can't be decoded
+pub fn sanitize(bytes: &[u8], ext: &str) -> Result<ProcessedImage, ImageError> {
+    if bytes.is_empty() {
+        return Err(rejected("No image was uploaded"));
+    }
+    if bytes.len() > MAX_UPLOAD_BYTES {
+        return Err(too_large());
+    }
+
+    let expected = format_for(ext).ok_or_else(|| rejected(format!("Unsupported image type: {ext}")))?;
+    let actual = image::guess_format(bytes)
+        .map_err(|_| rejected("The uploaded file isn't a JPEG, PNG, GIF or WebP image"))?;
+    if actual != expected {
+        return Err(rejected(format!(
+            "The uploaded file is {} but was uploaded as {}",
+            name_of(actual),
+            name_of(expected)
+        )));
+    }
+
+    let (width, height) = ImageReader::with_format(Cursor::new(bytes), actual)
+        .into_dimensions()
+        .map_err(|e| rejected(format!("The uploaded image couldn't be read: {e}")))?;
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(rejected(format!(
+            "Image is {width}x{height} pixels. The maximum is {MAX_DIMENSION}x{MAX_DIMENSION}."
+        )));
+    }
+
+    let mut decoder = decoder_for(bytes, actual)
+        .map_err(|e| rejected(format!("The uploaded image couldn't be read: {e}")))?;
+    // Stripping EXIF also strips the orientation, so it's applied to the
+    // pixels first to keep photos the right way up
+    let orientation = decoder.orientation().ok();
+    let mut decoded = DynamicImage::from_decoder(decoder)
+        .map_err(|e| rejected(format!("The uploaded image couldn't be decoded: {e}")))?;
+    if let Some(orientation) = orientation {
+        decoded.apply_orientation(orientation);
+    }
+
+    let bytes = match actual {
+        ImageFormat::Gif => bytes.to_vec(),
+        _ => encode(&decoded, actual)?,
+    };
+
+    Ok(ProcessedImage {
+        bytes,
+        content_type: super::content_type_for(ext).unwrap_or("application/octet-stream"),
+        width: decoded.width(),
+        height: decoded.height(),
+    })
+}
+
+/// A scaled-down copy of an attached image
+pub struct Variant {
+    pub name: &'static str,
+    pub ext: &'static str,
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Makes each of [`VARIANTS`] from an image that was attached with `ext`,
+/// skipping those the image already fits inside. JPEGs stay JPEGs and
+/// everything else becomes a PNG, which keeps transparency but shows only
+/// the first frame of an animated GIF.
+///
+/// # Errors
+///
+/// When the image can't be decoded or a variant can't be encoded
+pub fn make_variants(bytes: &[u8], ext: &str) -> Result<Vec<Variant>, ImageError> {
+    let format = format_for(ext).ok_or_else(|| ImageError::Storage(format!("Unsupported image type: {ext}")))?;
+    let image = decoder_for(bytes, format)
+        .and_then(DynamicImage::from_decoder)
+        .map_err(|e| ImageError::Storage(format!("Failed to decode attached image: {e}")))?;
+
+    let (variant_format, variant_ext) = if format == ImageFormat::Jpeg {
+        (ImageFormat::Jpeg, "jpg")
+    } else {
+        (ImageFormat::Png, "png")
+    };
+
+    VARIANTS
+        .iter()
+        .filter(|(_, size)| image.width() > *size || image.height() > *size)
+        .map(|(name, size)| {
+            let resized = image.resize(*size, *size, FilterType::Lanczos3);
+            Ok(Variant {
+                name,
+                ext: variant_ext,
+                bytes: encode(&resized, variant_format)?,
+                width: resized.width(),
+                height: resized.height(),
+            })
+        })
+        .collect()
+}
+
+fn decoder_for(bytes: &[u8], format: ImageFormat) -> image::ImageResult<impl ImageDecoder + '_> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    reader.into_decoder()
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    match format {
+        ImageFormat::Jpeg => {
+            let mut out = Vec::new();
+            JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+                .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))
+                .map_err(|e| ImageError::Storage(format!("Failed to encode JPEG: {e}")))?;
+            Ok(out)
+        }
+        // The WebP encoder only takes 8-bit RGBA
+        ImageFormat::WebP => write(&DynamicImage::ImageRgba8(image.to_rgba8()), format),
+        _ => write(image, format),
+    }
+}
+
+fn write(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    let mut out = Cursor::new(Vec::new());
+    image
+        .write_to(&mut out, format)
+        .map_err(|e| ImageError::Storage(format!("Failed to encode {}: {e}", name_of(format))))?;
+    Ok(out.into_inner())
+}
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use aws_sdk_s3::{config::Region, presigning::PresigningConfig, primitives::ByteStream, Client};
+
+use chrono::DateTime;
+
+use super::{ImageStorage, StorageResult, StoredObject, UPLOAD_URL_TTL};
+
+/// Images kept in an S3 bucket, or in any store that speaks the S3 API
+pub struct S3Storage {
+    client: Client,
+    bucket: String,
+    region: String,
+    endpoint: Option<String>,
+    public_url: Option<String>,
+}
+
+impl S3Storage {
+    /// Credentials come from the usual AWS environment variables and
+    /// profiles. Empty `endpoint` and `public_url` values count as unset so
+    /// they can be filled from optional environment variables in the config.
+    pub async fn new(
+        bucket: String,
+        region: String,
+        endpoint: Option<String>,
+        public_url: Option<String>,
+    ) -> Self {
+        let endpoint = endpoint.filter(|e| !e.is_empty());
+        let public_url = public_url.filter(|u| !u.is_empty());
+
+        let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let mut config = aws_sdk_s3::config::Builder::from(&sdk_config).region(Region::new(region.clone()));
+        if let Some(endpoint) = &endpoint {
+            config = config.endpoint_url(endpoint).force_path_style(true);
+        }
+
+        Self {
+            client: Client::from_conf(config.build()),
+            bucket,
+            region,
+            endpoint,
+            public_url,
+        }
+    }
+}
+
+#[async_trait]
+impl ImageStorage for S3Storage {
+    async fn presign_upload(&self, pending_key: &str, content_type: &str) -> StorageResult<String> {
+        let presign_config = PresigningConfig::builder()
+            .expires_in(UPLOAD_URL_TTL)
+            .build()
+            .expect("valid presigning config");
+
+        let presigned = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(pending_key)
+            .content_type(content_type)
+            .presigned(presign_config)
+            .await
+            .map_err(|e| format!("S3 presign failed: {e}"))?;
+
+        Ok(presigned.uri().to_string())
+    }
+
+    async fn read(&self, key: &str) -> StorageResult<Vec<u8>> {
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| format!("S3 get failed: {e}"))?;
+
+        let body = object
+            .body
+            .collect()
+            .await
+            .map_err(|e| format!("S3 read failed: {e}"))?;
+
+        Ok(body.into_bytes().to_vec())
+    }
+
+    async fn size(&self, key: &str) -> StorageResult<u64> {
+        let head = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| format!("S3 head failed: {e}"))?;
+
+        head.content_length()
+            .and_then(|length| u64::try_from(length).ok())
+            .ok_or_else(|| format!("S3 returned no size for {key}"))
+    }
+
+    async fn put(&self, key: &str, content_type: &str, body: Vec<u8>) -> StorageResult<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|e| format!("S3 put failed: {e}"))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| format!("S3 delete failed: {e}"))?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> StorageResult<Vec<StoredObject>> {
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        let mut objects = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| format!("S3 list failed: {e}"))?;
+            for object in page.contents() {
+                let (Some(key), Some(modified)) = (object.key(), object.last_modified()) else {
+                    continue;
+                };
+                objects.push(StoredObject {
+                    key: key.to_string(),
+                    last_modified: DateTime::from_timestamp(modified.secs(), modified.subsec_nanos())
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        Ok(objects)
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        match (&self.public_url, &self.endpoint) {
+            (Some(base), _) => format!("{}/{}", base.trim_end_matches('/'), key),
+            (None, Some(endpoint)) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), self.bucket, key),
+            (None, None) => format!("https://{}.s3.{}.amazonaws.com/{}", self.bucket, self.region, key),
+        }
+    }
+}
@@ -1,10 +1,12 @@
 pub mod app;
 pub mod controllers;
-pub mod s3;
 pub mod data;
+pub mod image_storage;
 pub mod initializers;
 pub mod mailers;
+pub mod markdown;
 pub mod models;
+pub mod permissions;
 pub mod tasks;
 pub mod views;
 pub mod workers;
@@ -5,7 +5,7 @@
   <p>Welcome to the BrokenJaw.net message board! To verify your email and log in
   for the first time, click the link below:</p>
 
-  <p><a href="http://{{domain}}/verify/{{verifyToken}}">Verify Your Account</a></p>
+  <p><a href="http://{{domain}}/api/auth/verify/{{verifyToken}}">Verify Your Account</a></p>
   <p>Best regards,<br>Kevin</p>
 </body>
 
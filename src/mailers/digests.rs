@@ -0,0 +1,55 @@
+#![allow(non_upper_case_globals)]
+
+use loco_rs::prelude::*;
+use serde_json::json;
+
+use crate::models::thread_subscriptions::DigestThread;
+use crate::models::users;
+
+static thread_digest: Dir<'_> = include_dir!("src/mailers/digests/thread_digest");
+
+pub struct DigestMailer {}
+impl Mailer for DigestMailer {}
+impl DigestMailer {
+    /// Sends `user` the new replies in the threads they follow. Users who
+    /// want every reply right away get this with a single reply in it.
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_digest(ctx: &AppContext, user: &users::Model, threads: &[DigestThread]) -> Result<()> {
+        let site = match ctx.config.server.host == "brokenjaw.net" {
+            true => "https://brokenjaw.net".to_string(),
+            false => ctx.config.server.full_url(),
+        };
+
+        let threads: Vec<serde_json::Value> = threads
+            .iter()
+            .map(|thread| {
+                json!({
+                  "title": thread.title,
+                  "url": format!("{site}/boards/{}/threads/{}", thread.board_id, thread.thread_id),
+                  "replies": thread.replies,
+                  "more_replies": thread.more_replies,
+                })
+            })
+            .collect();
+
+        Self::mail_template(
+            ctx,
+            &thread_digest,
+            mailer::Args {
+                to: user.email.to_string(),
+                locals: json!({
+                  "name": user.name,
+                  "threads": threads,
+                  "settings_url": format!("{site}/profile"),
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+<html>
+
+<body>
+  <p>Hey {{name | escape}},</p>
+  <p>Here's what's new in the threads you follow.</p>
+  {% for thread in threads %}
+  <h3><a href="{{thread.url}}">{{thread.title | escape}}</a></h3>
+  {% for reply in thread.replies %}
+  <p><strong>{{reply.poster_name | escape}}</strong> wrote:</p>
+  <blockquote>{{reply.body_html}}</blockquote>
+  {% endfor %}
+  {% if thread.more_replies > 0 %}
+  <p>...and {{thread.more_replies}} more.</p>
+  {% endif %}
+  <p><a href="{{thread.url}}">Read the thread</a></p>
+  {% endfor %}
+  <p>You're getting this because you follow these threads. To change how often these emails come, or stop them, visit
+    your <a href="{{settings_url}}">profile</a>.</p>
+</body>
+
+</html>
@@ -0,0 +1 @@
+{% if threads | length == 1 %}New replies in "{{threads.0.title}}"{% else %}New replies in {{threads | length}} threads you follow{% endif %}
\ No newline at end of file
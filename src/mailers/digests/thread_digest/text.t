@@ -0,0 +1,16 @@
+Hey {{name}},
+
+Here's what's new in the threads you follow.
+{% for thread in threads %}
+== {{thread.title}} ==
+{% for reply in thread.replies %}
+{{reply.poster_name}} wrote:
+
+{{reply.body}}
+{% endfor %}{% if thread.more_replies > 0 %}
+...and {{thread.more_replies}} more.
+{% endif %}
+Read the thread: {{thread.url}}
+{% endfor %}
+You're getting this because you follow these threads. To change how often
+these emails come, or stop them, visit {{settings_url}}
@@ -0,0 +1,56 @@
+#![allow(non_upper_case_globals)]
+
+use loco_rs::prelude::*;
+use serde_json::json;
+
+use crate::models::users;
+
+static mentioned: Dir<'_> = include_dir!("src/mailers/mentions/mentioned");
+
+/// Where a mention was made
+pub struct MentionArgs<'a> {
+    pub poster_name: &'a str,
+    pub board_id: i32,
+    pub thread_id: i32,
+    pub thread_title: &'a str,
+    /// The reply as sanitized HTML
+    pub body_html: &'a str,
+    /// The reply as it was written
+    pub body: &'a str,
+}
+
+pub struct MentionMailer {}
+impl Mailer for MentionMailer {}
+impl MentionMailer {
+    /// Tells `user` they were mentioned in a reply
+    ///
+    /// # Errors
+    ///
+    /// When email sending is failed
+    pub async fn send_mentioned(ctx: &AppContext, user: &users::Model, args: &MentionArgs<'_>) -> Result<()> {
+        let site = match ctx.config.server.host == "brokenjaw.net" {
+            true => "https://brokenjaw.net".to_string(),
+            false => ctx.config.server.full_url(),
+        };
+
+        Self::mail_template(
+            ctx,
+            &mentioned,
+            mailer::Args {
+                to: user.email.to_string(),
+                locals: json!({
+                  "name": user.name,
+                  "poster_name": args.poster_name,
+                  "thread_title": args.thread_title,
+                  "thread_url": format!("{site}/boards/{}/threads/{}", args.board_id, args.thread_id),
+                  "body_html": args.body_html,
+                  "body": args.body,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+<html>
+
+<body>
+  <p>Hey {{name | escape}},</p>
+  <p>{{poster_name | escape}} mentioned you in <a href="{{thread_url}}">{{thread_title | escape}}</a>:</p>
+  <blockquote>{{body_html}}</blockquote>
+  <p><a href="{{thread_url}}">Read the thread</a></p>
+</body>
+
+</html>
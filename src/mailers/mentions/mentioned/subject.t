@@ -0,0 +1 @@
+{{poster_name}} mentioned you in "{{thread_title}}"
\ No newline at end of file
@@ -0,0 +1,7 @@
+Hey {{name}},
+
+{{poster_name}} mentioned you in "{{thread_title}}":
+
+{{body}}
+
+Read the thread: {{thread_url}}
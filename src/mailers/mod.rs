@@ -1,2 +1,4 @@
 pub mod auth;
+pub mod digests;
+pub mod mentions;
 pub mod reports;
\ No newline at end of file
@@ -0,0 +1,322 @@
+//! Turns reply bodies into HTML that's safe to show as-is.
+//!
+//! Replies are written in a subset of Markdown: paragraphs, emphasis,
+//! strikethrough, links, lists, block quotes, inline code and fenced code
+//! blocks, whose language tag is kept as a `language-*` class for syntax
+//! highlighting. On top of that, bare `http(s)://` URLs become links,
+//! `@name` mentions of existing users link to their profiles, and text
+//! between `||` markers becomes a spoiler. Headings are shown as bold
+//! paragraphs, images as links to them, and any HTML in the body as the
+//! text that was typed. The output goes through a sanitizer with a fixed
+//! list of tags and attributes, so nothing in a body can add scripts,
+//! styles or event handlers to the page.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use ammonia::UrlRelative;
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd, TextMergeStream};
+use regex::Regex;
+
+/// Wraps text hidden until the reader clicks on it
+const SPOILER_MARKER: &str = "||";
+
+const SPOILER_START: &str = "<span class=\"spoiler\">";
+const SPOILER_END: &str = "</span>";
+
+/// Most users a single reply can mention
+pub const MAX_MENTIONS: usize = 20;
+
+/// Users mentioned in a body that exist, by their lowercased name
+pub type Mentions = HashMap<String, i32>;
+
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s<>"]+"#).expect("valid URL pattern"));
+
+/// `@` and a name. Names with spaces or other punctuation can't be mentioned.
+static MENTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"@([A-Za-z0-9_][A-Za-z0-9_.-]*)").expect("valid mention pattern"));
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::empty();
+    builder
+        .tags(HashSet::from([
+            "p", "br", "strong", "em", "del", "a", "code", "pre", "blockquote", "ul", "ol", "li", "hr", "span",
+        ]))
+        .tag_attributes(HashMap::from([
+            ("a", HashSet::from(["href", "title", "class"])),
+            ("code", HashSet::from(["class"])),
+            ("ol", HashSet::from(["start"])),
+            ("span", HashSet::from(["class"])),
+        ]))
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("code", "class") => value
+                .strip_prefix("language-")
+                .filter(|language| {
+                    !language.is_empty()
+                        && language.chars().all(|c| c.is_ascii_alphanumeric() || "+#-_.".contains(c))
+                })
+                .map(|_| value.into()),
+            ("a", "class") => (value == "mention").then(|| value.into()),
+            ("span", "class") => (value == "spoiler").then(|| value.into()),
+            _ => Some(value.into()),
+        })
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .url_relative(UrlRelative::Custom(Box::new(profile_links_only)))
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .clean_content_tags(HashSet::from(["script", "style"]));
+    builder
+});
+
+/// The only relative links allowed are the ones made for mentions
+fn profile_links_only(url: &str) -> Option<Cow<'_, str>> {
+    url.strip_prefix("/users/")
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        .map(|_| url.into())
+}
+
+/// Renders a reply body as sanitized HTML, linking the `mentions` in it
+#[must_use]
+pub fn render(body: &str, mentions: &Mentions) -> String {
+    let mut events = Vec::new();
+    let mut in_code_block = false;
+    let mut in_link = false;
+    let mut in_spoiler = false;
+
+    // Text is merged so a URL or spoiler marker isn't split across events
+    for event in TextMergeStream::new(Parser::new_ext(body, Options::ENABLE_STRIKETHROUGH)) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                events.push(event);
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                events.push(event);
+            }
+            Event::Start(Tag::Link { .. }) => {
+                in_link = true;
+                events.push(event);
+            }
+            Event::Start(Tag::Image { dest_url, title, .. }) => {
+                in_link = true;
+                events.push(Event::Start(Tag::Link {
+                    link_type: LinkType::Inline,
+                    dest_url,
+                    title,
+                    id: CowStr::Borrowed(""),
+                }));
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                in_link = false;
+                events.push(Event::End(TagEnd::Link));
+            }
+            Event::Start(Tag::Heading { .. }) => {
+                events.push(Event::Start(Tag::Paragraph));
+                events.push(Event::Start(Tag::Strong));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                close_spoiler(&mut events, &mut in_spoiler);
+                events.push(Event::End(TagEnd::Strong));
+                events.push(Event::End(TagEnd::Paragraph));
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::BlockQuote(_)) => {
+                close_spoiler(&mut events, &mut in_spoiler);
+                events.push(event);
+            }
+            // An HTML block is kept as a paragraph of the text that was typed
+            Event::Start(Tag::HtmlBlock) => events.push(Event::Start(Tag::Paragraph)),
+            Event::End(TagEnd::HtmlBlock) => events.push(Event::End(TagEnd::Paragraph)),
+            Event::Html(html) | Event::InlineHtml(html) => events.push(Event::Text(html)),
+            Event::Text(text) if !in_code_block => push_text(&mut events, &text, in_link, &mut in_spoiler, mentions),
+            event => events.push(event),
+        }
+    }
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events.into_iter());
+    SANITIZER.clean(&unsafe_html).to_string()
+}
+
+/// Ends a spoiler left open at the end of a block, so it can't swallow
+/// the rest of the reply
+fn close_spoiler(events: &mut Vec<Event<'_>>, in_spoiler: &mut bool) {
+    if *in_spoiler {
+        events.push(Event::InlineHtml(CowStr::Borrowed(SPOILER_END)));
+        *in_spoiler = false;
+    }
+}
+
+/// Adds `text` to `events`, turning spoiler markers into spoilers and, when
+/// not already inside a link, URLs and mentions into links
+fn push_text(events: &mut Vec<Event<'_>>, text: &str, in_link: bool, in_spoiler: &mut bool, mentions: &Mentions) {
+    for (i, piece) in text.split(SPOILER_MARKER).enumerate() {
+        if i > 0 {
+            let marker = if *in_spoiler { SPOILER_END } else { SPOILER_START };
+            events.push(Event::InlineHtml(CowStr::Borrowed(marker)));
+            *in_spoiler = !*in_spoiler;
+        }
+        if in_link {
+            events.push(Event::Text(piece.to_string().into()));
+        } else {
+            push_autolinked(events, piece, mentions);
+        }
+    }
+}
+
+fn push_autolinked(events: &mut Vec<Event<'_>>, text: &str, mentions: &Mentions) {
+    let mut rest = 0;
+    for found in URL.find_iter(text) {
+        let url = trim_url(found.as_str());
+        if found.start() > rest {
+            push_mentions(events, &text[rest..found.start()], mentions);
+        }
+        events.push(Event::Start(Tag::Link {
+            link_type: LinkType::Autolink,
+            dest_url: url.to_string().into(),
+            title: CowStr::Borrowed(""),
+            id: CowStr::Borrowed(""),
+        }));
+        events.push(Event::Text(url.to_string().into()));
+        events.push(Event::End(TagEnd::Link));
+        rest = found.start() + url.len();
+    }
+    if rest < text.len() {
+        push_mentions(events, &text[rest..], mentions);
+    }
+}
+
+fn push_mentions(events: &mut Vec<Event<'_>>, text: &str, mentions: &Mentions) {
+    let mut rest = 0;
+    for (start, name) in find_mentions(text) {
+        let Some(user_id) = mentions.get(&name.to_lowercase()) else {
+            continue;
+        };
+        if start > rest {
+            events.push(Event::Text(text[rest..start].to_string().into()));
+        }
+        events.push(Event::InlineHtml(format!("<a class=\"mention\" href=\"/users/{user_id}\">").into()));
+        events.push(Event::Text(format!("@{name}").into()));
+        events.push(Event::InlineHtml(CowStr::Borrowed("</a>")));
+        rest = start + 1 + name.len();
+    }
+    if rest < text.len() {
+        events.push(Event::Text(text[rest..].to_string().into()));
+    }
+}
+
+/// Where each mention in `text` starts, and the name it mentions. An `@`
+/// in the middle of a word, like in an email address, isn't a mention.
+fn find_mentions(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    MENTION.captures_iter(text).filter_map(|captures| {
+        let start = captures.get(0)?.start();
+        let follows_word = text[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '@');
+        let name = captures.get(1)?.as_str().trim_end_matches(['.', '-']);
+        (!follows_word).then_some((start, name))
+    })
+}
+
+/// The names mentioned in a body, without repeats and leaving out anything
+/// in code or links. Only the first [`MAX_MENTIONS`] count.
+#[must_use]
+pub fn mentioned_names(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut in_code_block = false;
+    let mut in_link = false;
+
+    for event in TextMergeStream::new(Parser::new_ext(body, Options::ENABLE_STRIKETHROUGH)) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Link { .. } | Tag::Image { .. }) => in_link = true,
+            Event::End(TagEnd::Link | TagEnd::Image) => in_link = false,
+            Event::Text(text) if !in_code_block && !in_link => {
+                // Text inside URLs isn't a mention either
+                for piece in URL.split(&text) {
+                    for (_, name) in find_mentions(piece) {
+                        if !names.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                            names.push(name.to_string());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    names.truncate(MAX_MENTIONS);
+    names
+}
+
+/// Leaves punctuation that ends the sentence out of a URL, along with a
+/// closing bracket that has no opening one in the URL
+fn trim_url(url: &str) -> &str {
+    let mut url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '*', '_']);
+    while url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+        url = url[..url.len() - 1].trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+    }
+    url
+}
+
+/// Renders a search snippet, keeping the private-use characters that mark
+/// the matched terms so they can be turned into `<mark>` afterwards. The
+/// markers only survive in text: in a link they're percent-encoded and
+/// taken back out here. Mentions are left as plain text.
+#[must_use]
+pub fn render_snippet(snippet: &str, match_start: char, match_end: char) -> String {
+    let encoded = |c: char| {
+        let mut buf = [0; 4];
+        c.encode_utf8(&mut buf)
+            .bytes()
+            .map(|b| format!("%{b:02X}"))
+            .collect::<String>()
+    };
+    render(snippet, &Mentions::new())
+        .replace(&encoded(match_start), "")
+        .replace(&encoded(match_end), "")
+        .replace(match_start, "<mark>")
+        .replace(match_end, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_are_shown_as_text() {
+        let html = render("<script>alert('hi')</script>\n\nOops <script>alert('hi')</script>", &Mentions::new());
+        assert!(!html.contains("<script"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn javascript_links_are_stripped() {
+        let html = render("[click me](javascript:alert(1)) and <a href=\"javascript:alert(1)\">me</a>", &Mentions::new());
+        // The typed tag stays as text, so only the Markdown link is a link
+        assert_eq!(
+            html,
+            "<p><a rel=\"nofollow noopener noreferrer\">click me</a> and \
+             &lt;a href=\"javascript:alert(1)\"&gt;me&lt;/a&gt;</p>\n"
+        );
+    }
+
+    #[test]
+    fn mentions_in_code_are_ignored() {
+        let body = "Hey @alice, try `@bob` here:\n\n```\n@carol\n```\n\n    @erin\n\nThanks @Dave and @ALICE";
+        assert_eq!(mentioned_names(body), vec!["alice", "Dave"]);
+    }
+
+    #[test]
+    fn only_known_users_are_linked() {
+        let mentions = Mentions::from([("alice".to_string(), 7)]);
+        let html = render("Hi @Alice and @bob, mail me at me@example.com", &mentions);
+        assert_eq!(
+            html,
+            "<p>Hi <a class=\"mention\" href=\"/users/7\" rel=\"nofollow noopener noreferrer\">@Alice</a> \
+             and @bob, mail me at me@example.com</p>\n"
+        );
+    }
+}
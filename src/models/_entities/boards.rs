@@ -14,14 +14,60 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub description: String,
     pub num_threads: i32,
+    pub requires_verified_email: bool,
+    pub position: i32,
+    pub is_archived: bool,
+    pub category_id: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub visibility: String,
+    pub posting_policy: String,
+    pub revisions_public: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::board_categories::Entity",
+        from = "Column::CategoryId",
+        to = "super::board_categories::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    BoardCategories,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::board_members::Entity")]
+    BoardMembers,
+    #[sea_orm(has_many = "super::board_moderators::Entity")]
+    BoardModerators,
     #[sea_orm(has_many = "super::threads::Entity")]
     Threads,
 }
 
+impl Related<super::board_categories::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BoardCategories.def()
+    }
+}
+
+impl Related<super::board_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BoardMembers.def()
+    }
+}
+
+impl Related<super::board_moderators::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BoardModerators.def()
+    }
+}
+
 impl Related<super::threads::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Threads.def()
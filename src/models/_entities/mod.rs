@@ -2,8 +2,18 @@
 
 pub mod prelude;
 
+pub mod bans;
+pub mod board_categories;
+pub mod board_members;
+pub mod board_moderators;
 pub mod boards;
+pub mod mentions;
+pub mod mod_actions;
+pub mod notifications;
 pub mod replies;
+pub mod reply_revisions;
+pub mod reports;
 pub mod reply_statuses;
+pub mod thread_subscriptions;
 pub mod threads;
 pub mod users;
@@ -1,7 +1,17 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.13
 
+pub use super::bans::Entity as Bans;
+pub use super::board_categories::Entity as BoardCategories;
+pub use super::board_members::Entity as BoardMembers;
+pub use super::board_moderators::Entity as BoardModerators;
 pub use super::boards::Entity as Boards;
+pub use super::mentions::Entity as Mentions;
+pub use super::mod_actions::Entity as ModActions;
+pub use super::notifications::Entity as Notifications;
 pub use super::replies::Entity as Replies;
+pub use super::reply_revisions::Entity as ReplyRevisions;
+pub use super::reports::Entity as Reports;
 pub use super::reply_statuses::Entity as ReplyStatuses;
+pub use super::thread_subscriptions::Entity as ThreadSubscriptions;
 pub use super::threads::Entity as Threads;
 pub use super::users::Entity as Users;
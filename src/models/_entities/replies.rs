@@ -18,6 +18,13 @@ pub struct Model {
     pub reply_status: i32,
     #[sea_orm(column_type = "Text", nullable)]
     pub image_key: Option<String>,
+    pub image_width: Option<i32>,
+    pub image_height: Option<i32>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub image_variants: Option<Json>,
+    pub edit_count: i32,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub body_html: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
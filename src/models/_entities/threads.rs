@@ -18,6 +18,9 @@ pub struct Model {
     pub last_active: DateTime,
     pub num_replies: i32,
     pub is_deleted: bool,
+    pub is_locked: bool,
+    pub is_pinned: bool,
+    pub is_archived: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
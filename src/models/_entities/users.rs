@@ -24,10 +24,12 @@ pub struct Model {
     pub email_verified_at: Option<DateTimeWithTimeZone>,
     pub magic_link_token: Option<String>,
     pub magic_link_expiration: Option<DateTimeWithTimeZone>,
-    pub is_moderator: bool,
     pub is_banned: bool,
+    pub role: String,
     #[sea_orm(unique)]
     pub google_id: String,
+    pub digest_frequency: String,
+    pub last_digest_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,184 @@
+use sea_orm::entity::prelude::*;
+use chrono::offset::Local;
+use loco_rs::prelude::*;
+use sea_orm::{QueryOrder, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::bans::{ActiveModel, Model, Entity, Column};
+use crate::models::users;
+pub type Bans = Entity;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanResponse {
+    pub id: i32,
+    pub user_id: i32,
+    pub moderator_id: i32,
+    pub reason: String,
+    pub started_at: DateTimeWithTimeZone,
+    pub expires_at: Option<DateTimeWithTimeZone>,
+}
+
+impl From<&Model> for BanResponse {
+    fn from(ban: &Model) -> Self {
+        Self {
+            id: ban.id,
+            user_id: ban.user_id,
+            moderator_id: ban.moderator_id,
+            reason: ban.reason.clone(),
+            started_at: ban.created_at,
+            expires_at: ban.expires_at,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Whether the ban had an expiry time that has now passed
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Local::now())
+    }
+
+    /// Bans a user, recording who banned them and why, and sets `users.is_banned`.
+    ///
+    /// # Parameters
+    ///
+    /// * db - current database connection
+    /// * user_id - id of the user being banned
+    /// * moderator_id - id of the moderator issuing the ban
+    /// * reason - explanation shown to the banned user
+    /// * expires_at - when the ban lifts by itself, or None for a permanent ban
+    pub async fn create<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        user_id: i32,
+        moderator_id: i32,
+        reason: String,
+        expires_at: Option<DateTimeWithTimeZone>,
+    ) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+
+        let user = users::Entity::find_by_id(user_id)
+            .one(&txn)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        let ban = ActiveModel {
+            user_id: Set(user_id),
+            moderator_id: Set(moderator_id),
+            reason: Set(reason),
+            // Stored as UTC so that lift_expired can compare timestamps in SQL
+            expires_at: Set(expires_at.map(|expires_at| expires_at.to_utc().into())),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        let mut user: users::ActiveModel = user.into();
+        user.is_banned = Set(true);
+        user.update(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(ban)
+    }
+
+    /// Lifts every active ban on a user and clears `users.is_banned`.
+    ///
+    /// `lifted_by` is the moderator lifting the ban, or None when an expired
+    /// ban is lifted automatically.
+    pub async fn lift<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        user_id: i32,
+        lifted_by: Option<i32>,
+    ) -> ModelResult<users::Model> {
+        let txn = db.begin().await?;
+
+        let user = users::Entity::find_by_id(user_id)
+            .one(&txn)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        Entity::update_many()
+            .col_expr(Column::LiftedAt, Expr::value(chrono::Utc::now()))
+            .col_expr(Column::LiftedBy, Expr::value(lifted_by))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::LiftedAt.is_null())
+            .exec(&txn)
+            .await?;
+
+        let mut user: users::ActiveModel = user.into();
+        user.is_banned = Set(false);
+        let user = user.update(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(user)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Finds the most recent ban on a user that has not been lifted
+    pub async fn find_active_for_user(
+        db: &DatabaseConnection,
+        user_id: i32,
+    ) -> ModelResult<Option<Model>> {
+        let ban = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::LiftedAt.is_null())
+            .order_by_desc(Column::Id)
+            .one(db)
+            .await?;
+        Ok(ban)
+    }
+
+    /// Lifts every ban whose expiry has passed. Returns how many users were unbanned.
+    pub async fn lift_expired(db: &DatabaseConnection) -> ModelResult<u64> {
+        let expired = Self::find()
+            .filter(Column::LiftedAt.is_null())
+            .filter(Column::ExpiresAt.lte(chrono::Utc::now()))
+            .all(db)
+            .await?;
+
+        let mut user_ids: Vec<i32> = expired.iter().map(|ban| ban.user_id).collect();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let mut lifted = 0;
+        for user_id in user_ids {
+            // A user may hold a newer, longer ban alongside the expired one
+            let still_banned = Self::find()
+                .filter(Column::UserId.eq(user_id))
+                .filter(Column::LiftedAt.is_null())
+                .all(db)
+                .await?
+                .iter()
+                .any(|ban| !ban.is_expired());
+            if still_banned {
+                continue;
+            }
+            Model::lift(db, user_id, None).await?;
+            lifted += 1;
+        }
+
+        Ok(lifted)
+    }
+}
@@ -0,0 +1,272 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{QueryOrder, QuerySelect, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+pub use super::_entities::board_categories::{ActiveModel, Model, Entity, Column};
+use crate::models::{boards, threads, users};
+use loco_rs::prelude::*;
+pub type BoardCategories = Entity;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct CategoryParams {
+    #[validate(length(min = 1, max = 100, message = "Title must be between 1 and 100 characters long."))]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub position: Option<i32>,
+}
+
+/// A board in the board tree, with its sub-boards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardNode {
+    pub id: i32,
+    pub title: String,
+    pub description: String,
+    pub position: i32,
+    pub is_archived: bool,
+    pub requires_verified_email: bool,
+    pub visibility: String,
+    pub posting_policy: String,
+    pub revisions_public: bool,
+    /// Threads in this board alone
+    pub num_threads: i32,
+    /// Threads in this board and its sub-boards
+    pub total_threads: i32,
+    /// When a thread in this board or its sub-boards was last posted in
+    pub last_active: Option<chrono::NaiveDateTime>,
+    pub sub_boards: Vec<BoardNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryNode {
+    pub id: i32,
+    pub title: String,
+    pub description: String,
+    pub position: i32,
+    pub total_threads: i32,
+    pub last_active: Option<chrono::NaiveDateTime>,
+    pub boards: Vec<BoardNode>,
+}
+
+/// Every board grouped by category. Boards without a category are listed
+/// separately so that nothing disappears from the tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardTree {
+    pub categories: Vec<CategoryNode>,
+    pub uncategorized: Vec<BoardNode>,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Creates a category, at the end of the display order unless a position is given
+    pub async fn create(db: &DatabaseConnection, params: &CategoryParams) -> ModelResult<Self> {
+        let position = match params.position {
+            Some(position) => position,
+            None => {
+                Entity::find()
+                    .select_only()
+                    .column_as(Column::Position.max(), "position")
+                    .into_tuple::<Option<i32>>()
+                    .one(db)
+                    .await?
+                    .flatten()
+                    .unwrap_or(0)
+                    + 1
+            }
+        };
+
+        let category = ActiveModel {
+            title: Set(params.title.clone()),
+            description: Set(params.description.clone()),
+            position: Set(position),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(category)
+    }
+
+    /// Deletes a category. Its boards are kept and become uncategorized.
+    pub async fn delete_category(db: &DatabaseConnection, category_id: i32) -> ModelResult<()> {
+        let txn = db.begin().await?;
+
+        let category = Entity::find_by_id(category_id)
+            .one(&txn)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        boards::Entity::update_many()
+            .col_expr(boards::Column::CategoryId, Expr::value(Option::<i32>::None))
+            .filter(boards::Column::CategoryId.eq(category_id))
+            .exec(&txn)
+            .await?;
+
+        category.delete(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {
+    pub async fn apply_update(mut self, db: &DatabaseConnection, params: &CategoryParams) -> ModelResult<Model> {
+        self.title = Set(params.title.clone());
+        self.description = Set(params.description.clone());
+        if let Some(position) = params.position {
+            self.position = Set(position);
+        }
+        Ok(self.update(db).await?)
+    }
+}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Builds the category → board → sub-board tree, rolling each sub-board's
+    /// thread count and latest activity up into its parent. Only boards `user`
+    /// can see are included, and archived boards are left out unless
+    /// `include_archived` is set. A sub-board whose parent is left out is
+    /// listed at the top level instead, in its parent's category unless it
+    /// has one of its own.
+    pub async fn find_tree(
+        db: &DatabaseConnection,
+        user: Option<&users::Model>,
+        include_archived: bool,
+    ) -> ModelResult<BoardTree> {
+        let categories = Self::find()
+            .order_by_asc(Column::Position)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+
+        let boards: Vec<boards::Model> = boards::Entity::find_visible(db, user)
+            .await?
+            .into_iter()
+            .filter(|board| include_archived || !board.is_archived)
+            .collect();
+
+        let last_active: Vec<(i32, Option<chrono::NaiveDateTime>)> = threads::Entity::find()
+            .filter(threads::Column::IsDeleted.eq(false))
+            .select_only()
+            .column(threads::Column::BoardId)
+            .column_as(threads::Column::LastActive.max(), "last_active")
+            .group_by(threads::Column::BoardId)
+            .into_tuple()
+            .all(db)
+            .await?;
+        let last_active_in = |board_id: i32| {
+            last_active
+                .iter()
+                .find(|(id, _)| *id == board_id)
+                .and_then(|(_, last_active)| *last_active)
+        };
+
+        let to_node = |board: &boards::Model| BoardNode {
+            id: board.id,
+            title: board.title.clone(),
+            description: board.description.clone(),
+            position: board.position,
+            is_archived: board.is_archived,
+            requires_verified_email: board.requires_verified_email,
+            visibility: board.visibility.clone(),
+            posting_policy: board.posting_policy.clone(),
+            revisions_public: board.revisions_public,
+            num_threads: board.num_threads,
+            total_threads: board.num_threads,
+            last_active: last_active_in(board.id),
+            sub_boards: vec![],
+        };
+
+        let is_listed = |board_id: i32| boards.iter().any(|board| board.id == board_id);
+        let top_level: Vec<BoardNode> = boards
+            .iter()
+            .filter(|board| board.parent_id.is_none_or(|parent_id| !is_listed(parent_id)))
+            .map(|board| {
+                let mut node = to_node(board);
+                node.sub_boards = boards
+                    .iter()
+                    .filter(|sub_board| sub_board.parent_id == Some(board.id))
+                    .map(to_node)
+                    .collect();
+                for sub_board in &node.sub_boards {
+                    node.total_threads += sub_board.total_threads;
+                    node.last_active = node.last_active.max(sub_board.last_active);
+                }
+                node
+            })
+            .collect();
+
+        // Parents may be hidden from `user`, so their categories are looked up separately
+        let board_categories: Vec<(i32, Option<i32>)> = boards::Entity::find()
+            .select_only()
+            .column(boards::Column::Id)
+            .column(boards::Column::CategoryId)
+            .into_tuple()
+            .all(db)
+            .await?;
+        let category_of_board = |board_id: i32| {
+            board_categories
+                .iter()
+                .find(|(id, _)| *id == board_id)
+                .and_then(|(_, category_id)| *category_id)
+        };
+        let category_of = |node: &BoardNode| {
+            boards
+                .iter()
+                .find(|board| board.id == node.id)
+                .and_then(|board| board.category_id.or_else(|| board.parent_id.and_then(category_of_board)))
+        };
+
+        let mut uncategorized = vec![];
+        let mut grouped: Vec<(i32, BoardNode)> = vec![];
+        for node in top_level {
+            match category_of(&node).filter(|id| categories.iter().any(|c| c.id == *id)) {
+                Some(category_id) => grouped.push((category_id, node)),
+                None => uncategorized.push(node),
+            }
+        }
+
+        let categories = categories
+            .into_iter()
+            .map(|category| {
+                let boards: Vec<BoardNode> = grouped
+                    .iter()
+                    .filter(|(category_id, _)| *category_id == category.id)
+                    .map(|(_, node)| node.clone())
+                    .collect();
+                CategoryNode {
+                    id: category.id,
+                    title: category.title,
+                    description: category.description,
+                    position: category.position,
+                    total_threads: boards.iter().map(|board| board.total_threads).sum(),
+                    last_active: boards.iter().filter_map(|board| board.last_active).max(),
+                    boards,
+                }
+            })
+            .collect();
+
+        Ok(BoardTree {
+            categories,
+            uncategorized,
+        })
+    }
+}
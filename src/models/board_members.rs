@@ -0,0 +1,131 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{JoinType, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::board_members::{ActiveModel, Model, Entity, Column};
+use crate::models::{boards, users};
+use loco_rs::prelude::*;
+pub type BoardMembers = Entity;
+
+/// A member of a board, as listed to moderators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardMemberResponse {
+    pub user_id: i32,
+    pub name: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Adds `user_id` to the members of `board_id`
+    ///
+    /// # Errors
+    ///
+    /// `EntityNotFound` if the board or user doesn't exist, and
+    /// `EntityAlreadyExists` if the user is already a member
+    pub async fn add<C: ConnectionTrait>(db: &C, board_id: i32, user_id: i32) -> ModelResult<Self> {
+        boards::Entity::find_by_id(board_id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        users::Entity::find_by_id(user_id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        if Entity::is_member(db, board_id, user_id).await? {
+            return Err(ModelError::EntityAlreadyExists);
+        }
+
+        let member = ActiveModel {
+            board_id: Set(board_id),
+            user_id: Set(user_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(member)
+    }
+
+    /// Removes `user_id` from the members of `board_id`
+    pub async fn remove<C: ConnectionTrait>(db: &C, board_id: i32, user_id: i32) -> ModelResult<()> {
+        let result = Entity::delete_many()
+            .filter(Column::BoardId.eq(board_id))
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(ModelError::EntityNotFound);
+        }
+        Ok(())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    pub async fn is_member<C: ConnectionTrait>(db: &C, board_id: i32, user_id: i32) -> ModelResult<bool> {
+        let member = Self::find()
+            .filter(Column::BoardId.eq(board_id))
+            .filter(Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+        Ok(member.is_some())
+    }
+
+    /// Ids of the boards `user_id` is a member of
+    pub async fn board_ids_for_user(db: &DatabaseConnection, user_id: i32) -> ModelResult<Vec<i32>> {
+        let board_ids = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .select_only()
+            .column(Column::BoardId)
+            .into_tuple()
+            .all(db)
+            .await?;
+        Ok(board_ids)
+    }
+
+    /// Members of `board_id` in the order they joined
+    pub async fn find_for_board(db: &DatabaseConnection, board_id: i32) -> ModelResult<Vec<BoardMemberResponse>> {
+        let members = Self::find()
+            .filter(Column::BoardId.eq(board_id))
+            .join(JoinType::InnerJoin, super::_entities::board_members::Relation::Users.def())
+            .select_only()
+            .column(Column::UserId)
+            .column_as(users::users::Column::Name, "name")
+            .column(Column::CreatedAt)
+            .order_by_asc(Column::Id)
+            .into_tuple::<(i32, String, DateTimeWithTimeZone)>()
+            .all(db)
+            .await?;
+
+        let members = members
+            .into_iter()
+            .map(|(user_id, name, created_at)| BoardMemberResponse {
+                user_id,
+                name,
+                created_at,
+            })
+            .collect();
+        Ok(members)
+    }
+}
@@ -0,0 +1,131 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{JoinType, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::board_moderators::{ActiveModel, Model, Entity, Column};
+use crate::models::{boards, users};
+use loco_rs::prelude::*;
+pub type BoardModerators = Entity;
+
+/// A moderator of a board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardModeratorResponse {
+    pub user_id: i32,
+    pub name: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Adds `user_id` to the moderators of `board_id`
+    ///
+    /// # Errors
+    ///
+    /// `EntityNotFound` if the board or user doesn't exist, and
+    /// `EntityAlreadyExists` if the user is already a moderator of it
+    pub async fn add<C: ConnectionTrait>(db: &C, board_id: i32, user_id: i32) -> ModelResult<Self> {
+        boards::Entity::find_by_id(board_id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        users::Entity::find_by_id(user_id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        if Entity::is_moderator(db, board_id, user_id).await? {
+            return Err(ModelError::EntityAlreadyExists);
+        }
+
+        let moderator = ActiveModel {
+            board_id: Set(board_id),
+            user_id: Set(user_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(moderator)
+    }
+
+    /// Removes `user_id` from the moderators of `board_id`
+    pub async fn remove<C: ConnectionTrait>(db: &C, board_id: i32, user_id: i32) -> ModelResult<()> {
+        let result = Entity::delete_many()
+            .filter(Column::BoardId.eq(board_id))
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(ModelError::EntityNotFound);
+        }
+        Ok(())
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    pub async fn is_moderator<C: ConnectionTrait>(db: &C, board_id: i32, user_id: i32) -> ModelResult<bool> {
+        let moderator = Self::find()
+            .filter(Column::BoardId.eq(board_id))
+            .filter(Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+        Ok(moderator.is_some())
+    }
+
+    /// Ids of the boards `user_id` moderates
+    pub async fn board_ids_for_user(db: &DatabaseConnection, user_id: i32) -> ModelResult<Vec<i32>> {
+        let board_ids = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .select_only()
+            .column(Column::BoardId)
+            .into_tuple()
+            .all(db)
+            .await?;
+        Ok(board_ids)
+    }
+
+    /// Moderators of `board_id` in the order they were added
+    pub async fn find_for_board(db: &DatabaseConnection, board_id: i32) -> ModelResult<Vec<BoardModeratorResponse>> {
+        let moderators = Self::find()
+            .filter(Column::BoardId.eq(board_id))
+            .join(JoinType::InnerJoin, super::_entities::board_moderators::Relation::Users.def())
+            .select_only()
+            .column(Column::UserId)
+            .column_as(users::users::Column::Name, "name")
+            .column(Column::CreatedAt)
+            .order_by_asc(Column::Id)
+            .into_tuple::<(i32, String, DateTimeWithTimeZone)>()
+            .all(db)
+            .await?;
+
+        let moderators = moderators
+            .into_iter()
+            .map(|(user_id, name, created_at)| BoardModeratorResponse {
+                user_id,
+                name,
+                created_at,
+            })
+            .collect();
+        Ok(moderators)
+    }
+}
@@ -1,8 +1,195 @@
 use sea_orm::entity::prelude::*;
-pub use super::_entities::boards::{self, ActiveModel, Model, Entity};
+use sea_orm::{PaginatorTrait, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+pub use super::_entities::boards::{self, ActiveModel, Model, Entity, Column};
+use crate::models::{board_categories, board_members, board_moderators, threads, users};
 use loco_rs::prelude::*;
 pub type Boards = Entity;
 
+/// Who can see a board and its threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardVisibility {
+    Public,
+    LoggedIn,
+    Members,
+}
+
+impl BoardVisibility {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::LoggedIn => "logged_in",
+            Self::Members => "members",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "public" => Some(Self::Public),
+            "logged_in" => Some(Self::LoggedIn),
+            "members" => Some(Self::Members),
+            _ => None,
+        }
+    }
+}
+
+/// Who can start threads and reply in a board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostingPolicy {
+    Everyone,
+    Members,
+    /// Announcement boards
+    Moderators,
+}
+
+impl PostingPolicy {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Everyone => "everyone",
+            Self::Members => "members",
+            Self::Moderators => "moderators",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "everyone" => Some(Self::Everyone),
+            "members" => Some(Self::Members),
+            "moderators" => Some(Self::Moderators),
+            _ => None,
+        }
+    }
+}
+
+fn validate_visibility(visibility: &str) -> Result<(), validator::ValidationError> {
+    if BoardVisibility::parse(visibility).is_none() {
+        return Err(validator::ValidationError::new("Visibility must be public, logged_in or members."));
+    }
+    Ok(())
+}
+
+fn validate_posting_policy(posting_policy: &str) -> Result<(), validator::ValidationError> {
+    if PostingPolicy::parse(posting_policy).is_none() {
+        return Err(validator::ValidationError::new("Posting policy must be everyone, members or moderators."));
+    }
+    Ok(())
+}
+
+fn default_visibility() -> String {
+    BoardVisibility::Public.as_str().to_string()
+}
+
+fn default_posting_policy() -> String {
+    PostingPolicy::Everyone.as_str().to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct CreateBoardParams {
+    #[validate(length(min = 1, max = 100, message = "Title must be between 1 and 100 characters long."))]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub requires_verified_email: bool,
+    pub category_id: Option<i32>,
+    /// Makes this a sub-board of a top-level board
+    pub parent_id: Option<i32>,
+    #[serde(default = "default_visibility")]
+    #[validate(custom(function = "validate_visibility"))]
+    pub visibility: String,
+    #[serde(default = "default_posting_policy")]
+    #[validate(custom(function = "validate_posting_policy"))]
+    pub posting_policy: String,
+    /// Lets everyone who can see the board see the edit history of its
+    /// replies, not just moderators and the posters themselves
+    #[serde(default)]
+    pub revisions_public: bool,
+}
+
+/// Changes to a board. Unset fields are left as they are.
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
+pub struct UpdateBoardParams {
+    #[validate(length(min = 1, max = 100, message = "Title must be between 1 and 100 characters long."))]
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub requires_verified_email: Option<bool>,
+    pub position: Option<i32>,
+    pub is_archived: Option<bool>,
+    /// `null` removes the board from its category
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub category_id: Option<Option<i32>>,
+    /// `null` makes the board top-level
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub parent_id: Option<Option<i32>>,
+    #[validate(custom(function = "validate_visibility"))]
+    pub visibility: Option<String>,
+    #[validate(custom(function = "validate_posting_policy"))]
+    pub posting_policy: Option<String>,
+    pub revisions_public: Option<bool>,
+}
+
+/// Lets an update tell a field set to `null` apart from one that was left out
+fn deserialize_some<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Checks that a board can sit in `category_id` under `parent_id`. Boards
+/// nest one level deep, so the parent must be top-level and a board that
+/// has sub-boards can't become one. `board_id` is None for a new board.
+async fn validate_placement<C: ConnectionTrait>(
+    db: &C,
+    board_id: Option<i32>,
+    category_id: Option<i32>,
+    parent_id: Option<i32>,
+) -> Result<()> {
+    if let Some(category_id) = category_id {
+        board_categories::Entity::find_by_id(category_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| loco_rs::Error::BadRequest("Category not found".to_string()))?;
+    }
+
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    if board_id == Some(parent_id) {
+        return Err(loco_rs::Error::BadRequest("A board can't be its own parent".to_string()));
+    }
+
+    let parent = Entity::find_by_id(parent_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| loco_rs::Error::BadRequest("Parent board not found".to_string()))?;
+    if parent.parent_id.is_some() {
+        return Err(loco_rs::Error::BadRequest("Sub-boards can't have sub-boards of their own".to_string()));
+    }
+
+    if let Some(board_id) = board_id {
+        let has_sub_boards = Entity::find()
+            .filter(Column::ParentId.eq(board_id))
+            .count(db)
+            .await?
+            > 0;
+        if has_sub_boards {
+            return Err(loco_rs::Error::BadRequest("A board with sub-boards can't become a sub-board".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
@@ -21,15 +208,213 @@ impl ActiveModelBehavior for ActiveModel {
 
 // implement your read-oriented logic here
 impl Model {
-    /// Find all boards
+    /// Falls back to the most restrictive setting if the column holds
+    /// something unexpected
+    #[must_use]
+    pub fn visibility(&self) -> BoardVisibility {
+        BoardVisibility::parse(&self.visibility).unwrap_or(BoardVisibility::Members)
+    }
+
+    #[must_use]
+    pub fn posting_policy(&self) -> PostingPolicy {
+        PostingPolicy::parse(&self.posting_policy).unwrap_or(PostingPolicy::Moderators)
+    }
+
+    /// Whether `user` (None for anonymous visitors) can see this board.
+    /// `member_board_ids` are the boards the user belongs to or moderates.
+    #[must_use]
+    pub fn is_visible_to(&self, user: Option<&users::Model>, member_board_ids: &[i32]) -> bool {
+        match (self.visibility(), user) {
+            (BoardVisibility::Public, _) => true,
+            (_, None) => false,
+            (BoardVisibility::LoggedIn, Some(_)) => true,
+            (BoardVisibility::Members, Some(user)) => {
+                user.role().is_global_moderator() || member_board_ids.contains(&self.id)
+            }
+        }
+    }
+
+    /// Find all boards in display order
     pub async fn find_all(db: &DatabaseConnection) -> ModelResult<Vec<Self>> {
-        let boards = Entity::find().all(db).await?;
+        let boards = Entity::find()
+            .order_by_asc(Column::Position)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
         Ok(boards)
     }
+
+    /// Creates a board at the end of the display order
+    pub async fn create<C: ConnectionTrait>(db: &C, params: &CreateBoardParams) -> Result<Self> {
+        validate_placement(db, None, params.category_id, params.parent_id).await?;
+
+        let last_position = Entity::find()
+            .select_only()
+            .column_as(Column::Position.max(), "position")
+            .into_tuple::<Option<i32>>()
+            .one(db)
+            .await?
+            .flatten()
+            .unwrap_or(0);
+
+        let board = ActiveModel {
+            title: Set(params.title.clone()),
+            description: Set(params.description.clone()),
+            requires_verified_email: Set(params.requires_verified_email),
+            num_threads: Set(0),
+            position: Set(last_position + 1),
+            category_id: Set(params.category_id),
+            parent_id: Set(params.parent_id),
+            visibility: Set(params.visibility.clone()),
+            posting_policy: Set(params.posting_policy.clone()),
+            revisions_public: Set(params.revisions_public),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(board)
+    }
+
+    /// Deletes a board. A board that still has threads is only deleted when
+    /// `cascade` is set, in which case its threads are purged along with it.
+    /// Run this in a transaction so that either everything goes or nothing
+    /// does. Returns the keys of the purged replies' images, for the caller
+    /// to delete with [`threads::delete_purged_images`] once it has committed.
+    pub async fn delete_board<C: ConnectionTrait>(db: &C, board_id: i32, cascade: bool) -> Result<(Self, Vec<String>)> {
+        let board = Entity::find_by_id(board_id)
+            .one(db)
+            .await?
+            .ok_or(loco_rs::Error::NotFound)?;
+
+        // Soft-deleted threads still reference the board, so they count too
+        let thread_ids: Vec<i32> = threads::Entity::find()
+            .filter(threads::Column::BoardId.eq(board_id))
+            .select_only()
+            .column(threads::Column::Id)
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        if !thread_ids.is_empty() && !cascade {
+            return Err(loco_rs::Error::BadRequest(
+                "This board still has threads. Move them to another board first, or delete them along with the board.".to_string(),
+            ));
+        }
+
+        let mut image_keys = Vec::new();
+        for thread_id in thread_ids {
+            let (_, thread_image_keys) = threads::Model::purge(db, thread_id).await?;
+            image_keys.extend(thread_image_keys);
+        }
+
+        board.clone().delete(db).await?;
+
+        Ok((board, image_keys))
+    }
 }
 
 // implement your write-oriented logic here
-impl ActiveModel {}
+impl ActiveModel {
+    /// Applies an admin's changes to the board
+    pub async fn apply_update<C: ConnectionTrait>(
+        mut self,
+        db: &C,
+        params: &UpdateBoardParams,
+    ) -> Result<Model> {
+        if params.category_id.is_some() || params.parent_id.is_some() {
+            let category_id = params.category_id.unwrap_or_else(|| self.category_id.clone().unwrap());
+            let parent_id = params.parent_id.unwrap_or_else(|| self.parent_id.clone().unwrap());
+            validate_placement(db, Some(self.id.clone().unwrap()), category_id, parent_id).await?;
+        }
+
+        if let Some(title) = &params.title {
+            self.title = Set(title.clone());
+        }
+        if let Some(description) = &params.description {
+            self.description = Set(description.clone());
+        }
+        if let Some(requires_verified_email) = params.requires_verified_email {
+            self.requires_verified_email = Set(requires_verified_email);
+        }
+        if let Some(position) = params.position {
+            self.position = Set(position);
+        }
+        if let Some(is_archived) = params.is_archived {
+            self.is_archived = Set(is_archived);
+        }
+        if let Some(category_id) = params.category_id {
+            self.category_id = Set(category_id);
+        }
+        if let Some(parent_id) = params.parent_id {
+            self.parent_id = Set(parent_id);
+        }
+        if let Some(visibility) = &params.visibility {
+            self.visibility = Set(visibility.clone());
+        }
+        if let Some(posting_policy) = &params.posting_policy {
+            self.posting_policy = Set(posting_policy.clone());
+        }
+        if let Some(revisions_public) = params.revisions_public {
+            self.revisions_public = Set(revisions_public);
+        }
+        Ok(self.update(db).await?)
+    }
+}
 
 // implement your custom finders, selectors oriented logic here
-impl Entity {}
+impl Entity {
+    /// The boards `user` belongs to or moderates, as passed to
+    /// [`Model::is_visible_to`]. Anonymous visitors belong to none.
+    pub async fn member_board_ids(db: &DatabaseConnection, user: Option<&users::Model>) -> ModelResult<Vec<i32>> {
+        let Some(user) = user else {
+            return Ok(vec![]);
+        };
+        let mut board_ids = board_members::Entity::board_ids_for_user(db, user.id).await?;
+        board_ids.extend(board_moderators::Entity::board_ids_for_user(db, user.id).await?);
+        Ok(board_ids)
+    }
+
+    /// All boards `user` can see, in display order
+    pub async fn find_visible(db: &DatabaseConnection, user: Option<&users::Model>) -> ModelResult<Vec<Model>> {
+        let member_board_ids = Self::member_board_ids(db, user).await?;
+
+        let boards = Model::find_all(db)
+            .await?
+            .into_iter()
+            .filter(|board| board.is_visible_to(user, &member_board_ids))
+            .collect();
+        Ok(boards)
+    }
+
+    /// Sets the display order of the boards to the order of `board_ids`.
+    /// Every board must be listed exactly once. Returns the boards whose
+    /// position changed, as they were before.
+    pub async fn reorder<C: ConnectionTrait>(db: &C, board_ids: &[i32]) -> Result<Vec<Model>> {
+        let num_boards = Self::find().count(db).await?;
+        let mut unique_ids = board_ids.to_vec();
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+        if unique_ids.len() != board_ids.len() || board_ids.len() as u64 != num_boards {
+            return Err(loco_rs::Error::BadRequest("Every board must be listed exactly once".to_string()));
+        }
+
+        let mut moved = Vec::new();
+        for (position, board_id) in board_ids.iter().enumerate() {
+            let board = Self::find_by_id(*board_id)
+                .one(db)
+                .await?
+                .ok_or(loco_rs::Error::NotFound)?;
+            let position = i32::try_from(position).unwrap_or(i32::MAX) + 1;
+            if board.position == position {
+                continue;
+            }
+            let mut active_board: ActiveModel = board.clone().into();
+            active_board.position = Set(position);
+            active_board.update(db).await?;
+            moved.push(board);
+        }
+
+        Ok(moved)
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::Func;
+use sea_orm::{IntoActiveModel, QueryOrder, QuerySelect, Set};
+pub use super::_entities::mentions::{ActiveModel, Model, Entity, Column};
+use crate::markdown;
+use crate::models::{replies, users};
+pub type Mentions = Entity;
+
+/// Finds the users called `names`, ignoring case. If two users' names only
+/// differ by case, the one who registered first is mentioned.
+pub async fn resolve<C: ConnectionTrait>(db: &C, names: &[String]) -> Result<markdown::Mentions, DbErr> {
+    if names.is_empty() {
+        return Ok(markdown::Mentions::new());
+    }
+
+    let lowercased: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+    let found: Vec<(i32, String)> = users::Entity::find()
+        .filter(Expr::expr(Func::lower(Expr::col(users::users::Column::Name))).is_in(lowercased))
+        // Later entries replace earlier ones, so the oldest account goes last
+        .order_by_desc(users::users::Column::Id)
+        .select_only()
+        .column(users::users::Column::Id)
+        .column(users::users::Column::Name)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(found.into_iter().map(|(id, name)| (name.to_lowercase(), id)).collect())
+}
+
+/// Renders a reply body with its mentions linked to the users' profiles
+pub async fn render<C: ConnectionTrait>(db: &C, body: &str) -> Result<String, DbErr> {
+    let mentions = resolve(db, &markdown::mentioned_names(body)).await?;
+    Ok(markdown::render(body, &mentions))
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Ids of the users `reply_id` mentions
+    pub async fn user_ids_for_reply<C: ConnectionTrait>(db: &C, reply_id: i32) -> Result<Vec<i32>, DbErr> {
+        Self::find()
+            .filter(Column::ReplyId.eq(reply_id))
+            .filter(Column::RemovedAt.is_null())
+            .select_only()
+            .column(Column::UserId)
+            .order_by_asc(Column::Id)
+            .into_tuple()
+            .all(db)
+            .await
+    }
+
+    /// Brings the mentions stored for `reply` in line with its body, and
+    /// returns the ids of the users it has never mentioned before. Posters
+    /// mentioning themselves don't count. Mentions taken out by an edit are
+    /// only marked as removed, so putting one back doesn't count as new and
+    /// nobody is notified twice about the same reply.
+    pub async fn sync<C: ConnectionTrait>(db: &C, reply: &replies::Model) -> Result<Vec<i32>, DbErr> {
+        let mentioned: HashSet<i32> = resolve(db, &markdown::mentioned_names(&reply.body))
+            .await?
+            .into_values()
+            .filter(|user_id| *user_id != reply.poster)
+            .collect();
+        let existing = Self::find()
+            .filter(Column::ReplyId.eq(reply.id))
+            .all(db)
+            .await?;
+
+        let now: DateTimeWithTimeZone = chrono::Utc::now().into();
+        let mut known = HashSet::new();
+        for mention in existing {
+            known.insert(mention.user_id);
+            let removed_at = if mentioned.contains(&mention.user_id) { None } else { Some(now) };
+            if mention.removed_at.is_some() != removed_at.is_some() {
+                let mut mention = mention.into_active_model();
+                mention.removed_at = Set(removed_at);
+                mention.update(db).await?;
+            }
+        }
+
+        let mut added: Vec<i32> = mentioned.difference(&known).copied().collect();
+        added.sort_unstable();
+        for user_id in &added {
+            ActiveModel {
+                reply_id: Set(reply.id),
+                user_id: Set(*user_id),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(added)
+    }
+}
@@ -4,3 +4,14 @@ pub mod boards;
 pub mod threads;
 pub mod replies;
 pub mod reply_statuses;
+pub mod bans;
+pub mod mod_actions;
+pub mod reports;
+pub mod board_categories;
+pub mod board_members;
+pub mod board_moderators;
+pub mod search;
+pub mod reply_revisions;
+pub mod mentions;
+pub mod notifications;
+pub mod thread_subscriptions;
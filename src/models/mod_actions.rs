@@ -0,0 +1,257 @@
+use sea_orm::entity::prelude::*;
+use loco_rs::prelude::*;
+use sea_orm::{JoinType, PaginatorTrait, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::mod_actions::{ActiveModel, Model, Entity, Column};
+use crate::models::users;
+pub type ModActions = Entity;
+
+/// The kinds of moderator action recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModAction {
+    DeleteThread,
+    RestoreThread,
+    PurgeThread,
+    LockThread,
+    UnlockThread,
+    PinThread,
+    UnpinThread,
+    ArchiveThread,
+    UnarchiveThread,
+    MoveThread,
+    MergeThread,
+    SplitThread,
+    HideReply,
+    DeleteReply,
+    BanUser,
+    UnbanUser,
+    DismissReports,
+    CreateBoard,
+    UpdateBoard,
+    DeleteBoard,
+    AddBoardMember,
+    RemoveBoardMember,
+    AddBoardModerator,
+    RemoveBoardModerator,
+    SetRole,
+}
+
+impl ModAction {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DeleteThread => "delete_thread",
+            Self::RestoreThread => "restore_thread",
+            Self::PurgeThread => "purge_thread",
+            Self::LockThread => "lock_thread",
+            Self::UnlockThread => "unlock_thread",
+            Self::PinThread => "pin_thread",
+            Self::UnpinThread => "unpin_thread",
+            Self::ArchiveThread => "archive_thread",
+            Self::UnarchiveThread => "unarchive_thread",
+            Self::MoveThread => "move_thread",
+            Self::MergeThread => "merge_thread",
+            Self::SplitThread => "split_thread",
+            Self::HideReply => "hide_reply",
+            Self::DeleteReply => "delete_reply",
+            Self::BanUser => "ban_user",
+            Self::UnbanUser => "unban_user",
+            Self::DismissReports => "dismiss_reports",
+            Self::CreateBoard => "create_board",
+            Self::UpdateBoard => "update_board",
+            Self::DeleteBoard => "delete_board",
+            Self::AddBoardMember => "add_board_member",
+            Self::RemoveBoardMember => "remove_board_member",
+            Self::AddBoardModerator => "add_board_moderator",
+            Self::RemoveBoardModerator => "remove_board_moderator",
+            Self::SetRole => "set_role",
+        }
+    }
+}
+
+/// What a moderator action was taken against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModTarget {
+    Board(i32),
+    Thread(i32),
+    Reply(i32),
+    User(i32),
+}
+
+impl ModTarget {
+    #[must_use]
+    pub fn type_name(self) -> &'static str {
+        match self {
+            Self::Board(_) => "board",
+            Self::Thread(_) => "thread",
+            Self::Reply(_) => "reply",
+            Self::User(_) => "user",
+        }
+    }
+
+    #[must_use]
+    pub fn id(self) -> i32 {
+        match self {
+            Self::Board(id) | Self::Thread(id) | Self::Reply(id) | Self::User(id) => id,
+        }
+    }
+}
+
+/// A moderator action to be written to the audit log
+#[derive(Debug, Clone)]
+pub struct NewModAction {
+    pub actor_id: i32,
+    pub action: ModAction,
+    pub target: ModTarget,
+    /// The user whose account or content was acted on
+    pub target_user_id: Option<i32>,
+    /// The board containing the thread or reply that was acted on
+    pub board_id: Option<i32>,
+    pub reason: Option<String>,
+    /// A snapshot of the target taken before the action changed it
+    pub previous_state: Option<serde_json::Value>,
+}
+
+/// Filters for browsing the audit log. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct ModActionFilter {
+    pub actor_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub board_id: Option<i32>,
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModActionResponse {
+    pub id: i32,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i32,
+    pub reason: Option<String>,
+    pub previous_state: Option<serde_json::Value>,
+    pub actor_id: i32,
+    pub actor_name: String,
+    pub target_user_id: Option<i32>,
+    pub board_id: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Writes a moderator action to the audit log. Pass the transaction the
+    /// action itself was made in, so that neither is kept without the other.
+    /// The user it was taken against is told separately, once it's committed,
+    /// with [`crate::models::notifications::Model::notify_mod_action`].
+    pub async fn record<C>(db: &C, entry: NewModAction) -> ModelResult<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let action = ActiveModel {
+            actor_id: Set(entry.actor_id),
+            action: Set(entry.action.as_str().to_string()),
+            target_type: Set(entry.target.type_name().to_string()),
+            target_id: Set(entry.target.id()),
+            target_user_id: Set(entry.target_user_id),
+            board_id: Set(entry.board_id),
+            reason: Set(entry.reason.filter(|reason| !reason.trim().is_empty())),
+            previous_state: Set(entry.previous_state),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(action)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Returns a page of audit log entries matching `filter`, newest first,
+    /// along with the total number of matching entries.
+    pub async fn find_paginated(
+        db: &DatabaseConnection,
+        filter: &ModActionFilter,
+        page_size: u64,
+        page_number: u64,
+    ) -> ModelResult<(Vec<ModActionResponse>, u64)> {
+        let mut query = Self::find();
+        if let Some(actor_id) = filter.actor_id {
+            query = query.filter(Column::ActorId.eq(actor_id));
+        }
+        if let Some(target_user_id) = filter.target_user_id {
+            query = query.filter(Column::TargetUserId.eq(target_user_id));
+        }
+        if let Some(board_id) = filter.board_id {
+            query = query.filter(Column::BoardId.eq(board_id));
+        }
+        if let Some(action) = &filter.action {
+            query = query.filter(Column::Action.eq(action.as_str()));
+        }
+
+        let total_count = query.clone().count(db).await?;
+
+        let actions = query
+            .join(JoinType::InnerJoin, crate::models::_entities::mod_actions::Relation::Actors.def())
+            .order_by_desc(Column::Id)
+            .offset(page_size * page_number)
+            .limit(page_size)
+            .select_only()
+            .columns([
+                Column::Id,
+                Column::Action,
+                Column::TargetType,
+                Column::TargetId,
+                Column::Reason,
+                Column::PreviousState,
+                Column::ActorId,
+                Column::TargetUserId,
+                Column::BoardId,
+                Column::CreatedAt,
+            ])
+            .column_as(users::users::Column::Name, "actor_name")
+            .into_tuple::<(i32, String, String, i32, Option<String>, Option<serde_json::Value>, i32, Option<i32>, Option<i32>, DateTimeWithTimeZone, String)>()
+            .all(db)
+            .await?;
+
+        let result = actions
+            .into_iter()
+            .map(|(id, action, target_type, target_id, reason, previous_state, actor_id, target_user_id, board_id, created_at, actor_name)| {
+                ModActionResponse {
+                    id,
+                    action,
+                    target_type,
+                    target_id,
+                    reason,
+                    previous_state,
+                    actor_id,
+                    actor_name,
+                    target_user_id,
+                    board_id,
+                    created_at,
+                }
+            })
+            .collect();
+
+        Ok((result, total_count))
+    }
+}
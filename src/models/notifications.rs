@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use sea_orm::entity::prelude::*;
+use loco_rs::prelude::*;
+use sea_orm::{PaginatorTrait, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::notifications::{ActiveModel, Model, Entity, Column};
+use crate::models::{mod_actions, replies, threads, users};
+pub type Notifications = Entity;
+
+/// What a notification tells its user about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Someone replied to one of the user's replies
+    Reply,
+    /// Someone posted in a thread the user started
+    ThreadReply,
+    /// Someone mentioned the user in a reply
+    Mention,
+    /// A moderator acted on one of the user's threads or replies
+    ModAction,
+}
+
+impl NotificationKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reply => "reply",
+            Self::ThreadReply => "thread_reply",
+            Self::Mention => "mention",
+            Self::ModAction => "mod_action",
+        }
+    }
+}
+
+/// A notification to be sent to `user_id` about something `actor_id` did
+#[derive(Debug, Clone)]
+pub struct NewNotification {
+    pub user_id: i32,
+    pub actor_id: i32,
+    pub kind: NotificationKind,
+    pub board_id: Option<i32>,
+    pub thread_id: Option<i32>,
+    pub reply_id: Option<i32>,
+    pub mod_action_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationResponse {
+    pub id: i32,
+    pub kind: String,
+    pub actor_id: i32,
+    pub actor_name: String,
+    pub board_id: Option<i32>,
+    pub thread_id: Option<i32>,
+    pub thread_title: Option<String>,
+    pub reply_id: Option<i32>,
+    /// For moderator actions, what was done and why
+    pub action: Option<String>,
+    pub reason: Option<String>,
+    pub is_read: bool,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Sends a notification. Nobody is notified about their own doings, in
+    /// which case nothing is saved and `None` is returned.
+    pub async fn notify<C>(db: &C, entry: NewNotification) -> ModelResult<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        if entry.user_id == entry.actor_id {
+            return Ok(None);
+        }
+
+        let notification = ActiveModel {
+            kind: Set(entry.kind.as_str().to_string()),
+            user_id: Set(entry.user_id),
+            actor_id: Set(entry.actor_id),
+            board_id: Set(entry.board_id),
+            thread_id: Set(entry.thread_id),
+            reply_id: Set(entry.reply_id),
+            mod_action_id: Set(entry.mod_action_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(Some(notification))
+    }
+
+    /// Tells the user whose thread or reply `action` was taken against.
+    /// Dismissed reports aren't passed on, since they'd tell the poster who
+    /// was reported. Purged and merged threads are gone by the time the
+    /// action is logged, so the notification only points at content that's
+    /// still there.
+    pub async fn notify_mod_action<C>(db: &C, action: &mod_actions::Model) -> ModelResult<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let Some(user_id) = action.target_user_id else {
+            return Ok(None);
+        };
+        if action.action == mod_actions::ModAction::DismissReports.as_str() {
+            return Ok(None);
+        }
+
+        let (thread_id, reply_id) = match action.target_type.as_str() {
+            "thread" => (Some(action.target_id), None),
+            "reply" => match replies::Entity::find_by_id(action.target_id).one(db).await? {
+                Some(reply) => (Some(reply.thread_id), Some(reply.id)),
+                None => (None, None),
+            },
+            _ => return Ok(None),
+        };
+        let thread = match thread_id {
+            Some(thread_id) => threads::Entity::find_by_id(thread_id).one(db).await?,
+            None => None,
+        };
+
+        Self::notify(db, NewNotification {
+            user_id,
+            actor_id: action.actor_id,
+            kind: NotificationKind::ModAction,
+            // A moved thread is linked in the board it's in now
+            board_id: thread.as_ref().map(|thread| thread.board_id).or(action.board_id),
+            thread_id: thread.as_ref().map(|thread| thread.id),
+            reply_id: reply_id.filter(|_| thread.is_some()),
+            mod_action_id: Some(action.id),
+        })
+        .await
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Returns a page of `user_id`'s notifications, newest first, along with
+    /// how many there are in total
+    pub async fn find_paginated(
+        db: &DatabaseConnection,
+        user_id: i32,
+        unread_only: bool,
+        page_size: u64,
+        page_number: u64,
+    ) -> ModelResult<(Vec<NotificationResponse>, u64)> {
+        let mut query = Self::find().filter(Column::UserId.eq(user_id));
+        if unread_only {
+            query = query.filter(Column::ReadAt.is_null());
+        }
+
+        let total_count = query.clone().count(db).await?;
+
+        let notifications = query
+            .order_by_desc(Column::Id)
+            .offset(page_size * page_number)
+            .limit(page_size)
+            .all(db)
+            .await?;
+
+        let actor_ids: Vec<i32> = notifications.iter().map(|notification| notification.actor_id).collect();
+        let actor_names: HashMap<i32, String> = users::Entity::find()
+            .filter(users::users::Column::Id.is_in(actor_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user.name))
+            .collect();
+
+        let thread_ids: Vec<i32> = notifications.iter().filter_map(|notification| notification.thread_id).collect();
+        let thread_titles: HashMap<i32, String> = threads::Entity::find()
+            .filter(threads::Column::Id.is_in(thread_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|thread| (thread.id, thread.title))
+            .collect();
+
+        let mod_action_ids: Vec<i32> = notifications.iter().filter_map(|notification| notification.mod_action_id).collect();
+        let mod_actions: HashMap<i32, mod_actions::Model> = mod_actions::Entity::find()
+            .filter(mod_actions::Column::Id.is_in(mod_action_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|action| (action.id, action))
+            .collect();
+
+        let result = notifications
+            .into_iter()
+            .map(|notification| {
+                let mod_action = notification.mod_action_id.and_then(|id| mod_actions.get(&id));
+                NotificationResponse {
+                    id: notification.id,
+                    kind: notification.kind,
+                    actor_id: notification.actor_id,
+                    actor_name: actor_names.get(&notification.actor_id).cloned().unwrap_or_default(),
+                    board_id: notification.board_id,
+                    thread_id: notification.thread_id,
+                    thread_title: notification.thread_id.and_then(|id| thread_titles.get(&id).cloned()),
+                    reply_id: notification.reply_id,
+                    action: mod_action.map(|action| action.action.clone()),
+                    reason: mod_action.and_then(|action| action.reason.clone()),
+                    is_read: notification.read_at.is_some(),
+                    created_at: notification.created_at,
+                }
+            })
+            .collect();
+
+        Ok((result, total_count))
+    }
+
+    /// How many of `user_id`'s notifications haven't been read yet
+    pub async fn unread_count(db: &DatabaseConnection, user_id: i32) -> ModelResult<u64> {
+        let count = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::ReadAt.is_null())
+            .count(db)
+            .await?;
+        Ok(count)
+    }
+
+    /// Marks one of `user_id`'s notifications as read. Someone else's
+    /// notification is reported as missing.
+    pub async fn mark_read(db: &DatabaseConnection, user_id: i32, notification_id: i32) -> ModelResult<Model> {
+        let notification = Self::find_by_id(notification_id)
+            .filter(Column::UserId.eq(user_id))
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        if notification.read_at.is_some() {
+            return Ok(notification);
+        }
+
+        let mut notification = notification.into_active_model();
+        notification.read_at = Set(Some(chrono::Utc::now().into()));
+        Ok(notification.update(db).await?)
+    }
+
+    /// Marks all of `user_id`'s notifications as read, returning how many
+    /// were unread
+    pub async fn mark_all_read(db: &DatabaseConnection, user_id: i32) -> ModelResult<u64> {
+        let result = Self::update_many()
+            .col_expr(Column::ReadAt, Expr::value(chrono::Utc::now()))
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::ReadAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
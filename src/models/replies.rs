@@ -1,6 +1,7 @@
 use sea_orm::entity::prelude::*;
-use sea_orm::{DatabaseTransaction, JoinType, QueryFilter, QueryOrder, QuerySelect, TransactionTrait};
+use sea_orm::{FromQueryResult, JoinType, QueryFilter, QueryOrder, QuerySelect, TransactionTrait};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 pub use super::_entities::replies::{ActiveModel, Model, Entity, Column};
 use crate::models::users;
 pub type Replies = Entity;
@@ -8,7 +9,10 @@ pub type Replies = Entity;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplyResponse {
     pub id: i32,
+    /// The text as the poster wrote it
     pub body: String,
+    /// The text rendered from Markdown, safe to show as-is
+    pub body_html: String,
     /// [reply_id, reply_text, reply_status] - ID, text, and status of the reply being responded to
     pub reply_to: Option<(i32, String, i32)>,
     pub thread_id: i32,
@@ -17,27 +21,128 @@ pub struct ReplyResponse {
     pub poster_is_banned: bool,
     pub updated_at: DateTimeWithTimeZone,
     pub reply_status: i32,
-    pub image_url: Option<String>,
+    pub image: Option<ReplyImage>,
+    /// Whether the text was changed after it was posted
+    pub edited: bool,
+    pub edit_count: i32,
+}
+
+/// The image attached to a reply, with smaller copies to show in its place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyImage {
+    /// The full-size image
+    pub url: String,
+    /// Unknown for images attached before dimensions were recorded
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Missing while the variants are being made, and when the image is
+    /// already smaller than the variant would be
+    pub thumbnail: Option<ImageVariant>,
+    pub medium: Option<ImageVariant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A variant as stored in `replies.image_variants`, keyed by its name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredVariant {
+    pub key: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub type StoredVariants = BTreeMap<String, StoredVariant>;
+
+fn reply_image(
+    image_key: Option<String>,
+    width: Option<i32>,
+    height: Option<i32>,
+    variants: Option<Json>,
+) -> Option<ReplyImage> {
+    let storage = crate::image_storage::get();
+    let mut variants: StoredVariants = variants
+        .and_then(|variants| serde_json::from_value(variants).ok())
+        .unwrap_or_default();
+    let mut variant = |name: &str| {
+        variants.remove(name).map(|variant| ImageVariant {
+            url: storage.public_url(&variant.key),
+            width: variant.width,
+            height: variant.height,
+        })
+    };
+
+    image_key.map(|key| ReplyImage {
+        url: storage.public_url(&key),
+        width,
+        height,
+        thumbnail: variant("thumbnail"),
+        medium: variant("medium"),
+    })
+}
+
+#[derive(FromQueryResult)]
+struct ReplyRow {
+    id: i32,
+    body: String,
+    body_html: Option<String>,
+    reply_to: Option<i32>,
+    thread_id: i32,
+    poster: i32,
+    updated_at: DateTimeWithTimeZone,
+    reply_status: i32,
+    image_key: Option<String>,
+    image_width: Option<i32>,
+    image_height: Option<i32>,
+    image_variants: Option<Json>,
+    edit_count: i32,
+    poster_username: String,
+    poster_is_banned: bool,
+    parent_body: Option<String>,
+    parent_status: Option<i32>,
 }
 
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
-    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    async fn before_save<C>(self, db: &C, insert: bool) -> std::result::Result<Self, DbErr>
     where
         C: ConnectionTrait,
     {
-        if !insert && self.updated_at.is_unchanged() {
-            let mut this = self;
+        let mut this = self;
+        // Keeps the rendered body in step with the text
+        if let sea_orm::ActiveValue::Set(body) = &this.body {
+            this.body_html = sea_orm::ActiveValue::Set(Some(crate::models::mentions::render(db, body).await?));
+        }
+        if !insert && this.updated_at.is_unchanged() {
             this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
-            Ok(this)
-        } else {
-            Ok(self)
         }
+        Ok(this)
     }
 }
 
 // implement your read-oriented logic here
 impl Model {
+    /// Every stored file belonging to the reply's image: the original and
+    /// its variants
+    #[must_use]
+    pub fn image_keys(&self) -> Vec<String> {
+        let variants: StoredVariants = self
+            .image_variants
+            .clone()
+            .and_then(|variants| serde_json::from_value(variants).ok())
+            .unwrap_or_default();
+
+        self.image_key
+            .iter()
+            .cloned()
+            .chain(variants.into_values().map(|variant| variant.key))
+            .collect()
+    }
+
     /// # Parameters
     ///
     /// * db - current database connection
@@ -45,7 +150,7 @@ impl Model {
     /// * thread_id - id of the thread that this reply is inside
     /// * poster - id of the poster that created this reply
     /// * reply_to - Optional id of a reply that this reply is responding to
-    /// * pending_image_key - Optional S3 key of a pending image upload
+    /// * pending_image_key - Optional key of a pending image upload
     pub async fn create(
         db: &DatabaseConnection,
         body: String,
@@ -53,7 +158,13 @@ impl Model {
         poster: i32,
         reply_to: Option<i32>,
         pending_image_key: Option<String>,
-    ) -> Result<Self, DbErr> {
+    ) -> loco_rs::Result<Self> {
+        // A rejected image fails the post before anything is saved
+        let pending_image = match pending_image_key {
+            Some(pending_key) => Some(crate::image_storage::get().process_pending(&pending_key).await?),
+            None => None,
+        };
+
         let txn = db.begin().await?;
 
         let reply = ActiveModel {
@@ -65,19 +176,14 @@ impl Model {
         };
 
         let mut result = reply.insert(&txn).await?;
+        crate::models::mentions::Entity::sync(&txn, &result).await?;
+        crate::models::thread_subscriptions::Entity::subscribe(&txn, thread_id, poster).await?;
 
-        if let Some(pending_key) = pending_image_key {
-            match crate::s3::move_pending_to_reply(&pending_key, result.id).await {
-                Ok(final_key) => {
-                    use sea_orm::Set;
-                    let mut active: ActiveModel = result.clone().into();
-                    active.image_key = Set(Some(final_key.clone()));
-                    result = active.update(&txn).await?;
-                }
-                Err(e) => {
-                    tracing::error!("Failed to move image from pending: {e}");
-                }
-            }
+        if let Some(pending_image) = pending_image {
+            let image = crate::image_storage::get().attach(pending_image, result.id).await?;
+            let mut active: ActiveModel = result.clone().into();
+            active.set_image(Some(image));
+            result = active.update(&txn).await?;
         }
 
         // Update thread num_replies count
@@ -101,7 +207,18 @@ impl Model {
 }
 
 // implement your write-oriented logic here
-impl ActiveModel {}
+impl ActiveModel {
+    /// Points the reply at a newly attached image, or at none. Variants of
+    /// any previous image are forgotten, so delete them first.
+    pub fn set_image(&mut self, image: Option<crate::image_storage::AttachedImage>) {
+        use sea_orm::Set;
+        let to_i32 = |n: u32| i32::try_from(n).ok();
+        self.image_width = Set(image.as_ref().and_then(|image| to_i32(image.width)));
+        self.image_height = Set(image.as_ref().and_then(|image| to_i32(image.height)));
+        self.image_key = Set(image.map(|image| image.key));
+        self.image_variants = Set(None);
+    }
+}
 
 // implement your custom finders, selectors oriented logic here
 impl Entity {
@@ -128,45 +245,74 @@ impl Entity {
             .columns([
                 Column::Id,
                 Column::Body,
+                Column::BodyHtml,
                 Column::ReplyTo,
                 Column::ThreadId,
                 Column::Poster,
                 Column::UpdatedAt,
                 Column::ReplyStatus,
                 Column::ImageKey,
+                Column::ImageWidth,
+                Column::ImageHeight,
+                Column::ImageVariants,
+                Column::EditCount,
             ])
             .column_as(users::users::Column::Name, "poster_username")
             .column_as(users::users::Column::IsBanned, "poster_is_banned")
             .column_as(Expr::col(("parent_reply", crate::models::_entities::replies::Column::Body)), "parent_body")
             .column_as(Expr::col(("parent_reply", crate::models::_entities::replies::Column::ReplyStatus)), "parent_status")
-            .into_tuple::<(i32, String, Option<i32>, i32, i32, DateTimeWithTimeZone, i32, Option<String>, String, bool, Option<String>, Option<i32>)>()
+            .into_model::<ReplyRow>()
             .all(db)
             .await?;
 
-        let result = replies_with_data
+        // Replies saved before bodies were rendered get rendered now, once
+        let mut rows = Vec::with_capacity(replies_with_data.len());
+        for mut row in replies_with_data {
+            if row.body_html.is_none() {
+                let body_html = crate::models::mentions::render(db, &row.body).await?;
+                Self::cache_body_html(db, row.id, &body_html).await?;
+                row.body_html = Some(body_html);
+            }
+            rows.push(row);
+        }
+
+        let result = rows
             .into_iter()
-            .map(|(id, body, reply_to_id, thread_id, poster, updated_at, reply_status, image_key, poster_username, poster_is_banned, parent_body, parent_status)| {
-                let reply_to = match (reply_to_id, parent_body, parent_status) {
+            .map(|row| {
+                let reply_to = match (row.reply_to, row.parent_body, row.parent_status) {
                     (Some(id), Some(text), Some(status)) => Some((id, text, status)),
                     (None, None, None) => None,
                     _ => panic!("Impossible case: reply_to_id, parent_body, and parent_status should all be Some or all be None"),
                 };
 
                 ReplyResponse {
-                    id,
-                    body,
+                    id: row.id,
+                    body: row.body,
+                    body_html: row.body_html.unwrap_or_default(),
                     reply_to,
-                    thread_id,
-                    poster,
-                    poster_username,
-                    poster_is_banned,
-                    updated_at,
-                    reply_status,
-                    image_url: image_key.map(|k| crate::s3::public_image_url(&k)),
+                    thread_id: row.thread_id,
+                    poster: row.poster,
+                    poster_username: row.poster_username,
+                    poster_is_banned: row.poster_is_banned,
+                    updated_at: row.updated_at,
+                    reply_status: row.reply_status,
+                    image: reply_image(row.image_key, row.image_width, row.image_height, row.image_variants),
+                    edited: row.edit_count > 0,
+                    edit_count: row.edit_count,
                 }
             })
             .collect();
 
         Ok(result)
     }
+
+    /// Stores the rendered body of a reply without touching its updated_at
+    pub async fn cache_body_html(db: &DatabaseConnection, reply_id: i32, body_html: &str) -> Result<(), DbErr> {
+        Self::update_many()
+            .col_expr(Column::BodyHtml, Expr::value(body_html))
+            .filter(Column::Id.eq(reply_id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
 }
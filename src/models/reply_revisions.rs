@@ -0,0 +1,153 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+pub use super::_entities::reply_revisions::{ActiveModel, Model, Entity, Column};
+use crate::models::{replies, users};
+use loco_rs::prelude::*;
+pub type ReplyRevisions = Entity;
+
+/// One version of a reply's text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyVersion {
+    /// Starts at 1 for the text the reply was posted with
+    pub version: usize,
+    pub body: String,
+    pub body_html: String,
+    /// Who wrote this version: the poster for the first one, and whoever
+    /// made the edit for the rest
+    pub author_id: i32,
+    pub author_name: String,
+    pub created_at: DateTimeWithTimeZone,
+    /// What changed since the previous version. Empty for the first one.
+    pub diff: Vec<DiffChunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A run of words that were kept, added or removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffChunk {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// A word-level diff of `old` and `new`, with neighbouring words that
+/// changed the same way merged into one chunk
+#[must_use]
+pub fn diff(old: &str, new: &str) -> Vec<DiffChunk> {
+    let mut chunks: Vec<DiffChunk> = Vec::new();
+    for change in TextDiff::from_words(old, new).iter_all_changes() {
+        let op = match change.tag() {
+            ChangeTag::Equal => DiffOp::Equal,
+            ChangeTag::Insert => DiffOp::Insert,
+            ChangeTag::Delete => DiffOp::Delete,
+        };
+        match chunks.last_mut() {
+            Some(last) if last.op == op => last.text.push_str(change.value()),
+            _ => chunks.push(DiffChunk {
+                op,
+                text: change.value().to_string(),
+            }),
+        }
+    }
+    chunks
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut this = self;
+        // Keeps the rendered body in step with the text
+        if let sea_orm::ActiveValue::Set(body) = &this.body {
+            this.body_html = sea_orm::ActiveValue::Set(Some(crate::models::mentions::render(db, body).await?));
+        }
+        if !insert && this.updated_at.is_unchanged() {
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+        }
+        Ok(this)
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Keeps the text `reply` has before `editor_id` replaces it
+    pub async fn record<C: ConnectionTrait>(db: &C, reply: &replies::Model, editor_id: i32) -> ModelResult<Self> {
+        let revision = ActiveModel {
+            body: Set(reply.body.clone()),
+            reply_id: Set(reply.id),
+            editor_id: Set(editor_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(revision)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Every version of `reply`, oldest first. Each revision holds the text
+    /// an edit replaced, so the reply's current text is the last version.
+    pub async fn versions(db: &DatabaseConnection, reply: &replies::Model) -> ModelResult<Vec<ReplyVersion>> {
+        let revisions = Self::find()
+            .filter(Column::ReplyId.eq(reply.id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+
+        let mut user_ids: Vec<i32> = revisions.iter().map(|revision| revision.editor_id).collect();
+        user_ids.push(reply.poster);
+        let names: std::collections::HashMap<i32, String> = users::Entity::find()
+            .filter(users::users::Column::Id.is_in(user_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user.name))
+            .collect();
+        let name_of = |user_id: i32| names.get(&user_id).cloned().unwrap_or_default();
+
+        // Version n+1 was written by the edit that saved revision n
+        let authors = std::iter::once((reply.poster, reply.created_at))
+            .chain(revisions.iter().map(|revision| (revision.editor_id, revision.created_at)));
+        let bodies = revisions
+            .iter()
+            .map(|revision| (revision.body.as_str(), revision.body_html.clone()))
+            .chain(std::iter::once((reply.body.as_str(), reply.body_html.clone())));
+
+        let mut versions: Vec<ReplyVersion> = Vec::new();
+        for (index, ((author_id, created_at), (body, body_html))) in authors.zip(bodies).enumerate() {
+            let body_html = match body_html {
+                Some(body_html) => body_html,
+                None => crate::models::mentions::render(db, body).await?,
+            };
+            let changes = versions
+                .last()
+                .map(|previous| diff(&previous.body, body))
+                .unwrap_or_default();
+            versions.push(ReplyVersion {
+                version: index + 1,
+                body: body.to_string(),
+                body_html,
+                author_id,
+                author_name: name_of(author_id),
+                created_at,
+                diff: changes,
+            });
+        }
+
+        Ok(versions)
+    }
+}
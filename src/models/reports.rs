@@ -0,0 +1,263 @@
+use sea_orm::entity::prelude::*;
+use loco_rs::prelude::*;
+use sea_orm::{JoinType, PaginatorTrait, QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+pub use super::_entities::reports::{ActiveModel, Model, Entity, Column};
+use crate::models::{replies, threads, users};
+pub type Reports = Entity;
+
+/// The categories a user can file a report under
+pub const REPORT_CATEGORIES: [&str; 5] = ["spam", "harassment", "off_topic", "illegal", "other"];
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct CreateReportParams {
+    #[validate(custom(function = "validate_category"))]
+    pub category: String,
+    #[serde(default)]
+    #[validate(length(max = 2000, message = "Reason must be at most 2000 characters long."))]
+    pub reason: String,
+}
+
+fn validate_category(category: &str) -> Result<(), validator::ValidationError> {
+    if !REPORT_CATEGORIES.contains(&category) {
+        return Err(validator::ValidationError::new("Unknown report category."));
+    }
+    Ok(())
+}
+
+/// How a moderator closed the open reports on a reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportResolution {
+    Hidden,
+    Deleted,
+    Dismissed,
+}
+
+impl ReportResolution {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hidden => "hidden",
+            Self::Deleted => "deleted",
+            Self::Dismissed => "dismissed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportResponse {
+    pub id: i32,
+    pub category: String,
+    pub reason: String,
+    pub reporter_id: i32,
+    pub reporter_name: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+/// A reply in the moderation queue along with every open report against it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportedReply {
+    pub reply_id: i32,
+    pub body: String,
+    pub reply_status: i32,
+    pub thread_id: i32,
+    pub board_id: i32,
+    pub poster: i32,
+    pub poster_username: String,
+    pub reports: Vec<ReportResponse>,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Files a report against a reply.
+    ///
+    /// # Errors
+    ///
+    /// `EntityNotFound` if the reply doesn't exist, and `EntityAlreadyExists`
+    /// if the reporter already has an open report on it.
+    pub async fn create(
+        db: &DatabaseConnection,
+        reply_id: i32,
+        reporter_id: i32,
+        params: &CreateReportParams,
+    ) -> ModelResult<Self> {
+        replies::Entity::find_by_id(reply_id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        let existing = Entity::find()
+            .filter(Column::ReplyId.eq(reply_id))
+            .filter(Column::ReporterId.eq(reporter_id))
+            .filter(Column::ResolvedAt.is_null())
+            .one(db)
+            .await?;
+        if existing.is_some() {
+            return Err(ModelError::EntityAlreadyExists);
+        }
+
+        let report = ActiveModel {
+            reply_id: Set(reply_id),
+            reporter_id: Set(reporter_id),
+            category: Set(params.category.clone()),
+            reason: Set(params.reason.clone()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(report)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Returns a page of replies that have open reports, oldest report first,
+    /// along with the total number of reported replies.
+    pub async fn find_open_grouped(
+        db: &DatabaseConnection,
+        page_size: u64,
+        page_number: u64,
+    ) -> ModelResult<(Vec<ReportedReply>, u64)> {
+        let open = Self::find().filter(Column::ResolvedAt.is_null());
+
+        let total_count = open
+            .clone()
+            .select_only()
+            .column(Column::ReplyId)
+            .distinct()
+            .count(db)
+            .await?;
+
+        let reply_ids = open
+            .clone()
+            .select_only()
+            .column(Column::ReplyId)
+            .group_by(Column::ReplyId)
+            .order_by_asc(Expr::col(Column::Id).min())
+            .offset(page_size * page_number)
+            .limit(page_size)
+            .into_tuple::<i32>()
+            .all(db)
+            .await?;
+
+        if reply_ids.is_empty() {
+            return Ok((vec![], total_count));
+        }
+
+        let reported_replies = replies::Entity::find()
+            .filter(replies::Column::Id.is_in(reply_ids.clone()))
+            .join(JoinType::InnerJoin, crate::models::_entities::replies::Relation::Users.def())
+            .join(JoinType::InnerJoin, crate::models::_entities::replies::Relation::Threads.def())
+            .select_only()
+            .columns([
+                replies::Column::Id,
+                replies::Column::Body,
+                replies::Column::ReplyStatus,
+                replies::Column::ThreadId,
+            ])
+            .column(threads::Column::BoardId)
+            .column(replies::Column::Poster)
+            .column_as(users::users::Column::Name, "poster_username")
+            .into_tuple::<(i32, String, i32, i32, i32, i32, String)>()
+            .all(db)
+            .await?;
+
+        let reports = open
+            .filter(Column::ReplyId.is_in(reply_ids.clone()))
+            .join(JoinType::InnerJoin, crate::models::_entities::reports::Relation::Reporters.def())
+            .order_by_asc(Column::Id)
+            .select_only()
+            .columns([
+                Column::Id,
+                Column::ReplyId,
+                Column::Category,
+                Column::Reason,
+                Column::ReporterId,
+                Column::CreatedAt,
+            ])
+            .column_as(users::users::Column::Name, "reporter_name")
+            .into_tuple::<(i32, i32, String, String, i32, DateTimeWithTimeZone, String)>()
+            .all(db)
+            .await?;
+
+        let result = reply_ids
+            .into_iter()
+            .filter_map(|reply_id| {
+                let (id, body, reply_status, thread_id, board_id, poster, poster_username) = reported_replies
+                    .iter()
+                    .find(|(id, ..)| *id == reply_id)
+                    .cloned()?;
+
+                let reports = reports
+                    .iter()
+                    .filter(|(_, report_reply_id, ..)| *report_reply_id == reply_id)
+                    .map(|(id, _, category, reason, reporter_id, created_at, reporter_name)| ReportResponse {
+                        id: *id,
+                        category: category.clone(),
+                        reason: reason.clone(),
+                        reporter_id: *reporter_id,
+                        reporter_name: reporter_name.clone(),
+                        created_at: *created_at,
+                    })
+                    .collect();
+
+                Some(ReportedReply {
+                    reply_id: id,
+                    body,
+                    reply_status,
+                    thread_id,
+                    board_id,
+                    poster,
+                    poster_username,
+                    reports,
+                })
+            })
+            .collect();
+
+        Ok((result, total_count))
+    }
+
+    /// Closes every open report on a reply. Returns how many were closed.
+    pub async fn resolve_for_reply<C>(
+        db: &C,
+        reply_id: i32,
+        moderator_id: i32,
+        resolution: ReportResolution,
+    ) -> ModelResult<u64>
+    where
+        C: ConnectionTrait,
+    {
+        let result = Self::update_many()
+            .col_expr(Column::Resolution, Expr::value(resolution.as_str()))
+            .col_expr(Column::ResolvedAt, Expr::value(chrono::Utc::now()))
+            .col_expr(Column::ResolvedBy, Expr::value(moderator_id))
+            .filter(Column::ReplyId.eq(reply_id))
+            .filter(Column::ResolvedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,472 @@
+//! Full-text matching for search. SQLite uses the FTS5 tables kept in sync
+//! by triggers, and Postgres uses the generated `search_vector` columns.
+//! Both stem words, so "broke" finds "breaking" and the like.
+
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::{Alias, Expr, JoinType, Order, SimpleExpr};
+use sea_orm::{DbBackend, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait, RelationTrait, Select};
+use serde::{Deserialize, Serialize};
+use loco_rs::model::ModelResult;
+
+use crate::models::_entities::{boards, replies, threads, users};
+
+/// Marks the start and end of a matched term in snippets straight from the
+/// database. They're private-use characters, so they survive HTML escaping
+/// and can't be confused with markup.
+const MATCH_START: char = '\u{E000}';
+const MATCH_END: char = '\u{E001}';
+
+/// Narrows a search beyond the text being matched. Dates are whole days in
+/// UTC, and both ends of the range are included. When searching threads,
+/// the author is the thread's poster and the dates are when it was started;
+/// `thread_id` and `has_image` only apply to replies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFilter {
+    pub board_id: Option<i32>,
+    pub thread_id: Option<i32>,
+    pub author_id: Option<i32>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub has_image: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchReplyResult {
+    pub reply_id: i32,
+    /// The part of the reply that matched, as HTML with the matched terms
+    /// wrapped in `<mark>`. The text is shown as the poster wrote it.
+    pub snippet: String,
+    /// The same part rendered from Markdown. Since it's cut out of the
+    /// reply, it may start or end partway through formatting.
+    pub snippet_html: String,
+    pub thread_id: i32,
+    pub thread_title: String,
+    pub board_id: i32,
+    pub board_title: String,
+    pub poster_id: i32,
+    pub poster_name: String,
+    pub created_at: DateTimeWithTimeZone,
+    /// How well the reply matched, higher being better
+    pub rank: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchThreadResult {
+    pub thread_id: i32,
+    /// The thread's title as HTML, with the matched terms wrapped in `<mark>`
+    pub title: String,
+    pub board_id: i32,
+    pub board_title: String,
+    pub poster_id: i32,
+    pub poster_name: String,
+    pub num_replies: i32,
+    pub last_active: chrono::NaiveDateTime,
+    pub created_at: DateTimeWithTimeZone,
+    /// How well the title matched, higher being better
+    pub rank: f64,
+}
+
+/// A result from searching threads and replies together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchHit {
+    Thread(SearchThreadResult),
+    Reply(SearchReplyResult),
+}
+
+impl SearchHit {
+    #[must_use]
+    pub fn rank(&self) -> f64 {
+        match self {
+            Self::Thread(thread) => thread.rank,
+            Self::Reply(reply) => reply.rank,
+        }
+    }
+}
+
+/// Turns what a user typed into an FTS5 query. Quoted text stays a phrase
+/// and every other word has to appear. Everything is quoted, so FTS5
+/// operators and punctuation are treated as plain text. Returns None if
+/// nothing searchable is left.
+#[must_use]
+pub fn fts5_query(input: &str) -> Option<String> {
+    let words = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    let mut terms = vec![];
+    // Every other piece between double quotes is a phrase
+    for (i, piece) in input.split('"').enumerate() {
+        if i % 2 == 1 {
+            let phrase = words(piece);
+            if !phrase.is_empty() {
+                terms.push(format!("\"{}\"", phrase.join(" ")));
+            }
+        } else {
+            terms.extend(words(piece).into_iter().map(|word| format!("\"{word}\"")));
+        }
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// A table searched through its full-text index. On SQLite that's the
+/// `<table>_fts` FTS5 table, and on Postgres the table's `search_vector`.
+#[derive(Clone, Copy)]
+enum Indexed {
+    Replies,
+    Threads,
+}
+
+impl Indexed {
+    fn table(self) -> &'static str {
+        match self {
+            Self::Replies => "replies",
+            Self::Threads => "threads",
+        }
+    }
+
+    fn fts_table(self) -> &'static str {
+        match self {
+            Self::Replies => "replies_fts",
+            Self::Threads => "threads_fts",
+        }
+    }
+
+    /// The indexed text column
+    fn text_column(self) -> &'static str {
+        match self {
+            Self::Replies => "body",
+            Self::Threads => "title",
+        }
+    }
+}
+
+/// Narrows a query over `indexed` to rows matching `query`, best match
+/// first. Returns None when `query` can't match anything.
+fn match_text<E: EntityTrait>(
+    mut select: Select<E>,
+    indexed: Indexed,
+    backend: DbBackend,
+    query: &str,
+) -> Option<Select<E>> {
+    let condition = match backend {
+        DbBackend::Sqlite => {
+            let fts_query = fts5_query(query)?;
+            QueryTrait::query(&mut select).join(
+                JoinType::InnerJoin,
+                Alias::new(indexed.fts_table()),
+                Expr::col((Alias::new(indexed.fts_table()), Alias::new("rowid")))
+                    .equals((Alias::new(indexed.table()), Alias::new("id"))),
+            );
+            Expr::cust_with_values(format!("{} MATCH ?", indexed.fts_table()), [fts_query])
+        }
+        _ => Expr::cust_with_values(
+            format!("{}.search_vector @@ websearch_to_tsquery('english', ?)", indexed.table()),
+            [query],
+        ),
+    };
+
+    Some(select.filter(condition).order_by(rank(indexed, backend, query), Order::Desc))
+}
+
+/// How well a row matched, higher being better. Only valid in a query
+/// narrowed by `match_text`.
+fn rank(indexed: Indexed, backend: DbBackend, query: &str) -> SimpleExpr {
+    match backend {
+        // bm25 scores are negative, with the best match lowest
+        DbBackend::Sqlite => Expr::cust(format!("-bm25({})", indexed.fts_table())),
+        _ => Expr::cust_with_values(
+            format!(
+                "CAST(ts_rank({}.search_vector, websearch_to_tsquery('english', ?)) AS DOUBLE PRECISION)",
+                indexed.table()
+            ),
+            [query],
+        ),
+    }
+}
+
+/// The matching text of a row, with the matches between `MATCH_START` and
+/// `MATCH_END`. Reply bodies are cut down to the part around the matches,
+/// while thread titles are returned whole. Only valid in a query narrowed
+/// by `match_text`.
+fn snippet(indexed: Indexed, backend: DbBackend, query: &str) -> SimpleExpr {
+    let (start, end) = (u32::from(MATCH_START), u32::from(MATCH_END));
+    match (backend, indexed) {
+        (DbBackend::Sqlite, Indexed::Replies) => Expr::cust(format!(
+            "snippet({}, 0, char({start}), char({end}), '…', 32)",
+            indexed.fts_table()
+        )),
+        (DbBackend::Sqlite, Indexed::Threads) => Expr::cust(format!(
+            "highlight({}, 0, char({start}), char({end}))",
+            indexed.fts_table()
+        )),
+        (_, Indexed::Replies) => Expr::cust_with_values(
+            format!(
+                "ts_headline('english', {}.{}, websearch_to_tsquery('english', ?), \
+                 'StartSel=' || chr({start}) || ', StopSel=' || chr({end}) || ', MinWords=15, MaxWords=35')",
+                indexed.table(),
+                indexed.text_column()
+            ),
+            [query],
+        ),
+        (_, Indexed::Threads) => Expr::cust_with_values(
+            format!(
+                "ts_headline('english', {}.{}, websearch_to_tsquery('english', ?), \
+                 'StartSel=' || chr({start}) || ', StopSel=' || chr({end}) || ', HighlightAll=true')",
+                indexed.table(),
+                indexed.text_column()
+            ),
+            [query],
+        ),
+    }
+}
+
+/// Escapes a snippet for HTML and turns its match markers into `<mark>` tags
+#[must_use]
+pub fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+/// Full-text search over live replies in `board_ids`, most relevant first.
+/// Returns a page of results along with the total number of matches.
+pub async fn search_replies(
+    db: &DatabaseConnection,
+    query: &str,
+    board_ids: Vec<i32>,
+    filter: &SearchFilter,
+    limit: u64,
+    offset: u64,
+) -> ModelResult<(Vec<SearchReplyResult>, u64)> {
+    let mut select = replies::Entity::find()
+        .filter(replies::Column::ReplyStatus.eq(1)) // Only search live replies
+        .join(JoinType::InnerJoin, replies::Relation::Threads.def())
+        .filter(threads::Column::IsDeleted.eq(false))
+        .filter(threads::Column::BoardId.is_in(board_ids))
+        .join(JoinType::InnerJoin, replies::Relation::Users.def())
+        .join_as(JoinType::InnerJoin, threads::Relation::Boards.def(), "board");
+
+    if let Some(board_id) = filter.board_id {
+        select = select.filter(threads::Column::BoardId.eq(board_id));
+    }
+    if let Some(thread_id) = filter.thread_id {
+        select = select.filter(replies::Column::ThreadId.eq(thread_id));
+    }
+    if let Some(author_id) = filter.author_id {
+        select = select.filter(replies::Column::Poster.eq(author_id));
+    }
+    if let Some(from) = filter.from {
+        select = select.filter(replies::Column::CreatedAt.gte(from.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+    if let Some(to) = filter.to.and_then(|to| to.succ_opt()) {
+        select = select.filter(replies::Column::CreatedAt.lt(to.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+    match filter.has_image {
+        Some(true) => select = select.filter(replies::Column::ImageKey.is_not_null()),
+        Some(false) => select = select.filter(replies::Column::ImageKey.is_null()),
+        None => {}
+    }
+
+    let backend = db.get_database_backend();
+    let Some(select) = match_text(select, Indexed::Replies, backend, query) else {
+        return Ok((vec![], 0));
+    };
+
+    let total_found = select.clone().count(db).await?;
+
+    let rows = select
+        .select_only()
+        .column(replies::Column::Id)
+        .column_as(snippet(Indexed::Replies, backend, query), "snippet")
+        .column(replies::Column::ThreadId)
+        .column_as(threads::Column::Title, "thread_title")
+        .column_as(threads::Column::BoardId, "board_id")
+        .column_as(Expr::col((Alias::new("board"), boards::Column::Title)), "board_title")
+        .column_as(replies::Column::Poster, "poster_id")
+        .column_as(users::Column::Name, "poster_name")
+        .column(replies::Column::CreatedAt)
+        .column_as(rank(Indexed::Replies, backend, query), "rank")
+        .limit(limit)
+        .offset(offset)
+        .into_tuple::<(i32, String, i32, String, i32, String, i32, String, DateTimeWithTimeZone, f64)>()
+        .all(db)
+        .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|(reply_id, snippet, thread_id, thread_title, board_id, board_title, poster_id, poster_name, created_at, rank)| {
+            SearchReplyResult {
+                reply_id,
+                snippet_html: crate::markdown::render_snippet(&snippet, MATCH_START, MATCH_END),
+                snippet: highlight(&snippet),
+                thread_id,
+                thread_title,
+                board_id,
+                board_title,
+                poster_id,
+                poster_name,
+                created_at,
+                rank,
+            }
+        })
+        .collect();
+
+    Ok((results, total_found))
+}
+
+/// Full-text search over the titles of threads in `board_ids`, most
+/// relevant first. Returns a page of results along with the total number
+/// of matches.
+pub async fn search_threads(
+    db: &DatabaseConnection,
+    query: &str,
+    board_ids: Vec<i32>,
+    filter: &SearchFilter,
+    limit: u64,
+    offset: u64,
+) -> ModelResult<(Vec<SearchThreadResult>, u64)> {
+    let mut select = threads::Entity::find()
+        .filter(threads::Column::IsDeleted.eq(false))
+        .filter(threads::Column::BoardId.is_in(board_ids))
+        .join(JoinType::InnerJoin, threads::Relation::Users.def())
+        .join(JoinType::InnerJoin, threads::Relation::Boards.def());
+
+    if let Some(board_id) = filter.board_id {
+        select = select.filter(threads::Column::BoardId.eq(board_id));
+    }
+    if let Some(author_id) = filter.author_id {
+        select = select.filter(threads::Column::Poster.eq(author_id));
+    }
+    if let Some(from) = filter.from {
+        select = select.filter(threads::Column::CreatedAt.gte(from.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+    if let Some(to) = filter.to.and_then(|to| to.succ_opt()) {
+        select = select.filter(threads::Column::CreatedAt.lt(to.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+
+    let backend = db.get_database_backend();
+    let Some(select) = match_text(select, Indexed::Threads, backend, query) else {
+        return Ok((vec![], 0));
+    };
+
+    let total_found = select.clone().count(db).await?;
+
+    let rows = select
+        .select_only()
+        .column(threads::Column::Id)
+        .column_as(snippet(Indexed::Threads, backend, query), "title")
+        .column(threads::Column::BoardId)
+        .column_as(boards::Column::Title, "board_title")
+        .column_as(threads::Column::Poster, "poster_id")
+        .column_as(users::Column::Name, "poster_name")
+        .column(threads::Column::NumReplies)
+        .column(threads::Column::LastActive)
+        .column(threads::Column::CreatedAt)
+        .column_as(rank(Indexed::Threads, backend, query), "rank")
+        .limit(limit)
+        .offset(offset)
+        .into_tuple::<(i32, String, i32, String, i32, String, i32, chrono::NaiveDateTime, DateTimeWithTimeZone, f64)>()
+        .all(db)
+        .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|(thread_id, title, board_id, board_title, poster_id, poster_name, num_replies, last_active, created_at, rank)| {
+            SearchThreadResult {
+                thread_id,
+                title: highlight(&title),
+                board_id,
+                board_title,
+                poster_id,
+                poster_name,
+                num_replies,
+                last_active,
+                created_at,
+                rank,
+            }
+        })
+        .collect();
+
+    Ok((results, total_found))
+}
+
+/// Searches thread titles and replies together, merging the two into one
+/// ranking. Returns a page of results along with the total number of
+/// matches of either kind.
+///
+/// Title and reply ranks aren't on the same scale: bm25 and `ts_rank` both
+/// depend on the length of the text, and titles are far shorter than reply
+/// bodies. Before the two are merged, each kind's ranks are divided by the
+/// rank of its best hit, so the returned ranks run from 0 to 1 within each
+/// kind. The best hits always lead their kind's window, so the scale is
+/// the same on every page.
+pub async fn search_all(
+    db: &DatabaseConnection,
+    query: &str,
+    board_ids: Vec<i32>,
+    filter: &SearchFilter,
+    limit: u64,
+    offset: u64,
+) -> ModelResult<(Vec<SearchHit>, u64)> {
+    // Any hit on the requested page is within the first offset + limit of its own kind
+    let window = offset.saturating_add(limit);
+    // Only replies can be narrowed to a thread or to having an image
+    let (mut threads, threads_found) = if filter.thread_id.is_some() || filter.has_image.is_some() {
+        (vec![], 0)
+    } else {
+        search_threads(db, query, board_ids.clone(), filter, window, 0).await?
+    };
+    let (mut replies, replies_found) = search_replies(db, query, board_ids, filter, window, 0).await?;
+    normalize_ranks(threads.iter_mut().map(|thread| &mut thread.rank));
+    normalize_ranks(replies.iter_mut().map(|reply| &mut reply.rank));
+
+    let mut hits: Vec<SearchHit> = threads
+        .into_iter()
+        .map(SearchHit::Thread)
+        .chain(replies.into_iter().map(SearchHit::Reply))
+        .collect();
+    hits.sort_by(|a, b| b.rank().total_cmp(&a.rank()));
+
+    let page = hits
+        .into_iter()
+        .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+        .take(usize::try_from(limit).unwrap_or(usize::MAX))
+        .collect();
+
+    Ok((page, threads_found + replies_found))
+}
+
+/// Scales `ranks`, which come best first, so that the best is 1
+fn normalize_ranks<'a>(mut ranks: impl Iterator<Item = &'a mut f64>) {
+    let Some(best) = ranks.next() else {
+        return;
+    };
+    let scale = *best;
+    if scale <= 0.0 {
+        return;
+    }
+    *best = 1.0;
+    for rank in ranks {
+        *rank /= scale;
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use sea_orm::entity::prelude::*;
+use loco_rs::prelude::*;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{QueryOrder, QuerySelect, Set};
+use serde::{Deserialize, Serialize};
+pub use super::_entities::thread_subscriptions::{ActiveModel, Model, Entity, Column};
+use crate::models::{replies, threads, users};
+pub type ThreadSubscriptions = Entity;
+
+/// Most replies from one thread shown in a digest. The rest are counted.
+pub const MAX_DIGEST_REPLIES: usize = 10;
+
+/// How often a user is emailed about new replies in the threads they follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    /// An email for every reply
+    Immediate,
+    Daily,
+    Weekly,
+    Off,
+}
+
+impl DigestFrequency {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Off => "off",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "immediate" => Some(Self::Immediate),
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    /// How long to wait between digests, for the frequencies that get them
+    #[must_use]
+    pub fn period(self) -> Option<chrono::Duration> {
+        match self {
+            Self::Daily => Some(chrono::Duration::days(1)),
+            Self::Weekly => Some(chrono::Duration::weeks(1)),
+            Self::Immediate | Self::Off => None,
+        }
+    }
+}
+
+/// New replies in one of the threads a user follows
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestThread {
+    pub board_id: i32,
+    pub thread_id: i32,
+    pub title: String,
+    /// Oldest first, leaving out the subscriber's own replies and any that
+    /// were hidden or deleted
+    pub replies: Vec<DigestReply>,
+    /// New replies beyond the first [`MAX_DIGEST_REPLIES`]
+    pub more_replies: usize,
+    /// The newest reply in the thread, which the digest covers up to
+    pub latest_reply_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestReply {
+    pub reply_id: i32,
+    pub poster_name: String,
+    pub body: String,
+    pub body_html: String,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+// implement your read-oriented logic here
+impl Model {
+    /// Records that the subscriber has been emailed about the replies up to
+    /// `reply_id`
+    pub async fn mark_sent<C: ConnectionTrait>(self, db: &C, reply_id: i32) -> ModelResult<Self> {
+        if reply_id <= self.last_reply_id {
+            return Ok(self);
+        }
+        let mut subscription = self.into_active_model();
+        subscription.last_reply_id = Set(reply_id);
+        Ok(subscription.update(db).await?)
+    }
+}
+
+// implement your write-oriented logic here
+impl ActiveModel {}
+
+// implement your custom finders, selectors oriented logic here
+impl Entity {
+    /// Subscribes `user_id` to `thread_id`, leaving an existing subscription
+    /// as it is. Only replies posted from now on are emailed about.
+    pub async fn subscribe<C: ConnectionTrait>(db: &C, thread_id: i32, user_id: i32) -> Result<(), DbErr> {
+        let latest_reply_id: Option<i32> = replies::Entity::find()
+            .filter(replies::Column::ThreadId.eq(thread_id))
+            .select_only()
+            .column_as(replies::Column::Id.max(), "latest_reply_id")
+            .into_tuple()
+            .one(db)
+            .await?
+            .flatten();
+
+        Self::insert(ActiveModel {
+            thread_id: Set(thread_id),
+            user_id: Set(user_id),
+            last_reply_id: Set(latest_reply_id.unwrap_or_default()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([Column::ThreadId, Column::UserId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Moves every subscription to `from_thread_id` over to `to_thread_id`,
+    /// for when one thread is merged into another. Users following both
+    /// keep one subscription, with the later of the two `last_reply_id`s.
+    pub async fn move_to_thread<C: ConnectionTrait>(db: &C, from_thread_id: i32, to_thread_id: i32) -> Result<(), DbErr> {
+        let moving = Self::find()
+            .filter(Column::ThreadId.eq(from_thread_id))
+            .all(db)
+            .await?;
+
+        for subscription in moving {
+            let existing = Self::find()
+                .filter(Column::ThreadId.eq(to_thread_id))
+                .filter(Column::UserId.eq(subscription.user_id))
+                .one(db)
+                .await?;
+
+            match existing {
+                Some(existing) => {
+                    if subscription.last_reply_id > existing.last_reply_id {
+                        let mut existing = existing.into_active_model();
+                        existing.last_reply_id = Set(subscription.last_reply_id);
+                        existing.update(db).await?;
+                    }
+                    subscription.delete(db).await?;
+                }
+                None => {
+                    let mut subscription = subscription.into_active_model();
+                    subscription.thread_id = Set(to_thread_id);
+                    subscription.update(db).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Unsubscribes `user_id` from `thread_id`. Returns whether they were
+    /// subscribed.
+    pub async fn unsubscribe(db: &DatabaseConnection, thread_id: i32, user_id: i32) -> ModelResult<bool> {
+        let result = Self::delete_many()
+            .filter(Column::ThreadId.eq(thread_id))
+            .filter(Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn find_subscription(db: &DatabaseConnection, thread_id: i32, user_id: i32) -> ModelResult<Option<Model>> {
+        let subscription = Self::find()
+            .filter(Column::ThreadId.eq(thread_id))
+            .filter(Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+        Ok(subscription)
+    }
+
+    /// Everyone subscribed to `thread_id`
+    pub async fn subscriptions_for_thread(db: &DatabaseConnection, thread_id: i32) -> ModelResult<Vec<Model>> {
+        let subscriptions = Self::find()
+            .filter(Column::ThreadId.eq(thread_id))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+        Ok(subscriptions)
+    }
+
+    /// The replies `user_id` hasn't been emailed about in each thread they
+    /// follow, with the subscription they came through. Threads whose only
+    /// new replies are the user's own, or were taken down, come back with no
+    /// replies so the subscription can still be moved past them. Deleted
+    /// threads are left out.
+    pub async fn pending_digest(db: &DatabaseConnection, user_id: i32) -> ModelResult<Vec<(Model, DigestThread)>> {
+        let subscriptions = Self::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_asc(Column::ThreadId)
+            .all(db)
+            .await?;
+
+        let mut pending = Vec::new();
+        for subscription in subscriptions {
+            let Some(thread) = threads::Entity::find_by_id(subscription.thread_id)
+                .one(db)
+                .await?
+                .filter(|thread| !thread.is_deleted)
+            else {
+                continue;
+            };
+
+            let new_replies = replies::Entity::find()
+                .filter(replies::Column::ThreadId.eq(thread.id))
+                .filter(replies::Column::Id.gt(subscription.last_reply_id))
+                .order_by_asc(replies::Column::Id)
+                .all(db)
+                .await?;
+            let Some(latest_reply_id) = new_replies.last().map(|reply| reply.id) else {
+                continue;
+            };
+
+            // Only live replies by other people are worth an email
+            let shown: Vec<replies::Model> = new_replies
+                .into_iter()
+                .filter(|reply| reply.reply_status == 1 && reply.poster != user_id)
+                .collect();
+            let more_replies = shown.len().saturating_sub(MAX_DIGEST_REPLIES);
+
+            let poster_ids: Vec<i32> = shown.iter().map(|reply| reply.poster).collect();
+            let poster_names: HashMap<i32, String> = users::Entity::find()
+                .filter(users::users::Column::Id.is_in(poster_ids))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|user| (user.id, user.name))
+                .collect();
+
+            let mut digest_replies = Vec::new();
+            for reply in shown.into_iter().take(MAX_DIGEST_REPLIES) {
+                let body_html = match reply.body_html {
+                    Some(body_html) => body_html,
+                    None => crate::models::mentions::render(db, &reply.body).await?,
+                };
+                digest_replies.push(DigestReply {
+                    reply_id: reply.id,
+                    poster_name: poster_names.get(&reply.poster).cloned().unwrap_or_default(),
+                    body: reply.body,
+                    body_html,
+                    created_at: reply.created_at,
+                });
+            }
+
+            pending.push((subscription, DigestThread {
+                board_id: thread.board_id,
+                thread_id: thread.id,
+                title: thread.title,
+                replies: digest_replies,
+                more_replies,
+                latest_reply_id,
+            }));
+        }
+
+        Ok(pending)
+    }
+}
@@ -1,10 +1,10 @@
 use sea_orm::entity::prelude::*;
-use sea_orm::{QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait, JoinType};
+use sea_orm::{DatabaseTransaction, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait, JoinType};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 pub use super::_entities::threads::{ActiveModel, Model, Entity, Column};
 use crate::models::replies::Entity as ReplyEntity;
-use crate::models::{boards, replies, users};
+use crate::models::{boards, mentions, replies, thread_subscriptions, users};
 pub type Threads = Entity;
 use loco_rs::prelude::*;
 
@@ -19,6 +19,17 @@ pub struct ThreadWithPosterName {
     pub poster_is_banned: bool,
     pub last_active: chrono::NaiveDateTime,
     pub num_replies: i32,
+    pub is_locked: bool,
+    pub is_pinned: bool,
+    pub is_archived: bool,
+}
+
+/// Moderator changes to a thread's state. Unset fields are left as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThreadStateParams {
+    pub is_locked: Option<bool>,
+    pub is_pinned: Option<bool>,
+    pub is_archived: Option<bool>,
 }
 
 #[async_trait::async_trait]
@@ -47,6 +58,12 @@ impl Model {
         initial_reply_text: String,
         pending_image_key: Option<String>,
     ) -> Result<Model> {
+        // A rejected image fails the post before anything is saved
+        let pending_image = match pending_image_key {
+            Some(pending_key) => Some(crate::image_storage::get().process_pending(&pending_key).await?),
+            None => None,
+        };
+
         let txn = db.begin().await?;
 
         let now = Utc::now();
@@ -70,18 +87,14 @@ impl Model {
             ..Default::default()
         };
         let reply = reply.insert(&txn).await?;
+        mentions::Entity::sync(&txn, &reply).await?;
+        thread_subscriptions::Entity::subscribe(&txn, thread.id, poster_id).await?;
 
-        if let Some(pending_key) = pending_image_key {
-            match crate::s3::move_pending_to_reply(&pending_key, reply.id).await {
-                Ok(final_key) => {
-                    let mut active: replies::ActiveModel = reply.into();
-                    active.image_key = Set(Some(final_key));
-                    active.update(&txn).await?;
-                }
-                Err(e) => {
-                    tracing::error!("Failed to move image from pending: {e}");
-                }
-            }
+        if let Some(pending_image) = pending_image {
+            let image = crate::image_storage::get().attach(pending_image, reply.id).await?;
+            let mut active: replies::ActiveModel = reply.into();
+            active.set_image(Some(image));
+            active.update(&txn).await?;
         }
 
         let board = boards::Entity::find_by_id(board_id)
@@ -98,11 +111,28 @@ impl Model {
         Ok(thread)
     }
 
-    pub async fn delete(
-        db: &DatabaseConnection,
-        board_id: i32,
+    /// Soft-deletes a thread. Its replies are kept so that the thread can be
+    /// restored, but it no longer appears in listings or search.
+    pub async fn delete<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
         thread_id: i32
-    ) -> Result<()> {
+    ) -> Result<Model> {
+        Self::set_deleted(db, thread_id, true).await
+    }
+
+    /// Reverses a soft delete
+    pub async fn restore<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        thread_id: i32
+    ) -> Result<Model> {
+        Self::set_deleted(db, thread_id, false).await
+    }
+
+    async fn set_deleted<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        thread_id: i32,
+        is_deleted: bool,
+    ) -> Result<Model> {
         let txn = db.begin().await?;
 
         let thread = crate::models::threads::Entity::find_by_id(thread_id)
@@ -110,44 +140,300 @@ impl Model {
             .await?;
 
         let thread = thread.ok_or(loco_rs::Error::NotFound)?;
+        if thread.is_deleted == is_deleted {
+            return Err(loco_rs::Error::NotFound);
+        }
+
+        adjust_num_threads(&txn, thread.board_id, if is_deleted { -1 } else { 1 }).await?;
+
+        let mut thread: ActiveModel = thread.into();
+        thread.is_deleted = Set(is_deleted);
+        let thread = thread.update(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(thread)
+    }
+
+    /// Permanently deletes a thread and its replies. Returns the keys of the
+    /// replies' images, which are left in the store for the caller to delete
+    /// with [`delete_purged_images`] once the transaction this runs in has
+    /// been committed.
+    pub async fn purge<C: ConnectionTrait>(
+        db: &C,
+        thread_id: i32
+    ) -> Result<(Model, Vec<String>)> {
+        let thread = crate::models::threads::Entity::find_by_id(thread_id)
+            .one(db)
+            .await?;
+
+        let thread = thread.ok_or(loco_rs::Error::NotFound)?;
+
+        let image_keys: Vec<String> = ReplyEntity::find()
+            .filter(crate::models::replies::Column::ThreadId.eq(thread_id))
+            .filter(crate::models::replies::Column::ImageKey.is_not_null())
+            .all(db)
+            .await?
+            .iter()
+            .flat_map(replies::Model::image_keys)
+            .collect();
 
         ReplyEntity::delete_many()
             .filter(crate::models::replies::Column::ThreadId.eq(thread_id))
+            .exec(db)
+            .await?;
+
+        thread.clone().delete(db).await?;
+
+        // Soft-deleted threads were already taken out of the count
+        if !thread.is_deleted {
+            adjust_num_threads(db, thread.board_id, -1).await?;
+        }
+
+        Ok((thread, image_keys))
+    }
+
+    /// Moves a thread to another board
+    pub async fn move_to_board<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        thread_id: i32,
+        board_id: i32,
+    ) -> Result<Model> {
+        let txn = db.begin().await?;
+
+        let thread = crate::models::threads::Entity::find_by_id(thread_id)
+            .one(&txn)
+            .await?
+            .filter(|thread| !thread.is_deleted)
+            .ok_or(loco_rs::Error::NotFound)?;
+
+        boards::Entity::find_by_id(board_id)
+            .one(&txn)
+            .await?
+            .ok_or(loco_rs::Error::NotFound)?;
+
+        if thread.board_id == board_id {
+            return Ok(thread);
+        }
+
+        adjust_num_threads(&txn, thread.board_id, -1).await?;
+        adjust_num_threads(&txn, board_id, 1).await?;
+
+        let mut thread: ActiveModel = thread.into();
+        thread.board_id = Set(board_id);
+        let thread = thread.update(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(thread)
+    }
+
+    /// Moves every reply and subscription in `source_id` into `target_id`
+    /// and removes the source thread. Replies are listed in id order, so the merged replies
+    /// end up interleaved with the target's by when they were posted.
+    pub async fn merge_into<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        source_id: i32,
+        target_id: i32,
+    ) -> Result<Model> {
+        if source_id == target_id {
+            return Err(loco_rs::Error::BadRequest("A thread can't be merged into itself".to_string()));
+        }
+
+        let txn = db.begin().await?;
+
+        let source = crate::models::threads::Entity::find_by_id(source_id)
+            .one(&txn)
+            .await?
+            .filter(|thread| !thread.is_deleted)
+            .ok_or(loco_rs::Error::NotFound)?;
+        let target = crate::models::threads::Entity::find_by_id(target_id)
+            .one(&txn)
+            .await?
+            .filter(|thread| !thread.is_deleted)
+            .ok_or(loco_rs::Error::NotFound)?;
+
+        ReplyEntity::update_many()
+            .col_expr(replies::Column::ThreadId, Expr::value(target_id))
+            .filter(replies::Column::ThreadId.eq(source_id))
             .exec(&txn)
             .await?;
 
-        thread.delete(&txn).await?;
+        let last_active = source.last_active.max(target.last_active);
+        let num_replies = count_replies(&txn, target_id).await?;
 
-        let board = boards::Entity::find_by_id(board_id)
+        let mut target: ActiveModel = target.into();
+        target.num_replies = Set(num_replies);
+        target.last_active = Set(last_active);
+        let target = target.update(&txn).await?;
+
+        thread_subscriptions::Entity::move_to_thread(&txn, source_id, target_id).await?;
+
+        adjust_num_threads(&txn, source.board_id, -1).await?;
+        source.delete(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(target)
+    }
+
+    /// Moves the replies of a thread from `first_reply_id` to `last_reply_id`
+    /// inclusive into a new thread in the same board. The first moved reply's
+    /// poster becomes the new thread's poster.
+    pub async fn split<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        thread_id: i32,
+        first_reply_id: i32,
+        last_reply_id: i32,
+        title: String,
+    ) -> Result<Model> {
+        let txn = db.begin().await?;
+
+        let thread = crate::models::threads::Entity::find_by_id(thread_id)
             .one(&txn)
             .await?
-            .ok_or(DbErr::RecordNotFound("Board not found".to_string()))?;
+            .filter(|thread| !thread.is_deleted)
+            .ok_or(loco_rs::Error::NotFound)?;
 
-        let mut board: boards::ActiveModel = board.into();
-        board.num_threads = Set(board.num_threads.unwrap() - 1);
-        board.update(&txn).await?;
+        let moved = ReplyEntity::find()
+            .filter(replies::Column::ThreadId.eq(thread_id))
+            .filter(replies::Column::Id.between(first_reply_id, last_reply_id))
+            .order_by_asc(replies::Column::Id)
+            .all(&txn)
+            .await?;
+
+        let (Some(first), Some(last)) = (moved.first(), moved.last()) else {
+            return Err(loco_rs::Error::BadRequest("No replies in that range belong to this thread".to_string()));
+        };
+        let num_moved = i32::try_from(moved.len()).unwrap_or(i32::MAX);
+        if num_moved >= count_replies(&txn, thread_id).await? {
+            return Err(loco_rs::Error::BadRequest("At least one reply must be left in the original thread".to_string()));
+        }
+
+        let new_thread = ActiveModel {
+            title: Set(title),
+            description: Set(String::new()),
+            board_id: Set(thread.board_id),
+            poster: Set(first.poster),
+            last_active: Set(last.created_at.naive_utc()),
+            num_replies: Set(num_moved),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        ReplyEntity::update_many()
+            .col_expr(replies::Column::ThreadId, Expr::value(new_thread.id))
+            .filter(replies::Column::ThreadId.eq(thread_id))
+            .filter(replies::Column::Id.between(first_reply_id, last_reply_id))
+            .exec(&txn)
+            .await?;
+
+        let num_replies = count_replies(&txn, thread_id).await?;
+        // The newest replies may have moved, taking the thread's latest activity with them
+        let newest = ReplyEntity::find()
+            .filter(replies::Column::ThreadId.eq(thread_id))
+            .order_by_desc(replies::Column::CreatedAt)
+            .one(&txn)
+            .await?;
+        let mut thread: ActiveModel = thread.into();
+        thread.num_replies = Set(num_replies);
+        if let Some(newest) = newest {
+            thread.last_active = Set(newest.created_at.naive_utc());
+        }
+        thread.update(&txn).await?;
+
+        adjust_num_threads(&txn, new_thread.board_id, 1).await?;
 
         txn.commit().await?;
 
-        Ok(())
+        Ok(new_thread)
+    }
+}
+
+/// Counts the replies in a thread, for recomputing `num_replies` after
+/// replies have been moved between threads
+async fn count_replies(txn: &DatabaseTransaction, thread_id: i32) -> Result<i32> {
+    use sea_orm::PaginatorTrait;
+
+    let count = ReplyEntity::find()
+        .filter(replies::Column::ThreadId.eq(thread_id))
+        .count(txn)
+        .await?;
+    Ok(i32::try_from(count).unwrap_or(i32::MAX))
+}
+
+/// Removes the images of purged replies from the store. Images
+/// live outside the database, so they're only removed once the rows
+/// referencing them are gone.
+pub async fn delete_purged_images(image_keys: Vec<String>) {
+    for key in image_keys {
+        if let Err(e) = crate::image_storage::get().delete(&key).await {
+            tracing::error!("Failed to delete purged image {key}: {e}");
+        }
     }
 }
 
+/// Adds `change` to a board's thread count
+async fn adjust_num_threads<C: ConnectionTrait>(
+    txn: &C,
+    board_id: i32,
+    change: i32,
+) -> Result<()> {
+    let board = boards::Entity::find_by_id(board_id)
+        .one(txn)
+        .await?
+        .ok_or(DbErr::RecordNotFound("Board not found".to_string()))?;
+
+    let mut board: boards::ActiveModel = board.into();
+    board.num_threads = Set(board.num_threads.unwrap() + change);
+    board.update(txn).await?;
+
+    Ok(())
+}
+
 // implement your write-oriented logic here
 impl ActiveModel {
-
+    /// Locks, pins or archives the thread, or reverses any of those
+    pub async fn set_state<C: ConnectionTrait>(
+        mut self,
+        db: &C,
+        params: &ThreadStateParams,
+    ) -> ModelResult<Model> {
+        if let Some(is_locked) = params.is_locked {
+            self.is_locked = Set(is_locked);
+        }
+        if let Some(is_pinned) = params.is_pinned {
+            self.is_pinned = Set(is_pinned);
+        }
+        if let Some(is_archived) = params.is_archived {
+            self.is_archived = Set(is_archived);
+        }
+        Ok(self.update(db).await?)
+    }
 }
 
 impl Entity {
+    /// Returns a page of a board's threads, pinned threads first and then the
+    /// most recently active. Archived threads are left out unless
+    /// `include_archived` is set.
     pub async fn find_paginated(
         db: &DatabaseConnection,
         board_id: i32,
+        include_archived: bool,
         page_size: u64,
         page_number: u64,
     ) -> Result<Vec<ThreadWithPosterName>, DbErr> {
-        let threads_with_users = Self::find()
+        let mut query = Self::find()
             .filter(Column::BoardId.eq(board_id))
+            .filter(Column::IsDeleted.eq(false));
+        if !include_archived {
+            query = query.filter(Column::IsArchived.eq(false));
+        }
+
+        let threads_with_users = query
             .join(JoinType::InnerJoin, crate::models::_entities::threads::Relation::Users.def())
+            .order_by_desc(Column::IsPinned)
             .order_by_desc(Column::LastActive)
             .offset(page_size * page_number)
             .limit(page_size)
@@ -160,16 +446,19 @@ impl Entity {
                 Column::Poster,
                 Column::LastActive,
                 Column::NumReplies,
+                Column::IsLocked,
+                Column::IsPinned,
+                Column::IsArchived,
             ])
             .column_as(users::users::Column::Name, "poster_username")
             .column_as(users::users::Column::IsBanned, "poster_is_banned")
-            .into_tuple::<(i32, String, String, i32, i32, chrono::NaiveDateTime, i32, String, bool)>()
+            .into_tuple::<(i32, String, String, i32, i32, chrono::NaiveDateTime, i32, bool, bool, bool, String, bool)>()
             .all(db)
             .await?;
 
         let result = threads_with_users
             .into_iter()
-            .map(|(id, title, description, board_id, poster, last_active, num_replies, poster_username, poster_is_banned)| {
+            .map(|(id, title, description, board_id, poster, last_active, num_replies, is_locked, is_pinned, is_archived, poster_username, poster_is_banned)| {
                 ThreadWithPosterName {
                     id,
                     title,
@@ -180,10 +469,25 @@ impl Entity {
                     poster_is_banned,
                     last_active,
                     num_replies,
+                    is_locked,
+                    is_pinned,
+                    is_archived,
                 }
             })
             .collect();
 
         Ok(result)
     }
+
+    /// Counts a board's archived threads that haven't been deleted
+    pub async fn count_archived(db: &DatabaseConnection, board_id: i32) -> Result<u64, DbErr> {
+        use sea_orm::PaginatorTrait;
+
+        Self::find()
+            .filter(Column::BoardId.eq(board_id))
+            .filter(Column::IsDeleted.eq(false))
+            .filter(Column::IsArchived.eq(true))
+            .count(db)
+            .await
+    }
 }
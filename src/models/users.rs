@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use chrono::{offset::Local, Duration};
+use chrono::{offset::Local, DateTime, Duration, Utc};
 use loco_rs::{auth::jwt, hash, prelude::*};
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
@@ -7,9 +7,14 @@ use uuid::Uuid;
 use validator::Validate;
 
 pub use super::_entities::users::{self, ActiveModel, Entity, Model};
+use crate::models::bans;
+use crate::models::thread_subscriptions::DigestFrequency;
+use crate::permissions::Role;
 
 pub const MAGIC_LINK_LENGTH: i8 = 32;
 pub const MAGIC_LINK_EXPIRATION_MIN: i8 = 5;
+pub const RESET_TOKEN_EXPIRATION_MIN: i64 = 60;
+pub const EMAIL_VERIFICATION_EXPIRATION_HOURS: i64 = 48;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LoginParams {
@@ -27,6 +32,13 @@ pub struct RegisterParams {
     pub name: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ResetParams {
+    pub token: String,
+    #[validate(custom(function = "validate_password"))]
+    pub password: String,
+}
+
 fn validate_password(password: &str) -> Result<(), validator::ValidationError> {
     if password.len() < 8 {
         return Err(validator::ValidationError::new("Password must be at least 8 characters long."));
@@ -75,6 +87,11 @@ impl ActiveModelBehavior for super::_entities::users::ActiveModel {
             let mut this = self;
             this.pid = ActiveValue::Set(Uuid::new_v4());
             this.api_key = ActiveValue::Set(format!("lo-{}", Uuid::new_v4()));
+            // New accounts get daily digests. Accounts from before digests
+            // existed were left off, since they never asked for them.
+            if this.digest_frequency.is_not_set() {
+                this.digest_frequency = ActiveValue::Set(DigestFrequency::Daily.as_str().to_string());
+            }
             Ok(this)
         } else {
             Ok(self)
@@ -148,6 +165,8 @@ impl Model {
             password: ActiveValue::set(String::new()),
             name: ActiveValue::set(name.to_string()),
             google_id: ActiveValue::set(google_id.to_string()),
+            // Google only hands out verified addresses
+            email_verified_at: ActiveValue::set(Some(Local::now().into())),
             ..Default::default()
         }
         .insert(db)
@@ -156,11 +175,12 @@ impl Model {
         Ok(user)
     }
 
-    /// finds a user by the provided verification token
+    /// finds a user by the provided verification token and checks that the
+    /// token has not expired
     ///
     /// # Errors
     ///
-    /// When could not find user by the given token or DB query error
+    /// When could not find user by the given token, DB query error or token expired
     pub async fn find_by_verification_token(
         db: &DatabaseConnection,
         token: &str,
@@ -173,7 +193,19 @@ impl Model {
             )
             .one(db)
             .await?;
-        user.ok_or_else(|| ModelError::EntityNotFound)
+
+        let user = user.ok_or_else(|| ModelError::EntityNotFound)?;
+        let expired = user.email_verification_sent_at.is_none_or(|sent_at| {
+            sent_at + Duration::hours(EMAIL_VERIFICATION_EXPIRATION_HOURS) < Local::now()
+        });
+        if expired {
+            tracing::debug!(
+                user_pid = user.pid.to_string(),
+                "email verification token expired for the user."
+            );
+            return Err(ModelError::msg("verification token expired"));
+        }
+        Ok(user)
     }
 
     /// finds a user by the magic token and verify and token expiration
@@ -212,11 +244,12 @@ impl Model {
         }
     }
 
-    /// finds a user by the provided reset token
+    /// finds a user by the provided reset token and checks that the token has
+    /// not expired
     ///
     /// # Errors
     ///
-    /// When could not find user by the given token or DB query error
+    /// When could not find user by the given token, DB query error or token expired
     pub async fn find_by_reset_token(db: &DatabaseConnection, token: &str) -> ModelResult<Self> {
         let user = users::Entity::find()
             .filter(
@@ -226,7 +259,19 @@ impl Model {
             )
             .one(db)
             .await?;
-        user.ok_or_else(|| ModelError::EntityNotFound)
+
+        let user = user.ok_or_else(|| ModelError::EntityNotFound)?;
+        let expired = user.reset_sent_at.is_none_or(|sent_at| {
+            sent_at + Duration::minutes(RESET_TOKEN_EXPIRATION_MIN) < Local::now()
+        });
+        if expired {
+            tracing::debug!(
+                user_pid = user.pid.to_string(),
+                "reset token expired for the user."
+            );
+            return Err(ModelError::msg("reset token expired"));
+        }
+        Ok(user)
     }
 
     /// finds a user by the provided pid
@@ -264,6 +309,51 @@ impl Model {
         user.ok_or_else(|| ModelError::EntityNotFound)
     }
 
+    /// Whether the user has verified their email address
+    #[must_use]
+    pub fn is_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    /// The user's global role. Unknown values are treated as `Member`.
+    #[must_use]
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role).unwrap_or(Role::Member)
+    }
+
+    /// How often the user is emailed about threads they follow. Unknown
+    /// values are treated as `Off`.
+    #[must_use]
+    pub fn digest_frequency(&self) -> DigestFrequency {
+        DigestFrequency::parse(&self.digest_frequency).unwrap_or(DigestFrequency::Off)
+    }
+
+    /// Lifts the user's ban once all of their active bans have expired, and
+    /// returns the up to date user. Users flagged as banned without a ban
+    /// record stay banned.
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error
+    pub async fn lift_ban_if_expired(self, db: &DatabaseConnection) -> ModelResult<Self> {
+        if !self.is_banned {
+            return Ok(self);
+        }
+
+        let active_bans = bans::Entity::find()
+            .filter(bans::Column::UserId.eq(self.id))
+            .filter(bans::Column::LiftedAt.is_null())
+            .all(db)
+            .await?;
+
+        if !active_bans.is_empty() && active_bans.iter().all(bans::Model::is_expired) {
+            tracing::info!(user_pid = self.pid.to_string(), "lifting expired ban");
+            return bans::Model::lift(db, self.id, None).await;
+        }
+
+        Ok(self)
+    }
+
     /// Verifies whether the provided plain password matches the hashed password
     ///
     /// # Errors
@@ -308,6 +398,9 @@ impl Model {
             email: ActiveValue::set(params.email.to_string()),
             password: ActiveValue::set(password_hash),
             name: ActiveValue::set(params.name.to_string()),
+            // google_id is unique and non-null, so password-only accounts get a
+            // placeholder that can never match a real Google account id
+            google_id: ActiveValue::set(format!("password-{}", Uuid::new_v4())),
             ..Default::default()
         }
         .insert(&txn)
@@ -349,6 +442,36 @@ impl ActiveModel {
         self.update(db).await.map_err(ModelError::from)
     }
 
+    /// Changes the user's global role
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn set_role<C: ConnectionTrait>(mut self, db: &C, role: Role) -> ModelResult<Model> {
+        self.role = ActiveValue::set(role.as_str().to_string());
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Changes how often the user is emailed about threads they follow
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn set_digest_frequency(mut self, db: &DatabaseConnection, frequency: DigestFrequency) -> ModelResult<Model> {
+        self.digest_frequency = ActiveValue::set(frequency.as_str().to_string());
+        self.update(db).await.map_err(ModelError::from)
+    }
+
+    /// Records when the user was last sent a digest
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn set_digest_sent(mut self, db: &DatabaseConnection, sent_at: DateTime<Utc>) -> ModelResult<Model> {
+        self.last_digest_at = ActiveValue::set(Some(sent_at.into()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
     /// Sets the information for a reset password request,
     /// generates a unique reset password token, and updates it in the
     /// database.
@@ -402,6 +525,17 @@ impl ActiveModel {
         self.update(db).await.map_err(ModelError::from)
     }
 
+    /// Replaces the user's API key with a freshly generated one and updates
+    /// it in the database. The previous key stops working immediately.
+    ///
+    /// # Errors
+    ///
+    /// when has DB query error
+    pub async fn rotate_api_key(mut self, db: &DatabaseConnection) -> ModelResult<Model> {
+        self.api_key = ActiveValue::set(format!("lo-{}", Uuid::new_v4()));
+        self.update(db).await.map_err(ModelError::from)
+    }
+
     /// Creates a magic link token for passwordless authentication.
     ///
     /// Generates a random token with a specified length and sets an expiration time
@@ -0,0 +1,190 @@
+//! Roles and the permissions they grant.
+//!
+//! Every user has a global role stored on their account: admin, global
+//! moderator or member. On top of that, a member can be made a moderator of
+//! individual boards, which gives them the board-scoped permissions there and
+//! nowhere else. Handlers ask this module whether a user may do something
+//! instead of checking roles themselves.
+
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{board_moderators, users};
+
+/// A user's role, either globally or within a particular board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    GlobalModerator,
+    /// Only ever derived from `board_moderators`, never stored on a user
+    BoardModerator,
+    Member,
+}
+
+impl Role {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Admin => "admin",
+            Self::GlobalModerator => "global_moderator",
+            Self::BoardModerator => "board_moderator",
+            Self::Member => "member",
+        }
+    }
+
+    /// Parses a role that can be stored on a user account
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "admin" => Some(Self::Admin),
+            "global_moderator" => Some(Self::GlobalModerator),
+            "member" => Some(Self::Member),
+            _ => None,
+        }
+    }
+
+    /// Whether the role moderates every board
+    #[must_use]
+    pub fn is_global_moderator(self) -> bool {
+        matches!(self, Self::Admin | Self::GlobalModerator)
+    }
+
+    /// Whether the role is above `other`. Admins are above global
+    /// moderators, who are above board moderators, who are above members.
+    #[must_use]
+    pub fn outranks(self, other: Self) -> bool {
+        self.rank() > other.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Self::Admin => 3,
+            Self::GlobalModerator => 2,
+            Self::BoardModerator => 1,
+            Self::Member => 0,
+        }
+    }
+
+    #[must_use]
+    pub fn grants(self, permission: Permission) -> bool {
+        match self {
+            Self::Admin => true,
+            Self::GlobalModerator => permission != Permission::ManageRoles,
+            Self::BoardModerator => permission.is_board_scoped(),
+            Self::Member => false,
+        }
+    }
+}
+
+/// Something a user needs to be allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    HideReply,
+    /// Delete other people's replies. Anyone can delete their own.
+    DeleteReply,
+    /// Delete, restore and purge threads
+    DeleteThread,
+    /// Lock, pin, archive, move, merge and split threads
+    ManageThread,
+    /// Work through the report queue and read the moderation log
+    ReviewReports,
+    BanUser,
+    /// Create, edit and delete boards and categories, and choose who
+    /// moderates each board
+    ManageBoard,
+    /// Manage who belongs to a members-only board
+    ManageMembers,
+    /// Change users' global roles
+    ManageRoles,
+}
+
+impl Permission {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::HideReply => "hide_reply",
+            Self::DeleteReply => "delete_reply",
+            Self::DeleteThread => "delete_thread",
+            Self::ManageThread => "manage_thread",
+            Self::ReviewReports => "review_reports",
+            Self::BanUser => "ban_user",
+            Self::ManageBoard => "manage_board",
+            Self::ManageMembers => "manage_members",
+            Self::ManageRoles => "manage_roles",
+        }
+    }
+
+    /// Whether board moderators get this permission within their boards
+    #[must_use]
+    pub fn is_board_scoped(self) -> bool {
+        matches!(
+            self,
+            Self::HideReply
+                | Self::DeleteReply
+                | Self::DeleteThread
+                | Self::ManageThread
+                | Self::ManageMembers
+        )
+    }
+}
+
+/// The role `user` has in `board_id`, or their global role when no board is given
+pub async fn effective_role<C: ConnectionTrait>(
+    db: &C,
+    user: &users::Model,
+    board_id: Option<i32>,
+) -> ModelResult<Role> {
+    let role = user.role();
+    if role != Role::Member {
+        return Ok(role);
+    }
+
+    match board_id {
+        Some(board_id) if board_moderators::Entity::is_moderator(db, board_id, user.id).await? => {
+            Ok(Role::BoardModerator)
+        }
+        _ => Ok(Role::Member),
+    }
+}
+
+/// Whether `user` has `permission`, in `board_id` if the action concerns a board
+pub async fn has_permission<C: ConnectionTrait>(
+    db: &C,
+    user: &users::Model,
+    permission: Permission,
+    board_id: Option<i32>,
+) -> ModelResult<bool> {
+    Ok(effective_role(db, user, board_id).await?.grants(permission))
+}
+
+/// Rejects the request unless `user` has `permission`, in `board_id` if the
+/// action concerns a board
+pub async fn ensure<C: ConnectionTrait>(
+    db: &C,
+    user: &users::Model,
+    permission: Permission,
+    board_id: Option<i32>,
+) -> Result<()> {
+    if !has_permission(db, user, permission, board_id).await? {
+        return Err(Error::Unauthorized(format!(
+            "You don't have the {} permission",
+            permission.as_str()
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `user` moderates `board_id`, either globally or as one of its board moderators
+pub async fn moderates_board<C: ConnectionTrait>(db: &C, user: &users::Model, board_id: i32) -> ModelResult<bool> {
+    Ok(effective_role(db, user, Some(board_id)).await? != Role::Member)
+}
+
+/// Whether `user` moderates anything, either globally or in at least one board
+pub async fn is_moderator(db: &DatabaseConnection, user: &users::Model) -> ModelResult<bool> {
+    if user.role().is_global_moderator() {
+        return Ok(true);
+    }
+    Ok(!board_moderators::Entity::board_ids_for_user(db, user.id).await?.is_empty())
+}
@@ -0,0 +1,32 @@
+use loco_rs::prelude::*;
+use crate::image_storage::{self, cleanup};
+
+/// Deletes abandoned uploads and the images of deleted replies. Run with
+/// `dry_run:true` to only log what would be deleted.
+pub struct CleanupImages;
+#[async_trait]
+impl Task for CleanupImages {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "cleanup_images".to_string(),
+            detail: "Delete abandoned uploads and images of deleted replies (dry_run:true to only report them)".to_string(),
+        }
+    }
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let dry_run = vars.cli_arg("dry_run").is_ok_and(|value| value == "true");
+        let storage = image_storage::get();
+
+        let orphans = cleanup::find_orphans(&ctx.db, storage, chrono::Utc::now()).await?;
+        if dry_run {
+            for orphan in &orphans {
+                tracing::info!("Would delete {} ({})", orphan.key, orphan.reason);
+            }
+            tracing::info!("Dry run: {} images would be deleted", orphans.len());
+            return Ok(());
+        }
+
+        let deleted = cleanup::remove_orphans(&ctx.db, storage, &orphans).await?;
+        tracing::info!("Deleted {} of {} orphaned images", deleted, orphans.len());
+        Ok(())
+    }
+}
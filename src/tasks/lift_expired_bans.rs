@@ -0,0 +1,18 @@
+use loco_rs::prelude::*;
+use crate::models::bans::Entity as BanEntity;
+
+pub struct LiftExpiredBans;
+#[async_trait]
+impl Task for LiftExpiredBans {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "lift_expired_bans".to_string(),
+            detail: "Unban users whose bans have expired".to_string(),
+        }
+    }
+    async fn run(&self, ctx: &AppContext, _vars: &task::Vars) -> Result<()> {
+        let lifted = BanEntity::lift_expired(&ctx.db).await?;
+        tracing::info!("Lifted expired bans for {} users", lifted);
+        Ok(())
+    }
+}
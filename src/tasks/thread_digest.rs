@@ -0,0 +1,91 @@
+use chrono::{Duration, Utc};
+use loco_rs::prelude::*;
+use sea_orm::QueryOrder;
+use crate::controllers::boards::ensure_can_view;
+use crate::mailers::digests::DigestMailer;
+use crate::models::boards::Entity as BoardEntity;
+use crate::models::thread_subscriptions::{DigestFrequency, Entity as ThreadSubscriptionEntity};
+use crate::models::users::{self, Entity as UserEntity};
+
+/// How early a digest can go out, so one that ran a little late yesterday
+/// doesn't push today's to tomorrow
+const SCHEDULE_SLACK: Duration = Duration::hours(1);
+
+/// Emails users on a daily or weekly digest the new replies in the threads
+/// they follow, once their last digest is a day or a week old
+pub struct ThreadDigest;
+#[async_trait]
+impl Task for ThreadDigest {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "thread_digest".to_string(),
+            detail: "Email subscribers the new replies in the threads they follow".to_string(),
+        }
+    }
+    async fn run(&self, ctx: &AppContext, _vars: &task::Vars) -> Result<()> {
+        tracing::info!("Starting thread digest task");
+
+        let now = Utc::now();
+        let users = UserEntity::find()
+            .filter(users::users::Column::DigestFrequency.is_in([
+                DigestFrequency::Daily.as_str(),
+                DigestFrequency::Weekly.as_str(),
+            ]))
+            .order_by_asc(users::users::Column::Id)
+            .all(&ctx.db)
+            .await?;
+
+        let mut sent = 0;
+        for user in users {
+            let Some(period) = user.digest_frequency().period() else {
+                continue;
+            };
+            let due = user
+                .last_digest_at
+                .is_none_or(|last| now.signed_duration_since(last) >= period - SCHEDULE_SLACK);
+            if !due {
+                continue;
+            }
+
+            match send_digest(ctx, &user).await {
+                Ok(true) => sent += 1,
+                Ok(false) => {}
+                Err(err) => {
+                    tracing::error!(user_id = user.id, "Failed to send thread digest: {err}");
+                    continue;
+                }
+            }
+            user.into_active_model().set_digest_sent(&ctx.db, now).await?;
+        }
+
+        tracing::info!("Sent {} thread digests", sent);
+        Ok(())
+    }
+}
+
+/// Emails `user` what's new in the threads they follow, if anything is, and
+/// moves their subscriptions past it. Returns whether an email went out.
+async fn send_digest(ctx: &AppContext, user: &users::Model) -> Result<bool> {
+    let mut threads = Vec::new();
+    let mut covered = Vec::new();
+    for (subscription, thread) in ThreadSubscriptionEntity::pending_digest(&ctx.db, user.id).await? {
+        // The thread may have moved to a board the user can't see
+        let visible = match BoardEntity::find_by_id(thread.board_id).one(&ctx.db).await? {
+            Some(board) => ensure_can_view(&ctx.db, &board, Some(user)).await.is_ok(),
+            None => false,
+        };
+        covered.push((subscription, thread.latest_reply_id));
+        if visible && !thread.replies.is_empty() {
+            threads.push(thread);
+        }
+    }
+
+    if !threads.is_empty() {
+        DigestMailer::send_digest(ctx, user, &threads).await?;
+    }
+    for (subscription, latest_reply_id) in covered {
+        subscription.mark_sent(&ctx.db, latest_reply_id).await?;
+    }
+
+    Ok(!threads.is_empty())
+}
@@ -0,0 +1,80 @@
+use loco_rs::prelude::*;
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+
+use crate::image_storage::{self, processing};
+use crate::models::replies::{self, StoredVariant, StoredVariants};
+
+/// Makes the thumbnail and medium copies of a reply's image, so thread pages
+/// don't have to load every image at full size
+pub struct ImageVariantsWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ImageVariantsWorkerArgs {
+    pub reply_id: i32,
+}
+
+impl ImageVariantsWorker {
+    /// Queues the variants of a reply's newly attached image. Pages fall back
+    /// to the original without them, so a failure is logged rather than
+    /// failing the post.
+    pub async fn enqueue(ctx: &AppContext, reply_id: i32) {
+        if let Err(e) = Self::perform_later(ctx, ImageVariantsWorkerArgs { reply_id }).await {
+            tracing::error!("Failed to queue image variants for reply {reply_id}: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl BackgroundWorker<ImageVariantsWorkerArgs> for ImageVariantsWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: ImageVariantsWorkerArgs) -> Result<()> {
+        let Some(reply) = replies::Entity::find_by_id(args.reply_id).one(&self.ctx.db).await? else {
+            return Ok(());
+        };
+        let Some(image_key) = reply.image_key else {
+            return Ok(());
+        };
+
+        let storage = image_storage::get();
+        let original = storage.read(&image_key).await.map_err(Error::Message)?;
+        let ext = image_key.rsplit('.').next().unwrap_or_default().to_string();
+        let variants = tokio::task::spawn_blocking(move || processing::make_variants(&original, &ext))
+            .await
+            .map_err(|e| Error::Message(format!("Making image variants panicked: {e}")))??;
+
+        let mut stored = StoredVariants::new();
+        for variant in variants {
+            let key = image_storage::variant_key(reply.id, variant.name, variant.ext);
+            let content_type = image_storage::content_type_for(variant.ext).unwrap_or("application/octet-stream");
+            storage
+                .put(&key, content_type, variant.bytes)
+                .await
+                .map_err(Error::Message)?;
+            stored.insert(
+                variant.name.to_string(),
+                StoredVariant {
+                    key,
+                    width: variant.width,
+                    height: variant.height,
+                },
+            );
+        }
+
+        // Goes around the model so updated_at is left alone, and only lands
+        // if the image wasn't replaced while its variants were being made
+        replies::Entity::update_many()
+            .col_expr(replies::Column::ImageVariants, Expr::value(serde_json::to_value(stored)?))
+            .filter(replies::Column::Id.eq(reply.id))
+            .filter(replies::Column::ImageKey.eq(image_key))
+            .exec(&self.ctx.db)
+            .await?;
+
+        Ok(())
+    }
+}
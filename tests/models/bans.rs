@@ -0,0 +1,68 @@
+use chrono::{Duration, Utc};
+use kboard::{
+    app::App,
+    models::bans::{Entity as BansEntity, Model as BansModel},
+    models::users,
+};
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, Set};
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_create() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    let ban = BansModel::create(db, 2, 1, "Spamming seagull photos".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(ban.user_id, 2);
+    assert_eq!(ban.moderator_id, 1);
+    assert!(ban.lifted_at.is_none());
+
+    let user2 = users::Model::find_by_pid(db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+    assert!(user2.is_banned);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_lift() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    BansModel::create(db, 2, 1, "Spamming".to_string(), None).await.unwrap();
+    let user2 = BansModel::lift(db, 2, Some(1)).await.unwrap();
+    assert!(!user2.is_banned);
+
+    assert!(BansEntity::find_active_for_user(db, 2).await.unwrap().is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_lift_expired() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    let ban = BansModel::create(db, 2, 1, "Cooling off".to_string(), Some((Utc::now() + Duration::hours(1)).into()))
+        .await
+        .unwrap();
+    BansModel::create(db, 1, 1, "Permanent".to_string(), None).await.unwrap();
+
+    // Nothing has expired yet
+    assert_eq!(BansEntity::lift_expired(db).await.unwrap(), 0);
+
+    let mut ban: kboard::models::bans::ActiveModel = ban.into();
+    ban.expires_at = Set(Some((Utc::now() - Duration::minutes(1)).into()));
+    ban.update(db).await.unwrap();
+
+    assert_eq!(BansEntity::lift_expired(db).await.unwrap(), 1);
+
+    let user1 = users::Model::find_by_pid(db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+    let user2 = users::Model::find_by_pid(db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+    assert!(user1.is_banned);
+    assert!(!user2.is_banned);
+}
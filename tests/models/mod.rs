@@ -1,3 +1,4 @@
+mod bans;
 mod boards;
 mod threads;
 mod replies;
@@ -1,10 +1,12 @@
-use insta::{assert_debug_snapshot, assert_yaml_snapshot};
+use insta::assert_yaml_snapshot;
 use kboard::{
     app::App,
+    models::{boards, replies},
     models::threads::Model as ThreadsModel,
     models::threads::Entity as ThreadsEntity
 };
 use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
 use serial_test::serial;
 
 macro_rules! configure_insta {
@@ -53,6 +55,7 @@ async fn test_find_paginated_all() {
     let threads = ThreadsEntity::find_paginated(
         &boot.app_context.db,
         1,
+        false,
         2000,
         0
     ).await.unwrap();
@@ -79,6 +82,7 @@ async fn test_find_paginated_some() {
     let threads = ThreadsEntity::find_paginated(
         &boot.app_context.db,
         1,
+        false,
         2,
         0
     ).await.unwrap();
@@ -107,6 +111,7 @@ async fn test_find_paginated_second_page() {
     let threads = ThreadsEntity::find_paginated(
         &boot.app_context.db,
         1,
+        false,
         2,
         1
     ).await.unwrap();
@@ -122,4 +127,83 @@ async fn test_find_paginated_second_page() {
             ".last_active" => "2025-09-04T22:17:46.190572"
         }
     );
-}
\ No newline at end of file
+}
+async fn num_threads(db: &sea_orm::DatabaseConnection, board_id: i32) -> i32 {
+    boards::Entity::find_by_id(board_id).one(db).await.unwrap().unwrap().num_threads
+}
+
+async fn reply_ids(db: &sea_orm::DatabaseConnection, thread_id: i32) -> Vec<i32> {
+    replies::Entity::find()
+        .filter(replies::Column::ThreadId.eq(thread_id))
+        .order_by_asc(replies::Column::Id)
+        .all(db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|reply| reply.id)
+        .collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_move_to_board() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    let board = boards::ActiveModel {
+        title: Set("Broken Toe Stories".to_string()),
+        description: Set("Tell about the time you broke your toe.".to_string()),
+        num_threads: Set(0),
+        requires_verified_email: Set(false),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    let thread = ThreadsModel::move_to_board(db, 1, board.id).await.unwrap();
+
+    assert_eq!(thread.board_id, board.id);
+    assert_eq!(num_threads(db, 1).await, 3);
+    assert_eq!(num_threads(db, board.id).await, 1);
+    assert_eq!(reply_ids(db, 1).await, vec![1, 5]);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_merge_into() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    let thread = ThreadsModel::merge_into(db, 2, 1).await.unwrap();
+
+    assert_eq!(thread.num_replies, 3);
+    assert_eq!(reply_ids(db, 1).await, vec![1, 2, 5]);
+    assert!(ThreadsEntity::find_by_id(2).one(db).await.unwrap().is_none());
+    assert_eq!(num_threads(db, 1).await, 3);
+
+    assert!(ThreadsModel::merge_into(db, 1, 1).await.is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_split() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    let thread = ThreadsModel::split(db, 1, 5, 5, "Sympathy".to_string()).await.unwrap();
+
+    assert_eq!(thread.title, "Sympathy");
+    assert_eq!(thread.poster, 2);
+    assert_eq!(thread.num_replies, 1);
+    assert_eq!(reply_ids(db, thread.id).await, vec![5]);
+    assert_eq!(reply_ids(db, 1).await, vec![1]);
+    assert_eq!(ThreadsEntity::find_by_id(1).one(db).await.unwrap().unwrap().num_replies, 1);
+    assert_eq!(num_threads(db, 1).await, 5);
+
+    // A thread can't be emptied by a split
+    assert!(ThreadsModel::split(db, 1, 1, 1, "Nothing left".to_string()).await.is_err());
+}
@@ -0,0 +1,414 @@
+use chrono::{Duration, Local};
+use kboard::{
+    app::App,
+    models::{bans, board_moderators, thread_subscriptions::DigestFrequency, users},
+    permissions::Role,
+};
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, IntoActiveModel, Set};
+use serial_test::serial;
+
+fn register_payload() -> serde_json::Value {
+    serde_json::json!({
+        "email": "pika@example.com",
+        "password": "squeak-squeak1",
+        "name": "pika",
+    })
+}
+
+#[tokio::test]
+#[serial]
+async fn can_register() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let response = request.post("/api/auth/register").json(&register_payload()).await;
+        assert_eq!(response.status_code(), 200);
+
+        let cookies = response.cookies();
+        assert!(cookies.get("jwt").is_some());
+        assert_eq!(cookies.get("username").unwrap().value(), "pika");
+        assert_eq!(cookies.get("is_moderator").unwrap().value(), "false");
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        assert_eq!(user.name, "pika");
+        assert!(user.verify_password("squeak-squeak1"));
+        assert_eq!(user.digest_frequency(), DigestFrequency::Daily);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn register_rejects_weak_password() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let payload = serde_json::json!({
+            "email": "pika@example.com",
+            "password": "squeaksqueak",
+            "name": "pika",
+        });
+
+        let response = request.post("/api/auth/register").json(&payload).await;
+        assert_eq!(response.status_code(), 400);
+        assert!(users::Model::find_by_email(&ctx.db, "pika@example.com").await.is_err());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn register_rejects_duplicate_email() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let payload = serde_json::json!({
+            "email": "user1@example.com",
+            "password": "squeak-squeak1",
+            "name": "pika",
+        });
+
+        let response = request.post("/api/auth/register").json(&payload).await;
+        assert_eq!(response.status_code(), 400);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_login() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        request.post("/api/auth/register").json(&register_payload()).await;
+
+        let response = request
+            .post("/api/auth/login")
+            .json(&serde_json::json!({
+                "email": "pika@example.com",
+                "password": "squeak-squeak1",
+            }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(response.cookies().get("jwt").is_some());
+
+        let response = request
+            .post("/api/auth/login")
+            .json(&serde_json::json!({
+                "email": "pika@example.com",
+                "password": "wrong-password1",
+            }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn login_rejects_banned_user() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        request.post("/api/auth/register").json(&register_payload()).await;
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        let mut user: users::ActiveModel = user.into();
+        user.is_banned = Set(true);
+        user.update(&ctx.db).await.unwrap();
+
+        let response = request
+            .post("/api/auth/login")
+            .json(&serde_json::json!({
+                "email": "pika@example.com",
+                "password": "squeak-squeak1",
+            }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+        assert!(response.cookies().get("jwt").is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_verify_email() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        request.post("/api/auth/register").json(&register_payload()).await;
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        assert!(!user.is_verified());
+        let token = user.email_verification_token.unwrap();
+
+        let response = request.get(&format!("/api/auth/verify/{token}")).await;
+        assert_eq!(response.status_code(), 307);
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        assert!(user.is_verified());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn verify_rejects_expired_token() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        request.post("/api/auth/register").json(&register_payload()).await;
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        let token = user.email_verification_token.clone().unwrap();
+        let mut user: users::ActiveModel = user.into();
+        user.email_verification_sent_at = Set(Some((Local::now() - Duration::days(3)).into()));
+        user.update(&ctx.db).await.unwrap();
+
+        let response = request.get(&format!("/api/auth/verify/{token}")).await;
+        assert_eq!(response.status_code(), 401);
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        assert!(!user.is_verified());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_reset_password() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        request.post("/api/auth/register").json(&register_payload()).await;
+
+        let response = request
+            .post("/api/auth/forgot")
+            .json(&serde_json::json!({ "email": "pika@example.com" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        let token = user.reset_token.unwrap();
+
+        let response = request
+            .post("/api/auth/reset")
+            .json(&serde_json::json!({ "token": token, "password": "weak" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .post("/api/auth/reset")
+            .json(&serde_json::json!({ "token": token, "password": "new-squeak2" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        assert!(user.verify_password("new-squeak2"));
+        assert!(user.reset_token.is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn reset_rejects_expired_token() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        request.post("/api/auth/register").json(&register_payload()).await;
+        request
+            .post("/api/auth/forgot")
+            .json(&serde_json::json!({ "email": "pika@example.com" }))
+            .await;
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        let token = user.reset_token.clone().unwrap();
+        let mut user: users::ActiveModel = user.into();
+        user.reset_sent_at = Set(Some((Local::now() - Duration::hours(2)).into()));
+        user.update(&ctx.db).await.unwrap();
+
+        let response = request
+            .post("/api/auth/reset")
+            .json(&serde_json::json!({ "token": token, "password": "new-squeak2" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let user = users::Model::find_by_email(&ctx.db, "pika@example.com").await.unwrap();
+        assert!(user.verify_password("squeak-squeak1"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_login_with_magic_link() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let response = request
+            .post("/api/auth/magic-link")
+            .json(&serde_json::json!({ "email": "user2@example.com" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let user = users::Model::find_by_email(&ctx.db, "user2@example.com").await.unwrap();
+        let token = user.magic_link_token.unwrap();
+
+        let response = request.get(&format!("/api/auth/magic-link/{token}")).await;
+        assert_eq!(response.status_code(), 307);
+        assert_eq!(response.cookies().get("username").unwrap().value(), "user2");
+
+        // The link only works once
+        let response = request.get(&format!("/api/auth/magic-link/{token}")).await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn moderator_can_ban_and_unban() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        // Regular users can't ban
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "user_id": 1, "reason": "revenge" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 999, "reason": "Spamming" }))
+            .await;
+        assert_eq!(response.status_code(), 404);
+
+        // Nor can moderators ban themselves
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 1, "reason": "Oops" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 2, "reason": "Spamming" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let ban: kboard::models::bans::BanResponse = response.json();
+        assert_eq!(ban.reason, "Spamming");
+        assert_eq!(ban.moderator_id, 1);
+        assert!(ban.expires_at.is_none());
+        assert!(users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap().is_banned);
+
+        let response = request
+            .post("/api/auth/unban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 2 }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert!(!users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap().is_banned);
+
+        // Admins are out of a global moderator's reach
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        user2.into_active_model().set_role(&ctx.db, Role::Admin).await.unwrap();
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 2, "reason": "Spamming" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+        assert!(!users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap().is_banned);
+
+        // And so are their bans
+        bans::Model::create(&ctx.db, 2, 2, "Locked out for maintenance".to_string(), None).await.unwrap();
+        let response = request
+            .post("/api/auth/unban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 2 }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+        assert!(users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap().is_banned);
+
+        let response = request
+            .post("/api/auth/unban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 999 }))
+            .await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn board_moderators_get_the_moderator_cookie() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        board_moderators::Model::add(&ctx.db, 1, user2.id).await.unwrap();
+
+        request
+            .post("/api/auth/magic-link")
+            .json(&serde_json::json!({ "email": "user2@example.com" }))
+            .await;
+        let token = users::Model::find_by_email(&ctx.db, "user2@example.com")
+            .await
+            .unwrap()
+            .magic_link_token
+            .unwrap();
+
+        let response = request.get(&format!("/api/auth/magic-link/{token}")).await;
+        assert_eq!(response.status_code(), 307);
+        assert_eq!(response.cookies().get("is_moderator").unwrap().value(), "true");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn banned_moderator_cannot_ban_or_unban() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        bans::Model::create(&ctx.db, user1.id, user1.id, "Abusing the ban hammer".to_string(), None)
+            .await
+            .unwrap();
+
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 2, "reason": "Spamming" }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+
+        // Nor can they lift their own ban
+        let response = request
+            .post("/api/auth/unban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": user1.id }))
+            .await;
+        assert_eq!(response.status_code(), 403);
+        assert!(users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap().is_banned);
+    })
+    .await;
+}
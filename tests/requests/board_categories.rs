@@ -0,0 +1,106 @@
+use kboard::{
+    app::App,
+    models::{board_categories::{BoardTree, Model as Category}, boards, users},
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn can_build_board_tree() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let response = request
+            .post("/api/board_categories")
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "title": "Injuries", "description": "Things that hurt" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let category: Category = response.json();
+
+        let response = request
+            .patch("/api/boards/1")
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "category_id": category.id }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .post("/api/boards")
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "title": "Dislocated Jaw Stories", "parent_id": 1 }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let sub_board: boards::Model = response.json();
+
+        // Boards only nest one level deep
+        let response = request
+            .post("/api/boards")
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "title": "Too Deep", "parent_id": sub_board.id }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .post(&format!("/api/boards/{}/threads", sub_board.id))
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "title": "Yawned too hard", "initial_reply_text": "Click." }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let tree: BoardTree = request.get("/api/boards/tree").await.json();
+        assert!(tree.uncategorized.is_empty());
+        assert_eq!(tree.categories.len(), 1);
+
+        let injuries = &tree.categories[0];
+        assert_eq!(injuries.title, "Injuries");
+        assert_eq!(injuries.total_threads, 5);
+        assert_eq!(injuries.boards.len(), 1);
+
+        let jaw = &injuries.boards[0];
+        assert_eq!(jaw.num_threads, 4);
+        assert_eq!(jaw.total_threads, 5);
+        assert_eq!(jaw.sub_boards.len(), 1);
+        assert_eq!(jaw.sub_boards[0].num_threads, 1);
+        assert_eq!(jaw.last_active, jaw.sub_boards[0].last_active);
+
+        // Sub-boards of an archived board move up to the category's top level
+        let response = request
+            .patch("/api/boards/1")
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "is_archived": true }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let tree: BoardTree = request.get("/api/boards/tree").await.json();
+        assert!(tree.uncategorized.is_empty());
+        let boards = &tree.categories[0].boards;
+        assert_eq!(boards.len(), 1);
+        assert_eq!(boards[0].id, sub_board.id);
+        assert_eq!(boards[0].total_threads, 1);
+
+        let response = request
+            .patch("/api/boards/1")
+            .add_header("authorization", format!("Bearer {token}"))
+            .json(&serde_json::json!({ "is_archived": false }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        // Deleting the category keeps its boards
+        let response = request
+            .delete(&format!("/api/board_categories/{}", category.id))
+            .add_header("authorization", format!("Bearer {token}"))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let tree: BoardTree = request.get("/api/boards/tree").await.json();
+        assert!(tree.categories.is_empty());
+        assert_eq!(tree.uncategorized[0].total_threads, 5);
+    })
+    .await;
+}
@@ -0,0 +1,307 @@
+use axum::body::Bytes;
+use kboard::{
+    app::App,
+    controllers::boards::{CreateReplyRequest, RepliesResponse},
+    models::users,
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+use std::io::Cursor;
+
+/// A 1x1 red PNG
+const TINY_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xde, 0x00, 0x00, 0x00, 0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0x00,
+    0x00, 0x03, 0x01, 0x01, 0x00, 0xc9, 0xfe, 0x92, 0xef, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+    0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// Asks for an upload URL, uploads the bytes to it and evaluates to the
+/// pending key
+macro_rules! upload {
+    ($request:expr, $token:expr, $content_type:expr, $bytes:expr) => {{
+        let response = $request
+            .post("/api/images/presign")
+            .add_header("authorization", format!("Bearer {}", $token))
+            .json(&serde_json::json!({"content_type": $content_type}))
+            .await;
+        let presigned: serde_json::Value = response.json();
+        let pending_key = presigned["pending_key"].as_str().unwrap().to_string();
+
+        let response = $request
+            .put(&format!("/api/images/upload/{pending_key}"))
+            .bytes(Bytes::from($bytes))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        pending_key
+    }};
+}
+
+/// A JPEG with an EXIF segment holding a GPS marker
+fn jpeg_with_exif() -> Vec<u8> {
+    let mut jpeg = Vec::new();
+    image::DynamicImage::new_rgb8(16, 16)
+        .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+        .unwrap();
+
+    let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\0\0\0\0\0GPSLatitude".to_vec();
+    let length = u16::try_from(exif.len() + 2).unwrap();
+    let mut segment = vec![0xff, 0xe1];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.append(&mut exif);
+
+    // Right after the start-of-image marker
+    jpeg.splice(2..2, segment);
+    jpeg
+}
+
+#[tokio::test]
+#[serial]
+async fn can_upload_attach_and_serve_image_from_local_storage() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let token = user1
+            .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
+            .unwrap();
+
+        let response = request
+            .post("/api/images/presign")
+            .add_header("authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({"content_type": "image/bmp"}))
+            .await;
+        assert_eq!(response.status_code(), 400, "Only web image formats can be uploaded");
+
+        let response = request
+            .post("/api/images/presign")
+            .add_header("authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({"content_type": "image/png"}))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let presigned: serde_json::Value = response.json();
+        let pending_key = presigned["pending_key"].as_str().unwrap().to_string();
+        let upload_url = presigned["upload_url"].as_str().unwrap();
+        assert_eq!(
+            upload_url,
+            format!("http://localhost:5150/api/images/upload/{pending_key}")
+        );
+
+        let upload_path = format!("/api/images/upload/{pending_key}");
+        let response = request
+            .put("/api/images/upload/pending/00000000-0000-0000-0000-000000000000.png")
+            .bytes(Bytes::from_static(TINY_PNG))
+            .await;
+        assert_eq!(response.status_code(), 400, "Keys that weren't handed out can't be uploaded to");
+
+        let response = request
+            .put(&upload_path)
+            .bytes(Bytes::from_static(TINY_PNG))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .put(&upload_path)
+            .bytes(Bytes::from_static(TINY_PNG))
+            .await;
+        assert_eq!(response.status_code(), 400, "Uploads can't be overwritten");
+
+        let response = request.get(&format!("/api/images/files/{pending_key}")).await;
+        assert_eq!(response.status_code(), 404, "Pending uploads aren't served");
+
+        let payload = CreateReplyRequest {
+            body: "Here's the seagull.".to_string(),
+            reply_to: None,
+            pending_image_key: Some(pending_key),
+        };
+        let response = request
+            .post("/api/boards/1/threads/1/replies")
+            .add_header("authorization", format!("Bearer {}", token))
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let reply_id = response.json::<serde_json::Value>()["reply_id"].as_i64().unwrap();
+
+        let response = request.get("/api/boards/1/threads/1/replies?page_size=10&page_number=0").await;
+        let replies_response: RepliesResponse = response.json();
+        let reply = replies_response.replies.last().unwrap();
+        let image = reply.image.as_ref().unwrap();
+        assert_eq!(
+            image.url,
+            format!("http://localhost:5150/api/images/files/replies/{reply_id}/image.png")
+        );
+        assert_eq!((image.width, image.height), (Some(1), Some(1)));
+        assert!(image.thumbnail.is_none() && image.medium.is_none(), "Nothing to scale down");
+
+        let response = request
+            .get(&format!("/api/images/files/replies/{reply_id}/image.png"))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(response.header("content-type"), "image/png");
+        let served = image::load_from_memory(response.as_bytes()).unwrap();
+        assert_eq!((served.width(), served.height()), (1, 1));
+
+        let response = request.get("/api/images/files/replies%2F..%2F..%2Fsecret.png").await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn invalid_images_are_rejected_and_metadata_is_stripped() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let token = user1
+            .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
+            .unwrap();
+
+        let post = |pending_key: String| {
+            let token = token.clone();
+            let request = &request;
+            async move {
+                request
+                    .post("/api/boards/1/threads/1/replies")
+                    .add_header("authorization", format!("Bearer {}", token))
+                    .json(&CreateReplyRequest {
+                        body: "Look at this.".to_string(),
+                        reply_to: None,
+                        pending_image_key: Some(pending_key),
+                    })
+                    .await
+            }
+        };
+
+        let reply_count = || async {
+            let response = request.get("/api/boards/1/threads/1/replies?page_size=100&page_number=0").await;
+            response.json::<RepliesResponse>().replies.len()
+        };
+        let replies_before = reply_count().await;
+
+        // Not an image at all
+        let pending_key = upload!(request, token, "image/png", b"#!/bin/sh\nrm -rf /".to_vec());
+        let response = post(pending_key).await;
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("isn't a JPEG, PNG, GIF or WebP image"));
+
+        // An image, but not the kind it was uploaded as
+        let pending_key = upload!(request, token, "image/png", jpeg_with_exif());
+        let response = post(pending_key).await;
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("is JPEG but was uploaded as PNG"));
+
+        // The right magic number with nothing decodable after it
+        let mut truncated = TINY_PNG[..40].to_vec();
+        truncated.extend_from_slice(&[0; 8]);
+        let pending_key = upload!(request, token, "image/png", truncated);
+        let response = post(pending_key).await;
+        assert_eq!(response.status_code(), 400);
+
+        // Presigned but never uploaded to
+        let response = request
+            .post("/api/images/presign")
+            .add_header("authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({"content_type": "image/png"}))
+            .await;
+        let presigned: serde_json::Value = response.json();
+        let response = post(presigned["pending_key"].as_str().unwrap().to_string()).await;
+        assert_eq!(response.status_code(), 400);
+
+        // Bigger than allowed, which upload URLs for S3 can't stop
+        let response = request
+            .post("/api/images/presign")
+            .add_header("authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({"content_type": "image/png"}))
+            .await;
+        let presigned: serde_json::Value = response.json();
+        let pending_key = presigned["pending_key"].as_str().unwrap().to_string();
+        let mut oversized = TINY_PNG.to_vec();
+        oversized.resize(kboard::image_storage::MAX_UPLOAD_BYTES + 1, 0);
+        kboard::image_storage::get().put(&pending_key, "image/png", oversized).await.unwrap();
+        let response = post(pending_key).await;
+        assert_eq!(response.status_code(), 400);
+        assert!(response.text().contains("Image is too large"));
+
+        assert_eq!(reply_count().await, replies_before, "Rejected images fail the whole post");
+
+        let original = jpeg_with_exif();
+        assert!(original.windows(4).any(|w| w == b"Exif"));
+        let pending_key = upload!(request, token, "image/jpeg", original);
+        let response = post(pending_key).await;
+        assert_eq!(response.status_code(), 200);
+        let reply_id = response.json::<serde_json::Value>()["reply_id"].as_i64().unwrap();
+
+        let response = request
+            .get(&format!("/api/images/files/replies/{reply_id}/image.jpg"))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let served = response.as_bytes();
+        assert!(!served.windows(4).any(|w| w == b"Exif"), "EXIF is stripped");
+        assert!(!served.windows(3).any(|w| w == b"GPS"));
+        assert_eq!(image::guess_format(served).unwrap(), image::ImageFormat::Jpeg);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn large_images_get_thumbnail_and_medium_variants() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let token = user1
+            .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
+            .unwrap();
+
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgba8(1600, 800)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let pending_key = upload!(request, token, "image/png", png);
+
+        let response = request
+            .post("/api/boards/1/threads")
+            .add_header("authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({
+                "title": "A wide seagull",
+                "initial_reply_text": "Taken from the pier.",
+                "pending_image_key": pending_key,
+            }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let thread_id = response.json::<serde_json::Value>()["thread_id"].as_i64().unwrap();
+
+        let response = request
+            .get(&format!("/api/boards/1/threads/{thread_id}/replies?page_size=10&page_number=0"))
+            .await;
+        let replies_response: RepliesResponse = response.json();
+        let reply = &replies_response.replies[0];
+        let image = reply.image.as_ref().unwrap();
+        assert_eq!((image.width, image.height), (Some(1600), Some(800)));
+
+        let thumbnail = image.thumbnail.as_ref().unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (320, 160));
+        assert_eq!(
+            thumbnail.url,
+            format!("http://localhost:5150/api/images/files/replies/{}/thumbnail.png", reply.id)
+        );
+        let medium = image.medium.as_ref().unwrap();
+        assert_eq!((medium.width, medium.height), (1024, 512));
+
+        let response = request
+            .get(&format!("/api/images/files/replies/{}/medium.png", reply.id))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let served = image::load_from_memory(response.as_bytes()).unwrap();
+        assert_eq!((served.width(), served.height()), (1024, 512));
+    })
+    .await;
+}
@@ -1 +1,9 @@
+mod auth;
+mod board_categories;
 mod boards;
+mod images;
+mod moderation;
+mod notifications;
+mod search;
+mod subscriptions;
+mod users;
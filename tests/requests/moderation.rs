@@ -0,0 +1,403 @@
+use kboard::{
+    app::App,
+    controllers::boards::CreateThreadResponse,
+    controllers::moderation::{ModActionsResponse, ReportsResponse, ResolveReportsResponse},
+    models::{replies, threads, users},
+    permissions::Role,
+};
+use loco_rs::testing::prelude::*;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, Set};
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn moderator_actions_are_logged() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        // Only moderators can read the log
+        let response = request
+            .get("/api/moderation/actions")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .delete("/api/boards/1/threads/1/replies/5")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "action": "hide", "reason": "Off topic" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .delete("/api/boards/1/threads/2?reason=Spam")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": 2, "reason": "Repeat spammer" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        // Users deleting their own replies aren't moderating
+        let response = request
+            .delete("/api/boards/1/threads/1/replies/1")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "action": "delete" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .get("/api/moderation/actions")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let log: ModActionsResponse = response.json();
+        assert_eq!(log.total_count, 3);
+        let actions: Vec<&str> = log.actions.iter().map(|a| a.action.as_str()).collect();
+        assert_eq!(actions, vec!["ban_user", "delete_thread", "hide_reply"]);
+
+        let hide = &log.actions[2];
+        assert_eq!(hide.actor_name, "user1");
+        assert_eq!(hide.target_type, "reply");
+        assert_eq!(hide.target_id, 5);
+        assert_eq!(hide.target_user_id, Some(2));
+        assert_eq!(hide.board_id, Some(1));
+        assert_eq!(hide.reason.as_deref(), Some("Off topic"));
+        assert_eq!(hide.previous_state.as_ref().unwrap()["reply_status"], 1);
+
+        let delete = &log.actions[1];
+        assert_eq!(delete.reason.as_deref(), Some("Spam"));
+        assert_eq!(delete.previous_state.as_ref().unwrap()["title"], "Startled by a chipmunk.");
+
+        let response = request
+            .get("/api/moderation/actions?board_id=1&page_size=1")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await;
+        let log: ModActionsResponse = response.json();
+        assert_eq!(log.total_count, 2);
+        assert_eq!(log.actions.len(), 1);
+        assert_eq!(log.actions[0].action, "delete_thread");
+
+        let response = request
+            .get("/api/moderation/actions?target_user_id=2&action=ban_user")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await;
+        let log: ModActionsResponse = response.json();
+        assert_eq!(log.total_count, 1);
+        assert_eq!(log.actions[0].reason.as_deref(), Some("Repeat spammer"));
+
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn reports_are_queued_and_resolved() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let report = |token: String, reply_id: i32, category: &'static str| {
+            request
+                .post(&format!("/api/boards/1/threads/1/replies/{reply_id}/report"))
+                .add_header("authorization", format!("Bearer {token}"))
+                .json(&serde_json::json!({ "category": category, "reason": "Not nice" }))
+        };
+
+        assert_eq!(report(user2_token.clone(), 1, "harassment").await.status_code(), 200);
+        assert_eq!(report(moderator_token.clone(), 1, "spam").await.status_code(), 200);
+        assert_eq!(report(user2_token.clone(), 3, "off_topic").await.status_code(), 200);
+
+        // One open report per user per reply
+        assert_eq!(report(user2_token.clone(), 1, "spam").await.status_code(), 400);
+        assert_eq!(report(user2_token.clone(), 5, "rude").await.status_code(), 400);
+        assert_eq!(report(user2_token.clone(), 999, "spam").await.status_code(), 404);
+        assert_eq!(report(user2_token.clone(), 4, "spam").await.status_code(), 400, "Hidden replies can't be reported");
+
+        let response = request
+            .get("/api/moderation/reports")
+            .add_header("authorization", format!("Bearer {user2_token}"))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .get("/api/moderation/reports")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let queue: ReportsResponse = response.json();
+        assert_eq!(queue.total_count, 2);
+        assert_eq!(queue.replies[0].reply_id, 1);
+        assert_eq!(queue.replies[0].reports.len(), 2);
+        assert_eq!(queue.replies[0].reports[0].reporter_name, "user2");
+        assert_eq!(queue.replies[1].reply_id, 3);
+
+        let response = request
+            .post("/api/moderation/reports/1/resolve")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .json(&serde_json::json!({ "action": "hide", "reason": "Harassment" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let resolved: ResolveReportsResponse = response.json();
+        assert_eq!(resolved.resolved, 2);
+        let reply = replies::Entity::find_by_id(1).one(&ctx.db).await.unwrap().unwrap();
+        assert_eq!(reply.reply_status, 2);
+
+        let response = request
+            .post("/api/moderation/reports/3/resolve")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .json(&serde_json::json!({ "action": "dismiss" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let reply = replies::Entity::find_by_id(3).one(&ctx.db).await.unwrap().unwrap();
+        assert_eq!(reply.reply_status, 1);
+
+        let response = request
+            .get("/api/moderation/reports")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .await;
+        let queue: ReportsResponse = response.json();
+        assert_eq!(queue.total_count, 0);
+
+        let response = request
+            .get("/api/moderation/actions")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .await;
+        let log: ModActionsResponse = response.json();
+        let actions: Vec<&str> = log.actions.iter().map(|a| a.action.as_str()).collect();
+        assert_eq!(actions, vec!["dismiss_reports", "hide_reply"]);
+
+        let response = request
+            .delete("/api/boards/1/threads/3")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(report(user2_token.clone(), 3, "spam").await.status_code(), 404, "Deleted threads can't be reported in");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn moderator_can_split_and_merge_threads() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let split = serde_json::json!({ "first_reply_id": 5, "last_reply_id": 5, "title": "Sympathy" });
+
+        // Reply 5 is the thread's newest, so it's what the thread was last active with
+        let first_reply = replies::Entity::find_by_id(1).one(&ctx.db).await.unwrap().unwrap();
+        let newest_reply = replies::Entity::find_by_id(5).one(&ctx.db).await.unwrap().unwrap();
+        let mut thread = threads::Entity::find_by_id(1).one(&ctx.db).await.unwrap().unwrap().into_active_model();
+        thread.last_active = Set(newest_reply.created_at.naive_utc());
+        thread.update(&ctx.db).await.unwrap();
+
+        let response = request
+            .post("/api/boards/1/threads/1/split")
+            .add_header("authorization", format!("Bearer {user2_token}"))
+            .json(&split)
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .post("/api/boards/1/threads/1/split")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .json(&split)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let new_thread: CreateThreadResponse = response.json();
+
+        let thread = threads::Entity::find_by_id(1).one(&ctx.db).await.unwrap().unwrap();
+        assert_eq!(thread.last_active, first_reply.created_at.naive_utc());
+        assert_eq!(thread.num_replies, 1);
+
+        let response = request
+            .post(&format!("/api/boards/1/threads/{}/merge", new_thread.thread_id))
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .json(&serde_json::json!({ "into_thread_id": 1 }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let replies = replies::Entity::find()
+            .filter(replies::Column::ThreadId.eq(1))
+            .count(&ctx.db)
+            .await
+            .unwrap();
+        assert_eq!(replies, 2);
+
+        let response = request
+            .get("/api/moderation/actions")
+            .add_header("authorization", format!("Bearer {moderator_token}"))
+            .await;
+        let log: ModActionsResponse = response.json();
+        let actions: Vec<&str> = log.actions.iter().map(|a| a.action.as_str()).collect();
+        assert_eq!(actions, vec!["merge_thread", "split_thread"]);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn board_moderators_only_moderate_their_boards() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        // Global moderators can't hand out roles; admins can
+        let response = request
+            .patch(&format!("/api/users/{}/role", user2.id))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "role": "global_moderator" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let user1 = user1.into_active_model().set_role(&ctx.db, Role::Admin).await.unwrap();
+        assert_eq!(user1.role(), Role::Admin);
+
+        let response = request
+            .patch(&format!("/api/users/{}/role", user2.id))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "role": "board_moderator" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .post("/api/boards/1/moderators")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "user_id": user2.id }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .post("/api/boards")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "title": "Broken Toe Stories" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let other_board: serde_json::Value = response.json();
+
+        let response = request
+            .post(&format!("/api/boards/{}/moderators", other_board["id"]))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": user2.id }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        // Moderating another board gives no power over board 1
+        let response = request
+            .delete("/api/boards/1/threads/1/replies/1")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "action": "hide" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .post("/api/boards/1/moderators")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "user_id": user2.id }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .delete("/api/boards/1/threads/1/replies/1")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "action": "hide" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .patch("/api/boards/1/threads/1")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "is_locked": true }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        // Bans and board management stay with global moderators
+        let response = request
+            .post("/api/auth/ban")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "user_id": user1.id, "reason": "Mutiny" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .patch("/api/boards/1")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "title": "Mine now" }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let moderators: Vec<serde_json::Value> = request
+            .get("/api/boards/1/moderators")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await
+            .json();
+        assert_eq!(moderators.len(), 1);
+        assert_eq!(moderators[0]["user_id"], user2.id);
+
+        let response = request
+            .delete(&format!("/api/boards/1/moderators/{}", user2.id))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .patch("/api/boards/1/threads/1")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&serde_json::json!({ "is_locked": false }))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        // Promoting to global moderator applies everywhere
+        let response = request
+            .patch(&format!("/api/users/{}/role", user2.id))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "role": "global_moderator", "reason": "Trusted" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .get("/api/moderation/actions?action=set_role")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let log: ModActionsResponse = response.json();
+        assert_eq!(log.total_count, 1);
+        assert_eq!(log.actions[0].previous_state, Some(serde_json::json!({ "role": "member" })));
+
+        let response = request
+            .patch(&format!("/api/users/{}/role", user1.id))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "role": "member" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+    })
+    .await;
+}
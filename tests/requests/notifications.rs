@@ -0,0 +1,174 @@
+use kboard::{
+    app::App,
+    controllers::{
+        boards::CreateReplyRequest,
+        notifications::{MarkAllReadResponse, NotificationsResponse, UnreadCountResponse},
+    },
+    models::users,
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn replies_mentions_and_moderation_raise_notifications() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let user1_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        // user1 started thread 1 and wrote reply 1 in it, so this is one
+        // notification rather than two
+        for (body, reply_to) in [("Same here", Some(1)), ("What do you think, @user1?", None)] {
+            let payload = CreateReplyRequest {
+                body: body.to_string(),
+                reply_to,
+                pending_image_key: None,
+            };
+            let response = request
+                .post("/api/boards/1/threads/1/replies")
+                .add_header("authorization", format!("Bearer {}", user2_token))
+                .json(&payload)
+                .await;
+            assert_eq!(response.status_code(), 200);
+        }
+
+        // Nobody hears about their own posts
+        let payload = CreateReplyRequest {
+            body: "Thanks!".to_string(),
+            reply_to: None,
+            pending_image_key: None,
+        };
+        let response = request
+            .post("/api/boards/1/threads/1/replies")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .get("/api/notifications")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let notifications: NotificationsResponse = response.json();
+        assert_eq!(notifications.total_count, 2);
+        assert_eq!(notifications.unread_count, 2);
+        let kinds: Vec<&str> = notifications.notifications.iter().map(|n| n.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["mention", "reply"]);
+        assert_eq!(notifications.notifications[1].actor_name, "user2");
+        assert_eq!(notifications.notifications[1].thread_title.as_deref(), Some("Startled by a seagull."));
+
+        // user1 moderates board 1
+        let response = request
+            .delete("/api/boards/1/threads/2/replies/2")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .json(&serde_json::json!({ "action": "hide", "reason": "Off topic" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let notifications: NotificationsResponse = request
+            .get("/api/notifications")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await
+            .json();
+        // user2 follows thread 1 since replying to it, so they also heard
+        // about user1's reply there
+        assert_eq!(notifications.total_count, 2);
+        assert_eq!(notifications.notifications[1].kind, "thread_reply");
+        let notification = &notifications.notifications[0];
+        assert_eq!(notification.kind, "mod_action");
+        assert_eq!(notification.action.as_deref(), Some("hide_reply"));
+        assert_eq!(notification.reason.as_deref(), Some("Off topic"));
+        assert_eq!(notification.thread_id, Some(2));
+        assert_eq!(notification.reply_id, Some(2));
+
+        let response = request.get("/api/notifications/unread_count").await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn notifications_can_be_marked_read() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let user1_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        for body in ["First", "Second", "Third"] {
+            let payload = CreateReplyRequest {
+                body: body.to_string(),
+                reply_to: None,
+                pending_image_key: None,
+            };
+            let response = request
+                .post("/api/boards/1/threads/1/replies")
+                .add_header("authorization", format!("Bearer {}", user2_token))
+                .json(&payload)
+                .await;
+            assert_eq!(response.status_code(), 200);
+        }
+
+        let notifications: NotificationsResponse = request
+            .get("/api/notifications")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(notifications.unread_count, 3);
+        assert!(notifications.notifications.iter().all(|n| n.kind == "thread_reply" && !n.is_read));
+        let newest = notifications.notifications[0].id;
+
+        let response = request
+            .post(&format!("/api/notifications/{newest}/read"))
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await;
+        assert_eq!(response.status_code(), 404, "Only the recipient can mark a notification read");
+
+        let response = request
+            .post(&format!("/api/notifications/{newest}/read"))
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let unread: UnreadCountResponse = request
+            .get("/api/notifications/unread_count")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(unread.unread_count, 2);
+
+        let notifications: NotificationsResponse = request
+            .get("/api/notifications?unread_only=true")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(notifications.total_count, 2);
+        assert!(notifications.notifications.iter().all(|n| n.id != newest));
+
+        let response = request
+            .post("/api/notifications/read_all")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let marked: MarkAllReadResponse = response.json();
+        assert_eq!(marked.marked, 2);
+
+        let unread: UnreadCountResponse = request
+            .get("/api/notifications/unread_count")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(unread.unread_count, 0);
+    })
+    .await;
+}
@@ -0,0 +1,230 @@
+use kboard::{app::App, models::users};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+fn reply_ids(response: &serde_json::Value) -> Vec<i64> {
+    response["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["reply_id"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn search_is_ranked_and_follows_reply_changes() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        // Words are stemmed, and the hidden reply 4 isn't searchable
+        let found: serde_json::Value = request.get("/api/search/replies?q=startles").await.json();
+        let mut ids = reply_ids(&found);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        // Quoted phrases have to match in order, and search syntax is just text
+        let found: serde_json::Value = request.get("/api/search/replies?q=%22seagull%20startled%22").await.json();
+        assert_eq!(reply_ids(&found), vec![1]);
+        let found: serde_json::Value = request.get("/api/search/replies?q=%22startled%20seagull%22").await.json();
+        assert!(reply_ids(&found).is_empty());
+        let found: serde_json::Value = request.get("/api/search/replies?q=seagull%20OR%20(NEAR*").await.json();
+        assert!(reply_ids(&found).is_empty());
+
+        // The closer match comes first
+        let response = request
+            .post("/api/boards/1/threads/3/replies")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "body": "Seagulls! Seagulls everywhere!" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let new_reply_id = response.json::<serde_json::Value>()["reply_id"].as_i64().unwrap();
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=seagull").await.json();
+        assert_eq!(reply_ids(&found), vec![new_reply_id, 1]);
+
+        // Edits are reindexed
+        let response = request
+            .patch(&format!("/api/boards/1/threads/3/replies/{new_reply_id}"))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "body": "Pelicans, actually." }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=seagull").await.json();
+        assert_eq!(reply_ids(&found), vec![1]);
+        let found: serde_json::Value = request.get("/api/search/replies?q=pelican").await.json();
+        assert_eq!(reply_ids(&found), vec![new_reply_id]);
+
+        // Hidden and deleted replies drop out
+        let response = request
+            .delete("/api/boards/1/threads/1/replies/1")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "action": "hide" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let response = request
+            .delete(&format!("/api/boards/1/threads/3/replies/{new_reply_id}"))
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "action": "delete" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=seagull").await.json();
+        assert!(reply_ids(&found).is_empty());
+        let found: serde_json::Value = request.get("/api/search/replies?q=pelican").await.json();
+        assert!(reply_ids(&found).is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn search_filters_pages_and_highlights() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&limit=2").await.json();
+        assert_eq!(found["total_found"], 3);
+        let first_page = reply_ids(&found);
+        assert_eq!(first_page.len(), 2);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&limit=2&offset=2").await.json();
+        assert_eq!(found["total_found"], 3);
+        let second_page = reply_ids(&found);
+        assert_eq!(second_page.len(), 1);
+        assert!(!first_page.contains(&second_page[0]));
+
+        // Matched terms are highlighted and the rest of the text is escaped
+        let found: serde_json::Value = request.get("/api/search/replies?q=chipmunk").await.json();
+        assert_eq!(
+            found["results"][0]["snippet"],
+            "I was runnning. A <mark>chipmunk</mark> startled me, and then I fell on my face."
+        );
+        let found: serde_json::Value = request.get("/api/search/replies?q=terrible").await.json();
+        assert_eq!(found["results"][0]["snippet"], "Oh wow. That&#39;s <mark>terrible</mark>!");
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&author_id=1").await.json();
+        let mut ids = reply_ids(&found);
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3]);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&thread_id=2").await.json();
+        assert_eq!(reply_ids(&found), vec![2]);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&board_id=2").await.json();
+        assert_eq!(found["total_found"], 0);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&has_image=true").await.json();
+        assert_eq!(found["total_found"], 0);
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&has_image=false").await.json();
+        assert_eq!(found["total_found"], 3);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&from=2000-01-01&to=2099-12-31").await.json();
+        assert_eq!(found["total_found"], 3);
+        let found: serde_json::Value = request.get("/api/search/replies?q=startled&to=2000-01-01").await.json();
+        assert_eq!(found["total_found"], 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_search_thread_titles_alone_or_with_replies() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let found: serde_json::Value = request.get("/api/search/threads?q=chipmunks").await.json();
+        assert_eq!(found["total_found"], 1);
+        assert_eq!(found["results"][0]["thread_id"], 2);
+        assert_eq!(found["results"][0]["title"], "Startled by a <mark>chipmunk</mark>.");
+        assert_eq!(found["results"][0]["poster_name"], "user2");
+
+        let found: serde_json::Value = request.get("/api/search/threads?q=startled&author_id=2").await.json();
+        assert_eq!(found["total_found"], 2);
+
+        // Both kinds of hit come back tagged, in one ranking
+        let found: serde_json::Value = request.get("/api/search?q=chipmunk").await.json();
+        assert_eq!(found["total_found"], 2);
+        let mut hits: Vec<(String, i64)> = found["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| {
+                let id = if hit["type"] == "thread" { &hit["thread_id"] } else { &hit["reply_id"] };
+                (hit["type"].as_str().unwrap().to_string(), id.as_i64().unwrap())
+            })
+            .collect();
+        hits.sort();
+        assert_eq!(hits, vec![("reply".to_string(), 2), ("thread".to_string(), 2)]);
+        // The best hit of each kind is scaled to 1
+        let ranks: Vec<f64> = found["results"].as_array().unwrap().iter().map(|hit| hit["rank"].as_f64().unwrap()).collect();
+        assert_eq!(ranks, vec![1.0, 1.0]);
+
+        let found: serde_json::Value = request.get("/api/search?q=startled&limit=2&offset=4").await.json();
+        assert_eq!(found["total_found"], 7);
+        assert_eq!(found["results"].as_array().unwrap().len(), 2);
+
+        // Paging stops somewhere, rather than overflowing
+        let response = request.get("/api/search?q=startled&offset=10001").await;
+        assert_eq!(response.status_code(), 400);
+        let response = request.get(&format!("/api/search?q=startled&offset={}", u64::MAX)).await;
+        assert_eq!(response.status_code(), 400);
+
+        // Replies-only filters leave threads out
+        let found: serde_json::Value = request.get("/api/search?q=chipmunk&has_image=false").await.json();
+        assert_eq!(found["total_found"], 1);
+        assert_eq!(found["results"][0]["type"], "reply");
+
+        // Soft-deleted threads drop out of both
+        let response = request
+            .delete("/api/boards/1/threads/2")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let found: serde_json::Value = request.get("/api/search/threads?q=chipmunk").await.json();
+        assert_eq!(found["total_found"], 0);
+        let found: serde_json::Value = request.get("/api/search?q=chipmunk").await.json();
+        assert_eq!(found["total_found"], 0);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn reply_snippets_come_raw_and_rendered() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let moderator_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let response = request
+            .post("/api/boards/1/threads/3/replies")
+            .add_header("authorization", format!("Bearer {}", moderator_token))
+            .json(&serde_json::json!({ "body": "The **pelican** took my <b>lunch</b>." }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let found: serde_json::Value = request.get("/api/search/replies?q=pelican").await.json();
+        assert_eq!(
+            found["results"][0]["snippet"],
+            "The **<mark>pelican</mark>** took my &lt;b&gt;lunch&lt;/b&gt;."
+        );
+        assert_eq!(
+            found["results"][0]["snippet_html"],
+            "<p>The <strong><mark>pelican</mark></strong> took my &lt;b&gt;lunch&lt;/b&gt;.</p>\n"
+        );
+    })
+    .await;
+}
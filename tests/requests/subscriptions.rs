@@ -0,0 +1,231 @@
+use kboard::{
+    app::App,
+    controllers::{
+        boards::{CreateReplyRequest, SubscriptionResponse},
+        notifications::NotificationsResponse,
+        users::DigestFrequencyResponse,
+    },
+    models::{thread_subscriptions::DigestFrequency, users},
+};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn can_subscribe_and_unsubscribe() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let subscription: SubscriptionResponse = request
+            .get("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await
+            .json();
+        assert!(!subscription.subscribed);
+
+        let response = request
+            .post("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let subscription: SubscriptionResponse = request
+            .get("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await
+            .json();
+        assert!(subscription.subscribed);
+
+        let response = request
+            .delete("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let subscription: SubscriptionResponse = request
+            .get("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await
+            .json();
+        assert!(!subscription.subscribed);
+
+        // Replying follows the thread
+        let payload = CreateReplyRequest {
+            body: "Chihuahuas are scary".to_string(),
+            reply_to: None,
+            pending_image_key: None,
+        };
+        let response = request
+            .post("/api/boards/1/threads/3/replies")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let subscription: SubscriptionResponse = request
+            .get("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await
+            .json();
+        assert!(subscription.subscribed);
+
+        let response = request.post("/api/boards/1/threads/3/subscription").await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn subscribers_are_notified_and_emailed_per_their_frequency() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let user1_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        // Thread 2 is user2's, so user1 only hears about it by following it
+        let response = request
+            .post("/api/boards/1/threads/2/subscription")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let frequency: DigestFrequencyResponse = request
+            .get("/api/users/me/digest_frequency")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(frequency.frequency, DigestFrequency::Daily);
+
+        let response = request
+            .put("/api/users/me/digest_frequency")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .json(&serde_json::json!({ "frequency": "hourly" }))
+            .await;
+        assert_eq!(response.status_code(), 400);
+
+        let response = request
+            .put("/api/users/me/digest_frequency")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .json(&serde_json::json!({ "frequency": "immediate" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let frequency: DigestFrequencyResponse = response.json();
+        assert_eq!(frequency.frequency, DigestFrequency::Immediate);
+
+        let payload = CreateReplyRequest {
+            body: "It was a big chipmunk".to_string(),
+            reply_to: None,
+            pending_image_key: None,
+        };
+        let response = request
+            .post("/api/boards/1/threads/2/replies")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let deliveries = ctx.mailer.as_ref().unwrap().deliveries();
+        assert_eq!(deliveries.count, 1);
+        assert!(deliveries.messages[0].contains("user1@example.com"));
+
+        let notifications: NotificationsResponse = request
+            .get("/api/notifications")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(notifications.total_count, 1);
+        assert_eq!(notifications.notifications[0].kind, "thread_reply");
+        assert_eq!(notifications.notifications[0].thread_id, Some(2));
+
+        // Unsubscribing stops both
+        let response = request
+            .delete("/api/boards/1/threads/2/subscription")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let response = request
+            .post("/api/boards/1/threads/2/replies")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(ctx.mailer.as_ref().unwrap().deliveries().count, 1);
+        let notifications: NotificationsResponse = request
+            .get("/api/notifications")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await
+            .json();
+        assert_eq!(notifications.total_count, 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn mentions_are_not_emailed_twice_and_merges_keep_subscriptions() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let user1_token = user1.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+        let user2_token = user2.generate_jwt(&jwt_secret.secret, jwt_secret.expiration).unwrap();
+
+        let response = request
+            .post("/api/boards/1/threads/2/subscription")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+        let response = request
+            .put("/api/users/me/digest_frequency")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .json(&serde_json::json!({ "frequency": "immediate" }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        // The mention email is all user1 gets
+        let payload = CreateReplyRequest {
+            body: "@user1 it was a big chipmunk".to_string(),
+            reply_to: None,
+            pending_image_key: None,
+        };
+        let response = request
+            .post("/api/boards/1/threads/2/replies")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(ctx.mailer.as_ref().unwrap().deliveries().count, 1);
+
+        // user2 follows both threads by now, and user1 only thread 2
+        let response = request
+            .post("/api/boards/1/threads/3/subscription")
+            .add_header("authorization", format!("Bearer {}", user2_token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let response = request
+            .post("/api/boards/1/threads/2/merge")
+            .add_header("authorization", format!("Bearer {}", user1_token))
+            .json(&serde_json::json!({ "into_thread_id": 3 }))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        for token in [&user1_token, &user2_token] {
+            let subscription: SubscriptionResponse = request
+                .get("/api/boards/1/threads/3/subscription")
+                .add_header("authorization", format!("Bearer {}", token))
+                .await
+                .json();
+            assert!(subscription.subscribed);
+        }
+    })
+    .await;
+}
@@ -0,0 +1,82 @@
+use kboard::{app::App, controllers::users::ApiKeyResponse, models::users};
+use loco_rs::testing::prelude::*;
+use serial_test::serial;
+
+const USER1_API_KEY: &str = "lo-95ec80d7-cb60-4b70-9b4b-9ef74cb88758";
+
+#[tokio::test]
+#[serial]
+async fn can_get_api_key() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+        let jwt_secret = ctx.config.get_jwt_config().unwrap();
+        let token = user1
+            .generate_jwt(&jwt_secret.secret, jwt_secret.expiration)
+            .unwrap();
+
+        let response = request
+            .get("/api/users/me/api_key")
+            .add_header("authorization", format!("Bearer {}", token))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let api_key: ApiKeyResponse = response.json();
+        assert_eq!(api_key.api_key, USER1_API_KEY);
+
+        let response = request.get("/api/users/me/api_key").await;
+        assert_eq!(response.status_code(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_rotate_api_key() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let response = request
+            .post("/api/users/me/api_key/rotate")
+            .add_header("authorization", format!("Bearer {}", USER1_API_KEY))
+            .await;
+        assert_eq!(response.status_code(), 200);
+
+        let api_key: ApiKeyResponse = response.json();
+        assert_ne!(api_key.api_key, USER1_API_KEY);
+
+        let response = request
+            .get("/api/users/me/api_key")
+            .add_header("authorization", format!("Bearer {}", USER1_API_KEY))
+            .await;
+        assert_eq!(response.status_code(), 401);
+
+        let response = request
+            .get("/api/users/me/api_key")
+            .add_header("authorization", format!("Bearer {}", api_key.api_key))
+            .await;
+        assert_eq!(response.status_code(), 200);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn can_get_user_profile() {
+    request::<App, _, _>(|request, ctx| async move {
+        seed::<App>(&ctx).await.unwrap();
+
+        let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+
+        let response = request.get(&format!("/api/users/{}", user2.id)).await;
+        assert_eq!(response.status_code(), 200);
+        let profile: serde_json::Value = response.json();
+        assert_eq!(profile["name"], "user2");
+        assert!(profile.get("email").is_none());
+
+        let response = request.get("/api/users/999999").await;
+        assert_eq!(response.status_code(), 404);
+    })
+    .await;
+}
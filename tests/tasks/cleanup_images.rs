@@ -0,0 +1,82 @@
+use std::time::{Duration, SystemTime};
+
+use kboard::{
+    app::App,
+    image_storage,
+    models::replies,
+};
+use loco_rs::{boot::run_task, task, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
+use serial_test::serial;
+
+/// Root of the local image store in config/test.yaml
+const ROOT: &str = "storage/test-images";
+
+/// Stores a file at `key` that was last modified `age` ago
+async fn store(key: &str, age: Duration) {
+    image_storage::get().put(key, "image/png", b"png".to_vec()).await.unwrap();
+    let file = std::fs::File::options()
+        .write(true)
+        .open(format!("{ROOT}/{key}"))
+        .unwrap();
+    file.set_modified(SystemTime::now() - age).unwrap();
+}
+
+fn exists(key: &str) -> bool {
+    std::path::Path::new(&format!("{ROOT}/{key}")).exists()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cleanup_images_removes_only_orphans() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let db = &boot.app_context.db;
+
+    let hour = Duration::from_secs(60 * 60);
+    let abandoned = "pending/00000000-0000-4000-8000-000000000001.png";
+    let fresh = "pending/00000000-0000-4000-8000-000000000002.png";
+    store(abandoned, hour).await;
+    store(fresh, Duration::ZERO).await;
+
+    // Reply 1 is live and keeps its image, but not a leftover variant
+    let mut reply: replies::ActiveModel = replies::Entity::find_by_id(1).one(db).await.unwrap().unwrap().into_active_model();
+    reply.image_key = Set(Some("replies/1/image.png".to_string()));
+    reply.update(db).await.unwrap();
+    store("replies/1/image.png", hour).await;
+    store("replies/1/thumbnail.jpg", hour).await;
+
+    // Reply 5 is deleted
+    let mut reply: replies::ActiveModel = replies::Entity::find_by_id(5).one(db).await.unwrap().unwrap().into_active_model();
+    reply.image_key = Set(Some("replies/5/image.png".to_string()));
+    reply.reply_status = Set(3);
+    reply.update(db).await.unwrap();
+    store("replies/5/image.png", hour).await;
+
+    // No reply 9999 at all
+    store("replies/9999/image.png", hour).await;
+
+    let mut dry_run = task::Vars::default();
+    dry_run.cli.insert("dry_run".to_string(), "true".to_string());
+    run_task::<App>(&boot.app_context, Some(&"cleanup_images".to_string()), &dry_run)
+        .await
+        .unwrap();
+    assert!(exists(abandoned) && exists("replies/5/image.png"), "A dry run deletes nothing");
+
+    run_task::<App>(&boot.app_context, Some(&"cleanup_images".to_string()), &task::Vars::default())
+        .await
+        .unwrap();
+
+    assert!(!exists(abandoned));
+    assert!(exists(fresh), "Uploads within the presign window may still be attached");
+    assert!(exists("replies/1/image.png"));
+    assert!(!exists("replies/1/thumbnail.jpg"));
+    assert!(!exists("replies/5/image.png"));
+    assert!(!exists("replies/9999/image.png"));
+
+    let deleted_reply = replies::Entity::find_by_id(5).one(db).await.unwrap().unwrap();
+    assert_eq!(deleted_reply.image_key, None);
+
+    image_storage::get().delete(fresh).await.unwrap();
+    image_storage::get().delete("replies/1/image.png").await.unwrap();
+}
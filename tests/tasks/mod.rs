@@ -1,3 +1,5 @@
 
-
-pub mod activity_report;
\ No newline at end of file
+pub mod activity_report;
+pub mod lift_expired_bans;
+pub mod cleanup_images;
+pub mod thread_digest;
@@ -0,0 +1,57 @@
+use chrono::{Duration, Utc};
+use kboard::{
+    app::App,
+    models::{replies, thread_subscriptions, users},
+};
+use loco_rs::{boot::run_task, task, testing::prelude::*};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_thread_digest_emails_new_replies_once() {
+    let boot = boot_test::<App>().await.unwrap();
+    seed::<App>(&boot.app_context).await.unwrap();
+    let ctx = &boot.app_context;
+
+    let user1 = users::Model::find_by_pid(&ctx.db, "11111111-1111-1111-1111-111111111111").await.unwrap();
+    let user2 = users::Model::find_by_pid(&ctx.db, "22222222-2222-2222-2222-222222222222").await.unwrap();
+
+    // user1 follows thread 1 from before these replies, and user2 follows
+    // it by replying
+    thread_subscriptions::Entity::subscribe(&ctx.db, 1, user1.id).await.unwrap();
+    for body in ["Seagulls are the worst", "Pelicans are worse"] {
+        replies::Model::create(&ctx.db, body.to_string(), 1, user2.id, None, None).await.unwrap();
+    }
+    let subscription = thread_subscriptions::Entity::find_subscription(&ctx.db, 1, user2.id).await.unwrap();
+    assert!(subscription.is_some(), "Replying follows the thread");
+
+    let digest = "thread_digest".to_string();
+    run_task::<App>(ctx, Some(&digest), &task::Vars::default()).await.unwrap();
+
+    // user2's only news is their own replies
+    let deliveries = ctx.mailer.as_ref().unwrap().deliveries();
+    assert_eq!(deliveries.count, 1);
+    assert!(deliveries.messages[0].contains("user1@example.com"));
+
+    let user1 = users::Entity::find_by_id(user1.id).one(&ctx.db).await.unwrap().unwrap();
+    assert!(user1.last_digest_at.is_some());
+
+    // A digest that comes due with nothing new in it isn't sent
+    let mut active: users::ActiveModel = user1.into_active_model();
+    active.last_digest_at = Set(Some((Utc::now() - Duration::days(2)).into()));
+    let user1 = active.update(&ctx.db).await.unwrap();
+    run_task::<App>(ctx, Some(&digest), &task::Vars::default()).await.unwrap();
+    assert_eq!(ctx.mailer.as_ref().unwrap().deliveries().count, 1);
+
+    // A new reply waits for the next digest once one was just sent
+    replies::Model::create(&ctx.db, "A heron, actually".to_string(), 1, user2.id, None, None).await.unwrap();
+    run_task::<App>(ctx, Some(&digest), &task::Vars::default()).await.unwrap();
+    assert_eq!(ctx.mailer.as_ref().unwrap().deliveries().count, 1);
+
+    let mut active: users::ActiveModel = users::Entity::find_by_id(user1.id).one(&ctx.db).await.unwrap().unwrap().into_active_model();
+    active.last_digest_at = Set(Some((Utc::now() - Duration::days(2)).into()));
+    active.update(&ctx.db).await.unwrap();
+    run_task::<App>(ctx, Some(&digest), &task::Vars::default()).await.unwrap();
+    assert_eq!(ctx.mailer.as_ref().unwrap().deliveries().count, 2);
+}